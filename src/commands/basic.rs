@@ -0,0 +1,205 @@
+use std::fmt::Debug;
+
+use frclib_core::time::{Duration, Instant};
+
+use super::{Command, Requirements, SubsystemId};
+
+/// A command that runs a function once and finishes immediately.
+pub struct InstantCommand<F: FnMut()> {
+    func: F,
+    requirements: Requirements,
+}
+
+impl<F: FnMut()> InstantCommand<F> {
+    pub fn new(func: F, requirements: &[SubsystemId]) -> Self {
+        Self {
+            func,
+            requirements: requirements.iter().copied().collect(),
+        }
+    }
+}
+
+impl<F: FnMut()> Command for InstantCommand<F> {
+    fn initialize(&mut self) {
+        (self.func)();
+    }
+    fn is_finished(&self) -> bool {
+        true
+    }
+    fn requirements(&self) -> Requirements {
+        self.requirements.clone()
+    }
+}
+
+/// A command that runs a function every cycle and never finishes on its own.
+pub struct RunCommand<F: FnMut()> {
+    func: F,
+    requirements: Requirements,
+}
+
+impl<F: FnMut()> RunCommand<F> {
+    pub fn new(func: F, requirements: &[SubsystemId]) -> Self {
+        Self {
+            func,
+            requirements: requirements.iter().copied().collect(),
+        }
+    }
+}
+
+impl<F: FnMut()> Command for RunCommand<F> {
+    fn execute(&mut self) {
+        (self.func)();
+    }
+    fn requirements(&self) -> Requirements {
+        self.requirements.clone()
+    }
+}
+
+/// A command built from a function for each stage of the command lifecycle.
+pub struct FunctionalCommand<I, E, D, F>
+where
+    I: FnMut(),
+    E: FnMut(),
+    D: FnMut(bool),
+    F: Fn() -> bool,
+{
+    on_init: I,
+    on_execute: E,
+    on_end: D,
+    is_finished: F,
+    requirements: Requirements,
+}
+
+impl<I, E, D, F> FunctionalCommand<I, E, D, F>
+where
+    I: FnMut(),
+    E: FnMut(),
+    D: FnMut(bool),
+    F: Fn() -> bool,
+{
+    pub fn new(
+        on_init: I,
+        on_execute: E,
+        on_end: D,
+        is_finished: F,
+        requirements: &[SubsystemId],
+    ) -> Self {
+        Self {
+            on_init,
+            on_execute,
+            on_end,
+            is_finished,
+            requirements: requirements.iter().copied().collect(),
+        }
+    }
+}
+
+impl<I, E, D, F> Command for FunctionalCommand<I, E, D, F>
+where
+    I: FnMut(),
+    E: FnMut(),
+    D: FnMut(bool),
+    F: Fn() -> bool,
+{
+    fn initialize(&mut self) {
+        (self.on_init)();
+    }
+    fn execute(&mut self) {
+        (self.on_execute)();
+    }
+    fn end(&mut self, interrupted: bool) {
+        (self.on_end)(interrupted);
+    }
+    fn is_finished(&self) -> bool {
+        (self.is_finished)()
+    }
+    fn requirements(&self) -> Requirements {
+        self.requirements.clone()
+    }
+}
+
+/// A command that does nothing and finishes after a set duration.
+#[derive(Debug, Clone, Copy)]
+pub struct WaitCommand {
+    duration: Duration,
+    start: Option<Instant>,
+}
+
+impl WaitCommand {
+    #[must_use]
+    pub const fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            start: None,
+        }
+    }
+}
+
+impl Command for WaitCommand {
+    fn initialize(&mut self) {
+        self.start = Some(Instant::now());
+    }
+    fn is_finished(&self) -> bool {
+        self.start
+            .is_some_and(|start| start.elapsed() >= self.duration)
+    }
+    fn runs_when_disabled(&self) -> bool {
+        true
+    }
+}
+
+/// A command that does nothing and finishes once a condition becomes true.
+pub struct WaitUntilCommand<F: Fn() -> bool> {
+    condition: F,
+}
+
+impl<F: Fn() -> bool> WaitUntilCommand<F> {
+    pub const fn new(condition: F) -> Self {
+        Self { condition }
+    }
+}
+
+impl<F: Fn() -> bool> Command for WaitUntilCommand<F> {
+    fn is_finished(&self) -> bool {
+        (self.condition)()
+    }
+    fn runs_when_disabled(&self) -> bool {
+        true
+    }
+}
+
+impl<F: FnMut()> Debug for InstantCommand<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstantCommand")
+            .field("requirements", &self.requirements)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: FnMut()> Debug for RunCommand<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunCommand")
+            .field("requirements", &self.requirements)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, E, D, F> Debug for FunctionalCommand<I, E, D, F>
+where
+    I: FnMut(),
+    E: FnMut(),
+    D: FnMut(bool),
+    F: Fn() -> bool,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionalCommand")
+            .field("requirements", &self.requirements)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: Fn() -> bool> Debug for WaitUntilCommand<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WaitUntilCommand").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,208 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use frclib_core::time::Duration;
+
+use super::{
+    CommandScheduler, ParallelCommandGroup, ParallelDeadlineGroup, ParallelRaceGroup, ProxyCommand,
+    RepeatCommand, SequentialCommandGroup, SubsystemId, WaitCommand, WaitUntilCommand,
+};
+
+/// The set of subsystems a command needs exclusive access to.
+pub type Requirements = HashSet<SubsystemId>;
+
+/// What happens when a command would be interrupted by another command
+/// that shares a requirement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InterruptionBehavior {
+    /// The running command is canceled and the incoming command is scheduled.
+    #[default]
+    CancelSelf,
+    /// The running command keeps running and the incoming command is not scheduled.
+    CancelIncoming,
+}
+
+/// A unit of robot behavior that is run by the [`CommandScheduler`].
+///
+/// The scheduler calls [`initialize`](Command::initialize) once when the command is scheduled,
+/// [`execute`](Command::execute) every cycle until [`is_finished`](Command::is_finished) returns true
+/// or the command is interrupted, and then [`end`](Command::end) once.
+pub trait Command {
+    /// Ran once when the command is scheduled.
+    fn initialize(&mut self) {}
+    /// Ran every cycle while the command is scheduled.
+    fn execute(&mut self) {}
+    /// Ran once when the command finishes or is interrupted.
+    fn end(&mut self, _interrupted: bool) {}
+    /// Checked every cycle after [`execute`](Command::execute),
+    /// returning true will end the command.
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    /// The subsystems this command requires, this is read once when the command is scheduled.
+    fn requirements(&self) -> Requirements {
+        Requirements::new()
+    }
+
+    /// If the command should keep running while the robot is disabled.
+    fn runs_when_disabled(&self) -> bool {
+        false
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        InterruptionBehavior::CancelSelf
+    }
+
+    /// The name of the command, used for logging.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl Command for Box<dyn Command> {
+    fn initialize(&mut self) {
+        self.as_mut().initialize();
+    }
+    fn execute(&mut self) {
+        self.as_mut().execute();
+    }
+    fn end(&mut self, interrupted: bool) {
+        self.as_mut().end(interrupted);
+    }
+    fn is_finished(&self) -> bool {
+        self.as_ref().is_finished()
+    }
+    fn requirements(&self) -> Requirements {
+        self.as_ref().requirements()
+    }
+    fn runs_when_disabled(&self) -> bool {
+        self.as_ref().runs_when_disabled()
+    }
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        self.as_ref().interruption_behavior()
+    }
+    fn name(&self) -> &str {
+        self.as_ref().name()
+    }
+}
+
+/// A shared handle to a [`Command`], this is what the [`CommandScheduler`] schedules.
+///
+/// Handles compare equal if they point to the same command,
+/// so the same handle can be scheduled, canceled and rescheduled.
+#[derive(Clone)]
+pub struct CommandHandle(pub(crate) Rc<RefCell<dyn Command>>);
+
+impl CommandHandle {
+    pub fn new(command: impl Command + 'static) -> Self {
+        Self(Rc::new(RefCell::new(command)))
+    }
+
+    /// Schedules the command if it can be scheduled.
+    pub fn schedule(&self) {
+        CommandScheduler::schedule(self);
+    }
+
+    /// Cancels the command if it is scheduled.
+    pub fn cancel(&self) {
+        CommandScheduler::cancel(self);
+    }
+
+    #[must_use]
+    pub fn is_scheduled(&self) -> bool {
+        CommandScheduler::is_scheduled(self)
+    }
+
+    /// Returns the name of the command,
+    /// or an empty string if the command is currently running.
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.0
+            .try_borrow()
+            .map(|command| command.name().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl<C: Command + 'static> From<C> for CommandHandle {
+    fn from(command: C) -> Self {
+        Self::new(command)
+    }
+}
+
+impl PartialEq for CommandHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for CommandHandle {}
+
+impl Debug for CommandHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CommandHandle").field(&self.name()).finish()
+    }
+}
+
+/// Convenience methods available on every [`Command`].
+pub trait CommandExt: Command + Sized + 'static {
+    /// Wraps the command in a [`CommandHandle`] so it can be scheduled.
+    fn into_handle(self) -> CommandHandle {
+        CommandHandle::new(self)
+    }
+
+    /// Schedules the command, returning the handle it was scheduled with.
+    fn schedule(self) -> CommandHandle {
+        let handle = self.into_handle();
+        handle.schedule();
+        handle
+    }
+
+    fn boxed(self) -> Box<dyn Command> {
+        Box::new(self)
+    }
+
+    /// Runs `next` after this command finishes.
+    fn and_then(self, next: impl Command + 'static) -> SequentialCommandGroup {
+        SequentialCommandGroup::new(vec![self.boxed(), next.boxed()])
+    }
+
+    /// Runs `other` at the same time, finishing when both have finished.
+    fn along_with(self, other: impl Command + 'static) -> ParallelCommandGroup {
+        ParallelCommandGroup::new(vec![self.boxed(), other.boxed()])
+    }
+
+    /// Runs `other` at the same time, finishing when either finishes.
+    fn race_with(self, other: impl Command + 'static) -> ParallelRaceGroup {
+        ParallelRaceGroup::new(vec![self.boxed(), other.boxed()])
+    }
+
+    /// Runs `other` at the same time, finishing when this command finishes.
+    fn deadline_with(self, other: impl Command + 'static) -> ParallelDeadlineGroup {
+        ParallelDeadlineGroup::new(self.boxed(), vec![other.boxed()])
+    }
+
+    /// Restarts this command every time it finishes.
+    fn repeatedly(self) -> RepeatCommand {
+        RepeatCommand::new(self.boxed())
+    }
+
+    /// Interrupts this command once the condition becomes true.
+    fn until(self, condition: impl Fn() -> bool + 'static) -> ParallelRaceGroup {
+        self.race_with(WaitUntilCommand::new(condition))
+    }
+
+    /// Interrupts this command if it has not finished after the timeout.
+    fn with_timeout(self, timeout: Duration) -> ParallelRaceGroup {
+        self.race_with(WaitCommand::new(timeout))
+    }
+
+    /// Schedules this command on its own when ran, see [`ProxyCommand`].
+    fn proxy(self) -> ProxyCommand {
+        ProxyCommand::new(self.into_handle())
+    }
+}
+
+impl<T: Command + Sized + 'static> CommandExt for T {}
@@ -0,0 +1,295 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+use super::{Command, CommandHandle, InterruptionBehavior, Requirements};
+
+/// Restarts a command every time it finishes, never finishing on its own.
+pub struct RepeatCommand {
+    command: Box<dyn Command>,
+    ended: bool,
+}
+
+impl RepeatCommand {
+    #[must_use]
+    pub fn new(command: Box<dyn Command>) -> Self {
+        Self {
+            command,
+            ended: true,
+        }
+    }
+}
+
+impl Command for RepeatCommand {
+    fn initialize(&mut self) {
+        self.ended = false;
+        self.command.initialize();
+    }
+
+    fn execute(&mut self) {
+        if self.ended {
+            self.ended = false;
+            self.command.initialize();
+        }
+        self.command.execute();
+        if self.command.is_finished() {
+            self.command.end(false);
+            self.ended = true;
+        }
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        if !self.ended {
+            self.command.end(interrupted);
+            self.ended = true;
+        }
+    }
+
+    fn requirements(&self) -> Requirements {
+        self.command.requirements()
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        self.command.runs_when_disabled()
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        self.command.interruption_behavior()
+    }
+}
+
+/// Runs one of two commands depending on a condition checked when this command is initialized.
+pub struct ConditionalCommand<F: Fn() -> bool> {
+    on_true: Box<dyn Command>,
+    on_false: Box<dyn Command>,
+    condition: F,
+    selected: bool,
+}
+
+impl<F: Fn() -> bool> ConditionalCommand<F> {
+    pub fn new(on_true: Box<dyn Command>, on_false: Box<dyn Command>, condition: F) -> Self {
+        Self {
+            on_true,
+            on_false,
+            condition,
+            selected: true,
+        }
+    }
+
+    fn selected(&mut self) -> &mut Box<dyn Command> {
+        if self.selected {
+            &mut self.on_true
+        } else {
+            &mut self.on_false
+        }
+    }
+}
+
+impl<F: Fn() -> bool> Command for ConditionalCommand<F> {
+    fn initialize(&mut self) {
+        self.selected = (self.condition)();
+        self.selected().initialize();
+    }
+
+    fn execute(&mut self) {
+        self.selected().execute();
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        self.selected().end(interrupted);
+    }
+
+    fn is_finished(&self) -> bool {
+        if self.selected {
+            self.on_true.is_finished()
+        } else {
+            self.on_false.is_finished()
+        }
+    }
+
+    fn requirements(&self) -> Requirements {
+        let mut requirements = self.on_true.requirements();
+        requirements.extend(self.on_false.requirements());
+        requirements
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        self.on_true.runs_when_disabled() && self.on_false.runs_when_disabled()
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        if self.on_true.interruption_behavior() == InterruptionBehavior::CancelSelf
+            || self.on_false.interruption_behavior() == InterruptionBehavior::CancelSelf
+        {
+            InterruptionBehavior::CancelSelf
+        } else {
+            InterruptionBehavior::CancelIncoming
+        }
+    }
+}
+
+/// Runs one of many commands chosen by a key when this command is initialized.
+///
+/// If the selector returns a key with no command, this command finishes immediately.
+pub struct SelectCommand<K: Eq + Hash + Debug, F: Fn() -> K, S: BuildHasher = RandomState> {
+    commands: HashMap<K, Box<dyn Command>, S>,
+    selector: F,
+    selected: Option<K>,
+}
+
+impl<K: Eq + Hash + Debug, F: Fn() -> K, S: BuildHasher> SelectCommand<K, F, S> {
+    pub const fn new(commands: HashMap<K, Box<dyn Command>, S>, selector: F) -> Self {
+        Self {
+            commands,
+            selector,
+            selected: None,
+        }
+    }
+
+    fn selected(&mut self) -> Option<&mut Box<dyn Command>> {
+        self.selected
+            .as_ref()
+            .and_then(|key| self.commands.get_mut(key))
+    }
+}
+
+impl<K: Eq + Hash + Debug, F: Fn() -> K, S: BuildHasher> Command for SelectCommand<K, F, S> {
+    fn initialize(&mut self) {
+        let key = (self.selector)();
+        if !self.commands.contains_key(&key) {
+            tracing::warn!("SelectCommand selector returned {key:?} which has no command");
+        }
+        self.selected = Some(key);
+        if let Some(command) = self.selected() {
+            command.initialize();
+        }
+    }
+
+    fn execute(&mut self) {
+        if let Some(command) = self.selected() {
+            command.execute();
+        }
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        if let Some(command) = self.selected() {
+            command.end(interrupted);
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.selected
+            .as_ref()
+            .and_then(|key| self.commands.get(key))
+            .is_none_or(Command::is_finished)
+    }
+
+    fn requirements(&self) -> Requirements {
+        self.commands
+            .values()
+            .flat_map(Command::requirements)
+            .collect()
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        self.commands
+            .values()
+            .all(Command::runs_when_disabled)
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        if self
+            .commands
+            .values()
+            .any(|command| command.interruption_behavior() == InterruptionBehavior::CancelSelf)
+        {
+            InterruptionBehavior::CancelSelf
+        } else {
+            InterruptionBehavior::CancelIncoming
+        }
+    }
+}
+
+/// Schedules another command by its handle and finishes once that command is no longer scheduled.
+///
+/// The proxied command's requirements are not part of this command's requirements,
+/// so a group containing a proxy only reserves the subsystems while the proxied command runs.
+#[derive(Debug)]
+pub struct ProxyCommand {
+    handle: CommandHandle,
+}
+
+impl ProxyCommand {
+    #[must_use]
+    pub const fn new(handle: CommandHandle) -> Self {
+        Self { handle }
+    }
+}
+
+impl Command for ProxyCommand {
+    fn initialize(&mut self) {
+        self.handle.schedule();
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        if interrupted {
+            self.handle.cancel();
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.handle.is_scheduled()
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        true
+    }
+}
+
+/// Runs `on_true` if the condition is true when initialized, otherwise `on_false`,
+/// see [`ConditionalCommand`].
+pub fn either<F: Fn() -> bool>(
+    on_true: Box<dyn Command>,
+    on_false: Box<dyn Command>,
+    condition: F,
+) -> ConditionalCommand<F> {
+    ConditionalCommand::new(on_true, on_false, condition)
+}
+
+/// Runs the command matching the key returned by the selector when initialized,
+/// see [`SelectCommand`].
+pub const fn select<K: Eq + Hash + Debug, F: Fn() -> K, S: BuildHasher>(
+    commands: HashMap<K, Box<dyn Command>, S>,
+    selector: F,
+) -> SelectCommand<K, F, S> {
+    SelectCommand::new(commands, selector)
+}
+
+impl Debug for RepeatCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RepeatCommand")
+            .field("command", &self.command.name())
+            .field("ended", &self.ended)
+            .finish()
+    }
+}
+
+impl<F: Fn() -> bool> Debug for ConditionalCommand<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConditionalCommand")
+            .field("on_true", &self.on_true.name())
+            .field("on_false", &self.on_false.name())
+            .field("selected", &self.selected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Eq + Hash + Debug, F: Fn() -> K, S: BuildHasher> Debug for SelectCommand<K, F, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SelectCommand")
+            .field("commands", &self.commands.keys().collect::<Vec<_>>())
+            .field("selected", &self.selected)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt::Debug;
+
+use super::{Command, InterruptionBehavior, Requirements};
+
+/// Merges the requirements of every command in a group.
+fn merged_requirements(commands: &[Box<dyn Command>]) -> Requirements {
+    commands
+        .iter()
+        .flat_map(Command::requirements)
+        .collect()
+}
+
+/// Asserts no two commands in a parallel group share a requirement.
+fn assert_disjoint(commands: &[Box<dyn Command>]) {
+    let mut seen = Requirements::new();
+    for command in commands {
+        for requirement in command.requirements() {
+            assert!(
+                seen.insert(requirement),
+                "Commands in a parallel group cannot share requirements ({})",
+                command.name()
+            );
+        }
+    }
+}
+
+/// A group only runs when disabled if every command in it can.
+fn group_runs_when_disabled(commands: &[Box<dyn Command>]) -> bool {
+    commands.iter().all(Command::runs_when_disabled)
+}
+
+/// A group can only be interrupted if any command in it can be.
+fn group_interruption_behavior(commands: &[Box<dyn Command>]) -> InterruptionBehavior {
+    if commands
+        .iter()
+        .any(|command| command.interruption_behavior() == InterruptionBehavior::CancelSelf)
+    {
+        InterruptionBehavior::CancelSelf
+    } else {
+        InterruptionBehavior::CancelIncoming
+    }
+}
+
+/// Runs a list of commands one after another, finishing when the last one finishes.
+pub struct SequentialCommandGroup {
+    commands: Vec<Box<dyn Command>>,
+    current: usize,
+}
+
+impl SequentialCommandGroup {
+    #[must_use]
+    pub fn new(commands: Vec<Box<dyn Command>>) -> Self {
+        let current = commands.len();
+        Self { commands, current }
+    }
+}
+
+impl Command for SequentialCommandGroup {
+    fn initialize(&mut self) {
+        self.current = 0;
+        if let Some(command) = self.commands.first_mut() {
+            command.initialize();
+        }
+    }
+
+    fn execute(&mut self) {
+        let Some(command) = self.commands.get_mut(self.current) else {
+            return;
+        };
+        command.execute();
+        if command.is_finished() {
+            command.end(false);
+            self.current += 1;
+            if let Some(next) = self.commands.get_mut(self.current) {
+                next.initialize();
+            }
+        }
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        if interrupted {
+            if let Some(command) = self.commands.get_mut(self.current) {
+                command.end(true);
+            }
+        }
+        self.current = self.commands.len();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current >= self.commands.len()
+    }
+
+    fn requirements(&self) -> Requirements {
+        merged_requirements(&self.commands)
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        group_runs_when_disabled(&self.commands)
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        group_interruption_behavior(&self.commands)
+    }
+}
+
+/// Runs a set of commands at the same time, finishing when every command has finished.
+pub struct ParallelCommandGroup {
+    commands: Vec<Box<dyn Command>>,
+    running: Vec<bool>,
+}
+
+impl ParallelCommandGroup {
+    /// # Panics
+    /// Panics if any two commands share a requirement.
+    #[must_use]
+    pub fn new(commands: Vec<Box<dyn Command>>) -> Self {
+        assert_disjoint(&commands);
+        let running = vec![false; commands.len()];
+        Self { commands, running }
+    }
+}
+
+impl Command for ParallelCommandGroup {
+    fn initialize(&mut self) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            command.initialize();
+            *running = true;
+        }
+    }
+
+    fn execute(&mut self) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            if !*running {
+                continue;
+            }
+            command.execute();
+            if command.is_finished() {
+                command.end(false);
+                *running = false;
+            }
+        }
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            if interrupted && *running {
+                command.end(true);
+            }
+            *running = false;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.running.contains(&true)
+    }
+
+    fn requirements(&self) -> Requirements {
+        merged_requirements(&self.commands)
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        group_runs_when_disabled(&self.commands)
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        group_interruption_behavior(&self.commands)
+    }
+}
+
+/// Runs a set of commands at the same time, finishing when any command finishes
+/// and interrupting the rest.
+pub struct ParallelRaceGroup {
+    commands: Vec<Box<dyn Command>>,
+    finished: bool,
+}
+
+impl ParallelRaceGroup {
+    /// # Panics
+    /// Panics if any two commands share a requirement.
+    #[must_use]
+    pub fn new(commands: Vec<Box<dyn Command>>) -> Self {
+        assert_disjoint(&commands);
+        Self {
+            commands,
+            finished: true,
+        }
+    }
+}
+
+impl Command for ParallelRaceGroup {
+    fn initialize(&mut self) {
+        self.finished = false;
+        for command in &mut self.commands {
+            command.initialize();
+        }
+    }
+
+    fn execute(&mut self) {
+        for command in &mut self.commands {
+            command.execute();
+            if command.is_finished() {
+                self.finished = true;
+            }
+        }
+    }
+
+    fn end(&mut self, interrupted: bool) {
+        for command in &mut self.commands {
+            let finished = !interrupted && command.is_finished();
+            command.end(!finished);
+        }
+        self.finished = true;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn requirements(&self) -> Requirements {
+        merged_requirements(&self.commands)
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        group_runs_when_disabled(&self.commands)
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        group_interruption_behavior(&self.commands)
+    }
+}
+
+/// Runs a set of commands at the same time, finishing when the deadline command finishes
+/// and interrupting any other command still running.
+pub struct ParallelDeadlineGroup {
+    /// The deadline is always the first command.
+    commands: Vec<Box<dyn Command>>,
+    running: Vec<bool>,
+}
+
+impl ParallelDeadlineGroup {
+    /// # Panics
+    /// Panics if any two commands share a requirement.
+    #[must_use]
+    pub fn new(deadline: Box<dyn Command>, others: Vec<Box<dyn Command>>) -> Self {
+        let mut commands = Vec::with_capacity(others.len() + 1);
+        commands.push(deadline);
+        commands.extend(others);
+        assert_disjoint(&commands);
+        let running = vec![false; commands.len()];
+        Self { commands, running }
+    }
+}
+
+impl Command for ParallelDeadlineGroup {
+    fn initialize(&mut self) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            command.initialize();
+            *running = true;
+        }
+    }
+
+    fn execute(&mut self) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            if !*running {
+                continue;
+            }
+            command.execute();
+            if command.is_finished() {
+                command.end(false);
+                *running = false;
+            }
+        }
+    }
+
+    fn end(&mut self, _interrupted: bool) {
+        for (command, running) in self.commands.iter_mut().zip(&mut self.running) {
+            if *running {
+                command.end(true);
+            }
+            *running = false;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.running.first().copied().unwrap_or(false)
+    }
+
+    fn requirements(&self) -> Requirements {
+        merged_requirements(&self.commands)
+    }
+
+    fn runs_when_disabled(&self) -> bool {
+        group_runs_when_disabled(&self.commands)
+    }
+
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        group_interruption_behavior(&self.commands)
+    }
+}
+
+/// Runs the commands one after another, see [`SequentialCommandGroup`].
+#[must_use]
+pub fn sequence(commands: Vec<Box<dyn Command>>) -> SequentialCommandGroup {
+    SequentialCommandGroup::new(commands)
+}
+
+/// Runs the commands at the same time until all finish, see [`ParallelCommandGroup`].
+#[must_use]
+pub fn parallel(commands: Vec<Box<dyn Command>>) -> ParallelCommandGroup {
+    ParallelCommandGroup::new(commands)
+}
+
+/// Runs the commands at the same time until any finishes, see [`ParallelRaceGroup`].
+#[must_use]
+pub fn race(commands: Vec<Box<dyn Command>>) -> ParallelRaceGroup {
+    ParallelRaceGroup::new(commands)
+}
+
+/// Runs the commands at the same time until the deadline finishes, see [`ParallelDeadlineGroup`].
+#[must_use]
+pub fn deadline(deadline: Box<dyn Command>, others: Vec<Box<dyn Command>>) -> ParallelDeadlineGroup {
+    ParallelDeadlineGroup::new(deadline, others)
+}
+
+fn command_names(commands: &[Box<dyn Command>]) -> Vec<&str> {
+    commands.iter().map(Command::name).collect()
+}
+
+impl Debug for SequentialCommandGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SequentialCommandGroup")
+            .field("commands", &command_names(&self.commands))
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl Debug for ParallelCommandGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelCommandGroup")
+            .field("commands", &command_names(&self.commands))
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+impl Debug for ParallelRaceGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelRaceGroup")
+            .field("commands", &command_names(&self.commands))
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+impl Debug for ParallelDeadlineGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelDeadlineGroup")
+            .field("commands", &command_names(&self.commands))
+            .field("running", &self.running)
+            .finish()
+    }
+}
@@ -0,0 +1,15 @@
+mod basic;
+mod command;
+mod decorators;
+mod groups;
+mod scheduler;
+mod subsystem;
+mod trigger;
+
+pub use basic::*;
+pub use command::*;
+pub use decorators::*;
+pub use groups::*;
+pub use scheduler::CommandScheduler;
+pub use subsystem::*;
+pub use trigger::Trigger;
@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use linkme::distributed_slice;
+
+use super::{Command, CommandHandle, InterruptionBehavior, Subsystem, SubsystemId, SubsystemRef};
+use crate::driver_station::DriverStation;
+use crate::vendor::performers::{stages::PRE_USER, Performer};
+
+#[derive(Default)]
+struct SchedulerState {
+    subsystems: Vec<Rc<RefCell<dyn Subsystem>>>,
+    default_commands: HashMap<SubsystemId, CommandHandle>,
+    scheduled: Vec<CommandHandle>,
+    requirements: HashMap<SubsystemId, CommandHandle>,
+    /// While the scheduler is running commands, schedules and cancels
+    /// are deferred until every command has been executed.
+    in_run_loop: bool,
+    to_schedule: Vec<CommandHandle>,
+    to_cancel: Vec<CommandHandle>,
+}
+
+thread_local! {
+    static SCHEDULER: RefCell<SchedulerState> = RefCell::new(SchedulerState::default());
+}
+
+fn with_state<R>(func: impl FnOnce(&mut SchedulerState) -> R) -> R {
+    SCHEDULER.with(|state| func(&mut state.borrow_mut()))
+}
+
+#[distributed_slice(PRE_USER)]
+static COMMAND_SCHEDULER: Performer = Performer::new("CommandScheduler", true, |enabled| {
+    CommandScheduler::run(enabled);
+    Ok(())
+});
+
+/// Runs every scheduled [`Command`] and every registered [`Subsystem`] periodic.
+///
+/// The scheduler is ran automatically every cycle during the pre-user stage,
+/// so user code only has to register subsystems and schedule commands.
+///
+/// The scheduler is local to the main loop thread, commands and subsystems
+/// don't have to be thread-safe.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandScheduler;
+
+impl CommandScheduler {
+    /// Registers a subsystem so its periodic methods are ran every cycle.
+    pub fn register_subsystem<S: Subsystem + 'static>(subsystem: S) -> SubsystemRef<S> {
+        let subsystem = SubsystemRef::new(subsystem);
+        with_state(|state| state.subsystems.push(subsystem.as_dyn()));
+        subsystem
+    }
+
+    pub(crate) fn set_default_command(subsystem: SubsystemId, command: impl Command + 'static) {
+        if !command.requirements().contains(&subsystem) {
+            tracing::warn!(
+                "Default command {} does not require its subsystem",
+                command.name()
+            );
+        }
+        let old = with_state(|state| {
+            state
+                .default_commands
+                .insert(subsystem, CommandHandle::new(command))
+        });
+        if let Some(old) = old {
+            Self::cancel(&old);
+        }
+    }
+
+    /// Schedules a command, interrupting any scheduled commands that share a requirement.
+    ///
+    /// The command will not be scheduled if:
+    /// - it is already scheduled
+    /// - the robot is disabled and the command doesn't run when disabled
+    /// - a command sharing a requirement has [`InterruptionBehavior::CancelIncoming`]
+    pub fn schedule(handle: &CommandHandle) {
+        let deferred = with_state(|state| {
+            if state.in_run_loop {
+                state.to_schedule.push(handle.clone());
+            }
+            state.in_run_loop
+        });
+        if deferred || Self::is_scheduled(handle) {
+            return;
+        }
+
+        let (requirements, runs_when_disabled) = {
+            let command = handle.0.borrow();
+            (command.requirements(), command.runs_when_disabled())
+        };
+
+        if DriverStation::is_disabled() && !runs_when_disabled {
+            return;
+        }
+
+        let conflicts = with_state(|state| {
+            let mut conflicts: Vec<CommandHandle> = Vec::new();
+            for requirement in &requirements {
+                if let Some(holder) = state.requirements.get(requirement) {
+                    if !conflicts.contains(holder) {
+                        conflicts.push(holder.clone());
+                    }
+                }
+            }
+            conflicts
+        });
+
+        if conflicts.iter().any(|conflict| {
+            conflict.0.borrow().interruption_behavior() == InterruptionBehavior::CancelIncoming
+        }) {
+            return;
+        }
+        for conflict in &conflicts {
+            Self::cancel(conflict);
+        }
+
+        with_state(|state| {
+            state.scheduled.push(handle.clone());
+            for requirement in requirements {
+                let _ = state.requirements.insert(requirement, handle.clone());
+            }
+        });
+        handle.0.borrow_mut().initialize();
+    }
+
+    /// Cancels a command if it is scheduled, calling its end with `interrupted` set to true.
+    pub fn cancel(handle: &CommandHandle) {
+        let deferred = with_state(|state| {
+            if state.in_run_loop {
+                state.to_cancel.push(handle.clone());
+            }
+            state.in_run_loop
+        });
+        if !deferred && Self::remove(handle) {
+            handle.0.borrow_mut().end(true);
+        }
+    }
+
+    /// Cancels every scheduled command.
+    pub fn cancel_all() {
+        let scheduled = with_state(|state| state.scheduled.clone());
+        for handle in &scheduled {
+            Self::cancel(handle);
+        }
+    }
+
+    #[must_use]
+    pub fn is_scheduled(handle: &CommandHandle) -> bool {
+        with_state(|state| state.scheduled.contains(handle))
+    }
+
+    /// Returns the command currently holding the requirement on a subsystem.
+    #[must_use]
+    pub fn requiring(subsystem: SubsystemId) -> Option<CommandHandle> {
+        with_state(|state| state.requirements.get(&subsystem).cloned())
+    }
+
+    /// Removes a command from the scheduled set without ending it,
+    /// returns true if it was scheduled.
+    fn remove(handle: &CommandHandle) -> bool {
+        with_state(|state| {
+            let len = state.scheduled.len();
+            state.scheduled.retain(|scheduled| scheduled != handle);
+            state.requirements.retain(|_, holder| holder != handle);
+            len != state.scheduled.len()
+        })
+    }
+
+    /// Runs one iteration of the scheduler.
+    ///
+    /// This is called automatically every cycle, calling it manually will
+    /// run commands more than once per cycle.
+    pub fn run(enabled: bool) {
+        let subsystems = with_state(|state| state.subsystems.clone());
+        for subsystem in &subsystems {
+            let mut subsystem = subsystem.borrow_mut();
+            subsystem.periodic();
+            #[cfg(frc_sim)]
+            subsystem.sim_periodic();
+        }
+
+        let scheduled = with_state(|state| {
+            state.in_run_loop = true;
+            state.scheduled.clone()
+        });
+        for handle in &scheduled {
+            let mut command = handle.0.borrow_mut();
+            if !enabled && !command.runs_when_disabled() {
+                drop(command);
+                let _ = Self::remove(handle);
+                handle.0.borrow_mut().end(true);
+                continue;
+            }
+            command.execute();
+            if command.is_finished() {
+                drop(command);
+                let _ = Self::remove(handle);
+                handle.0.borrow_mut().end(false);
+            }
+        }
+
+        let (to_cancel, to_schedule) = with_state(|state| {
+            state.in_run_loop = false;
+            (
+                std::mem::take(&mut state.to_cancel),
+                std::mem::take(&mut state.to_schedule),
+            )
+        });
+        for handle in &to_cancel {
+            Self::cancel(handle);
+        }
+        for handle in &to_schedule {
+            Self::schedule(handle);
+        }
+
+        let defaults = with_state(|state| {
+            state
+                .default_commands
+                .iter()
+                .filter(|(subsystem, _)| !state.requirements.contains_key(subsystem))
+                .map(|(_, handle)| handle.clone())
+                .collect::<Vec<_>>()
+        });
+        for handle in &defaults {
+            Self::schedule(handle);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{Command, CommandScheduler};
+
+static NEXT_SUBSYSTEM_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A unique identifier for a registered [`Subsystem`], used for requirement tracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubsystemId(usize);
+
+impl SubsystemId {
+    pub(crate) fn next() -> Self {
+        Self(NEXT_SUBSYSTEM_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A robot mechanism that commands can require.
+///
+/// Only one command requiring a subsystem can be scheduled at a time,
+/// scheduling a second one will interrupt the first.
+pub trait Subsystem {
+    /// Ran every cycle by the [`CommandScheduler`] before any commands are executed.
+    fn periodic(&mut self) {}
+    /// Ran every cycle in simulation after [`Subsystem::periodic`].
+    fn sim_periodic(&mut self) {}
+
+    /// The name of the subsystem, used for logging.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// A shared handle to a [`Subsystem`] registered with the [`CommandScheduler`].
+///
+/// Cloning the handle is cheap, commands should hold a clone of the handle
+/// and declare its [`id`](SubsystemRef::id) in their requirements.
+pub struct SubsystemRef<S: Subsystem> {
+    id: SubsystemId,
+    inner: Rc<RefCell<S>>,
+}
+
+impl<S: Subsystem + 'static> SubsystemRef<S> {
+    pub(crate) fn new(subsystem: S) -> Self {
+        Self {
+            id: SubsystemId::next(),
+            inner: Rc::new(RefCell::new(subsystem)),
+        }
+    }
+
+    pub(crate) fn as_dyn(&self) -> Rc<RefCell<dyn Subsystem>> {
+        self.inner.clone()
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> SubsystemId {
+        self.id
+    }
+
+    /// # Panics
+    /// Panics if the subsystem is currently mutably borrowed.
+    #[must_use]
+    pub fn borrow(&self) -> Ref<'_, S> {
+        self.inner.borrow()
+    }
+
+    /// # Panics
+    /// Panics if the subsystem is currently borrowed.
+    #[must_use]
+    pub fn borrow_mut(&self) -> RefMut<'_, S> {
+        self.inner.borrow_mut()
+    }
+
+    /// Sets the command that is scheduled whenever no other command requires this subsystem.
+    ///
+    /// The command should require this subsystem, otherwise it will be rescheduled every cycle.
+    pub fn set_default_command(&self, command: impl Command + 'static) {
+        CommandScheduler::set_default_command(self.id, command);
+    }
+}
+
+impl<S: Subsystem> Clone for SubsystemRef<S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S: Subsystem> Debug for SubsystemRef<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubsystemRef")
+            .field("id", &self.id)
+            .field("name", &self.inner.try_borrow().map(|s| s.name().to_owned()).ok())
+            .finish()
+    }
+}
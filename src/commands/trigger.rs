@@ -0,0 +1,155 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use frclib_core::units::time::Time;
+
+use super::CommandHandle;
+use crate::event::EventLoop;
+use crate::math::filter::debouncer::{DebounceType, Debouncer};
+
+/// Binds a boolean condition to commands, scheduling and canceling them on the edges of the condition.
+///
+/// The condition is checked every time the trigger's [`EventLoop`] is polled,
+/// which for the default loop is once per cycle.
+#[derive(Clone)]
+pub struct Trigger {
+    event_loop: EventLoop,
+    condition: Rc<dyn Fn() -> bool>,
+}
+
+impl Trigger {
+    /// Creates a trigger polled by the [default event loop](EventLoop::default_loop).
+    pub fn new(condition: impl Fn() -> bool + 'static) -> Self {
+        Self::with_loop(EventLoop::default_loop(), condition)
+    }
+
+    /// Creates a trigger polled by a specific event loop.
+    pub fn with_loop(event_loop: EventLoop, condition: impl Fn() -> bool + 'static) -> Self {
+        Self {
+            event_loop,
+            condition: Rc::new(condition),
+        }
+    }
+
+    /// Returns the current value of the condition.
+    #[must_use]
+    pub fn get(&self) -> bool {
+        (self.condition)()
+    }
+
+    /// Binds an action that is given the previous and current value of the condition every poll.
+    ///
+    /// The previous value starts as the value of the condition when the binding is made.
+    fn bind_edges(&self, mut action: impl FnMut(bool, bool) + 'static) {
+        let condition = Rc::clone(&self.condition);
+        let mut previous = condition();
+        self.event_loop.bind(move || {
+            let current = condition();
+            action(previous, current);
+            previous = current;
+        });
+    }
+
+    /// Schedules the command when the condition changes to true.
+    pub fn on_true(&self, command: impl Into<CommandHandle>) -> &Self {
+        let command = command.into();
+        self.bind_edges(move |previous, current| {
+            if !previous && current {
+                command.schedule();
+            }
+        });
+        self
+    }
+
+    /// Schedules the command when the condition changes to false.
+    pub fn on_false(&self, command: impl Into<CommandHandle>) -> &Self {
+        let command = command.into();
+        self.bind_edges(move |previous, current| {
+            if previous && !current {
+                command.schedule();
+            }
+        });
+        self
+    }
+
+    /// Schedules the command when the condition changes to true
+    /// and cancels it when the condition changes to false.
+    pub fn while_true(&self, command: impl Into<CommandHandle>) -> &Self {
+        let command = command.into();
+        self.bind_edges(move |previous, current| {
+            if !previous && current {
+                command.schedule();
+            } else if previous && !current {
+                command.cancel();
+            }
+        });
+        self
+    }
+
+    /// Toggles the command when the condition changes to true,
+    /// canceling it if it is scheduled and scheduling it otherwise.
+    pub fn toggle_on_true(&self, command: impl Into<CommandHandle>) -> &Self {
+        let command = command.into();
+        self.bind_edges(move |previous, current| {
+            if !previous && current {
+                if command.is_scheduled() {
+                    command.cancel();
+                } else {
+                    command.schedule();
+                }
+            }
+        });
+        self
+    }
+
+    /// A trigger that is true when both conditions are true, polled by this trigger's loop.
+    #[must_use]
+    pub fn and(&self, other: impl Into<Self>) -> Self {
+        let (this, other) = (Rc::clone(&self.condition), other.into().condition);
+        Self::with_loop(self.event_loop.clone(), move || this() && other())
+    }
+
+    /// A trigger that is true when either condition is true, polled by this trigger's loop.
+    #[must_use]
+    pub fn or(&self, other: impl Into<Self>) -> Self {
+        let (this, other) = (Rc::clone(&self.condition), other.into().condition);
+        Self::with_loop(self.event_loop.clone(), move || this() || other())
+    }
+
+    /// A trigger that is true when this condition is false.
+    #[must_use]
+    pub fn negate(&self) -> Self {
+        let this = Rc::clone(&self.condition);
+        Self::with_loop(self.event_loop.clone(), move || !this())
+    }
+
+    /// A trigger that only changes once this condition has held its new value
+    /// for the debounce time, see [`Debouncer`].
+    #[must_use]
+    pub fn debounce(&self, debounce_time: impl Time, debounce_type: DebounceType) -> Self {
+        let this = Rc::clone(&self.condition);
+        let debouncer = RefCell::new(Debouncer::new(
+            debounce_time,
+            debounce_type,
+            debounce_type == DebounceType::Falling,
+        ));
+        Self::with_loop(self.event_loop.clone(), move || {
+            debouncer.borrow_mut().calculate(this())
+        })
+    }
+}
+
+impl<F: Fn() -> bool + 'static> From<F> for Trigger {
+    fn from(condition: F) -> Self {
+        Self::new(condition)
+    }
+}
+
+impl Debug for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trigger")
+            .field("event_loop", &self.event_loop)
+            .finish_non_exhaustive()
+    }
+}
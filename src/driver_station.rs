@@ -0,0 +1,284 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use frclib_core::hal::get_hal;
+use frclib_core::hal::rt::station_interface::{EnabledState, Mode, StationData};
+use frclib_core::time::uptime;
+use parking_lot::RwLock;
+
+use crate::robots::RobotMode;
+use crate::telemetry::log;
+
+/// The alliance the robot is assigned to for the current match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alliance {
+    Red,
+    Blue,
+}
+
+/// Match information that is not part of the HAL [`StationData`].
+///
+/// The current [`StationInterfaceDriver`](frclib_core::hal::rt::station_interface::StationInterfaceDriver)
+/// does not report these values, so outside of sim they stay at their defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchInfo {
+    pub alliance: Option<Alliance>,
+    /// The driver station position, 1 through 3.
+    pub station_number: Option<u8>,
+    pub match_number: u16,
+}
+
+#[derive(Debug, Default)]
+struct DriverStationState {
+    station: StationData,
+    match_info: MatchInfo,
+    /// While the uptime is before this the robot is reported as disabled no matter what the HAL reports.
+    disabled_until: Option<Duration>,
+    /// When set, replaces the data reported by the HAL on every refresh.
+    #[cfg(frc_sim)]
+    sim_station: Option<StationData>,
+}
+
+static STATE: LazyLock<RwLock<DriverStationState>> =
+    LazyLock::new(|| RwLock::new(DriverStationState::default()));
+
+/// Disables an enabled state, an e-stop stays an e-stop.
+const fn disabled(enabled_state: EnabledState) -> EnabledState {
+    match enabled_state {
+        EnabledState::EStopped => EnabledState::EStopped,
+        EnabledState::Enabled | EnabledState::Disabled => EnabledState::Disabled,
+    }
+}
+
+/// A snapshot of the driver station control word,
+/// refreshed once at the start of every iteration of the main loop.
+#[derive(Debug, Clone, Copy)]
+pub struct DriverStation;
+
+impl DriverStation {
+    /// Pulls the latest station data from the HAL and logs it to telemetry.
+    pub(crate) fn refresh() {
+        let hal_station = get_hal().ok().map(|hal| {
+            let api = hal.station_interface_api();
+            api.refresh();
+            api.get_station_data()
+        });
+
+        let (station, match_info) = {
+            let mut state = STATE.write();
+            #[cfg(frc_sim)]
+            let hal_station = state.sim_station.or(hal_station);
+            if let Some(station) = hal_station {
+                state.station = station;
+            }
+            if state.disabled_until.is_some_and(|until| uptime() < until) {
+                state.station.enabled_state = disabled(state.station.enabled_state);
+            } else {
+                state.disabled_until = None;
+            }
+            (state.station, state.match_info)
+        };
+
+        log("/DriverStation/Enabled", station.enabled_state == EnabledState::Enabled);
+        log("/DriverStation/EStop", station.enabled_state == EnabledState::EStopped);
+        log("/DriverStation/Autonomous", station.mode == Mode::Auto);
+        log("/DriverStation/Test", station.mode == Mode::Test);
+        log("/DriverStation/DSAttached", station.station_attached);
+        log("/DriverStation/FMSAttached", station.fms_attached);
+        log("/DriverStation/MatchNumber", match_info.match_number);
+        log(
+            "/DriverStation/Alliance",
+            match match_info.alliance {
+                Some(Alliance::Red) => "Red",
+                Some(Alliance::Blue) => "Blue",
+                None => "",
+            },
+        );
+    }
+
+    /// Reports the robot as disabled for at least the duration, even if the driver station has it enabled.
+    pub(crate) fn force_disabled_for(duration: Duration) {
+        let until = uptime() + duration;
+        let mut state = STATE.write();
+        state.disabled_until = Some(state.disabled_until.map_or(until, |current| current.max(until)));
+        state.station.enabled_state = disabled(state.station.enabled_state);
+    }
+
+    /// Returns the latest station data.
+    #[must_use]
+    pub fn station_data() -> StationData {
+        STATE.read().station
+    }
+
+    /// Returns the latest match info.
+    #[must_use]
+    pub fn match_info() -> MatchInfo {
+        STATE.read().match_info
+    }
+
+    /// Returns the [`RobotMode`] the latest station data commands.
+    #[must_use]
+    pub fn robot_mode() -> RobotMode {
+        RobotMode::from_station_data(&Self::station_data())
+    }
+
+    #[must_use]
+    pub fn is_enabled() -> bool {
+        Self::station_data().enabled_state == EnabledState::Enabled
+    }
+
+    #[must_use]
+    pub fn is_disabled() -> bool {
+        !Self::is_enabled()
+    }
+
+    #[must_use]
+    pub fn is_estopped() -> bool {
+        Self::station_data().enabled_state == EnabledState::EStopped
+    }
+
+    #[must_use]
+    pub fn is_autonomous() -> bool {
+        Self::station_data().mode == Mode::Auto
+    }
+
+    #[must_use]
+    pub fn is_teleop() -> bool {
+        Self::station_data().mode == Mode::Teleop
+    }
+
+    #[must_use]
+    pub fn is_test() -> bool {
+        Self::station_data().mode == Mode::Test
+    }
+
+    #[must_use]
+    pub fn is_ds_attached() -> bool {
+        Self::station_data().station_attached
+    }
+
+    #[must_use]
+    pub fn is_fms_attached() -> bool {
+        Self::station_data().fms_attached
+    }
+
+    #[must_use]
+    pub fn alliance() -> Option<Alliance> {
+        Self::match_info().alliance
+    }
+
+    #[must_use]
+    pub fn station_number() -> Option<u8> {
+        Self::match_info().station_number
+    }
+
+    #[must_use]
+    pub fn match_number() -> u16 {
+        Self::match_info().match_number
+    }
+}
+
+/// Overrides for the driver station state in simulation.
+///
+/// Once any station value is set the HAL station data is ignored
+/// until [`reset`] is called.
+/// Changes are visible immediately, not only after the next loop iteration.
+#[cfg(frc_sim)]
+pub mod sim {
+    use frclib_core::hal::rt::station_interface::{EnabledState, Mode, StationData};
+
+    use super::{Alliance, MatchInfo, STATE};
+    use crate::robots::RobotMode;
+
+    fn modify_station(func: impl FnOnce(&mut StationData)) {
+        let mut state = STATE.write();
+        let mut station = state.sim_station.unwrap_or(state.station);
+        func(&mut station);
+        state.sim_station = Some(station);
+        state.station = station;
+    }
+
+    pub fn set_enabled(enabled: bool) {
+        modify_station(|station| {
+            if station.enabled_state != EnabledState::EStopped {
+                station.enabled_state = if enabled {
+                    EnabledState::Enabled
+                } else {
+                    EnabledState::Disabled
+                };
+            }
+        });
+    }
+
+    /// E-stopping can only be undone by clearing it or calling [`reset`].
+    pub fn set_estopped(estopped: bool) {
+        modify_station(|station| {
+            station.enabled_state = if estopped {
+                EnabledState::EStopped
+            } else {
+                EnabledState::Disabled
+            };
+        });
+    }
+
+    pub fn set_mode(mode: Mode) {
+        modify_station(|station| station.mode = mode);
+    }
+
+    /// Sets both the enabled state and mode to match the given [`RobotMode`].
+    pub fn set_robot_mode(mode: RobotMode) {
+        match mode {
+            RobotMode::Disabled => set_enabled(false),
+            RobotMode::Teleop => {
+                set_mode(Mode::Teleop);
+                set_enabled(true);
+            }
+            RobotMode::Autonomous => {
+                set_mode(Mode::Auto);
+                set_enabled(true);
+            }
+            RobotMode::Test => {
+                set_mode(Mode::Test);
+                set_enabled(true);
+            }
+        }
+    }
+
+    pub fn set_ds_attached(attached: bool) {
+        modify_station(|station| station.station_attached = attached);
+    }
+
+    pub fn set_fms_attached(attached: bool) {
+        modify_station(|station| station.fms_attached = attached);
+    }
+
+    pub fn set_alliance(alliance: Option<Alliance>) {
+        STATE.write().match_info.alliance = alliance;
+    }
+
+    pub fn set_station_number(station_number: Option<u8>) {
+        STATE.write().match_info.station_number = station_number;
+    }
+
+    pub fn set_match_number(match_number: u16) {
+        STATE.write().match_info.match_number = match_number;
+    }
+
+    /// Replaces the whole control word at once.
+    pub fn set_station_data(station: StationData) {
+        modify_station(|current| *current = station);
+    }
+
+    pub fn set_match_info(match_info: MatchInfo) {
+        STATE.write().match_info = match_info;
+    }
+
+    /// Clears every override, the next refresh will use the HAL station data again.
+    pub fn reset() {
+        let mut state = STATE.write();
+        state.sim_station = None;
+        state.station = StationData::default();
+        state.match_info = super::MatchInfo::default();
+        state.disabled_until = None;
+    }
+}
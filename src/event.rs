@@ -0,0 +1,69 @@
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+use std::rc::Rc;
+
+type Binding = Box<dyn FnMut()>;
+
+#[derive(Default)]
+struct EventLoopInner {
+    bindings: RefCell<Vec<Binding>>,
+    /// Incremented every time the loop is cleared,
+    /// lets [`EventLoop::poll`] notice a clear made by one of its own bindings.
+    generation: Cell<u64>,
+}
+
+thread_local! {
+    static DEFAULT_LOOP: EventLoop = EventLoop::default();
+}
+
+/// A collection of actions that are ran every time the loop is polled.
+///
+/// The [default loop](EventLoop::default_loop) is polled once per cycle by the robot runtime
+/// before the pre-user stage, so bindings made to it run before the [`CommandScheduler`](crate::commands::CommandScheduler).
+///
+/// Event loops are cheap to clone, clones share the same bindings.
+#[derive(Clone, Default)]
+pub struct EventLoop(Rc<EventLoopInner>);
+
+impl EventLoop {
+    /// Returns the loop polled by the robot runtime every cycle.
+    #[must_use]
+    pub fn default_loop() -> Self {
+        DEFAULT_LOOP.with(Clone::clone)
+    }
+
+    /// Adds an action to be ran every time the loop is polled.
+    ///
+    /// Actions bound while the loop is being polled will first run on the next poll.
+    pub fn bind(&self, action: impl FnMut() + 'static) {
+        self.0.bindings.borrow_mut().push(Box::new(action));
+    }
+
+    /// Runs every bound action in the order they were bound.
+    pub fn poll(&self) {
+        let generation = self.0.generation.get();
+        let mut bindings = std::mem::take(&mut *self.0.bindings.borrow_mut());
+        for binding in &mut bindings {
+            binding();
+        }
+        if self.0.generation.get() == generation {
+            let mut current = self.0.bindings.borrow_mut();
+            let added = std::mem::replace(&mut *current, bindings);
+            current.extend(added);
+        }
+    }
+
+    /// Removes every bound action.
+    pub fn clear(&self) {
+        self.0.bindings.borrow_mut().clear();
+        self.0.generation.set(self.0.generation.get().wrapping_add(1));
+    }
+}
+
+impl Debug for EventLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventLoop")
+            .field("bindings", &self.0.bindings.try_borrow().map_or(0, |bindings| bindings.len()))
+            .finish()
+    }
+}
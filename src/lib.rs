@@ -42,15 +42,21 @@
 // use frclib_core::hal;
 // use robots::{RobotCore, RobotCoreImpl, UserRobot};
 
+pub mod commands;
+pub mod driver_station;
+pub mod event;
 pub mod math;
 pub mod robots;
 #[macro_use]
 pub mod macros;
 pub mod prelude;
+pub mod preferences;
+pub mod replay;
 pub mod telemetry;
 #[cfg(feature = "vendor")]
 pub mod vendor;
 pub mod runtime;
+pub mod watchdog;
 pub mod io;
 
 pub use frclib_core::units;
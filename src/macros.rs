@@ -46,4 +46,26 @@ macro_rules! deploy_dir {
     () => {
         env!("FRC_DEPLOY_DIR")
     };
-}
\ No newline at end of file
+}
+/// Implements [`TelemetryTree`](crate::telemetry::TelemetryTree) for a struct
+/// by logging each listed field under a child namespace named after it.
+///
+/// ```ignore
+/// struct ModuleState { velocity: f64, angle: Rotation2d }
+/// telemetry_tree!(ModuleState { velocity, angle });
+/// ```
+#[macro_export]
+macro_rules! telemetry_tree {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::telemetry::TelemetryTree for $type {
+            fn log_tree(&self, namespace: &$crate::telemetry::TelemetryNamespace) {
+                $(
+                    $crate::telemetry::TelemetryTree::log_tree(
+                        &self.$field,
+                        &namespace.child(stringify!($field)),
+                    );
+                )*
+            }
+        }
+    };
+}
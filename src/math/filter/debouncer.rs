@@ -20,14 +20,14 @@ impl Debouncer {
     pub fn new(debounce_time: impl Time, debounce_type: DebounceType, base_value: bool) -> Self {
         Self {
             debounce_time: debounce_time.into(),
-            previous_time: Second::new(0.0),
+            previous_time: now(),
             debounce_type,
             base_value,
         }
     }
 
     pub fn reset_timer(&mut self) {
-        self.previous_time = Second::new(0.0);
+        self.previous_time = now();
     }
 
     pub fn calculate(&mut self, input: bool) -> bool {
@@ -51,6 +51,10 @@ impl Debouncer {
     }
 
     fn has_elapsed(&self) -> bool {
-        Second::new(0.0) - self.previous_time >= self.debounce_time
+        now() - self.previous_time >= self.debounce_time
     }
 }
+
+fn now() -> Second {
+    Second::from(frclib_core::time::uptime())
+}
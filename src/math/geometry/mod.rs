@@ -1,3 +1,44 @@
+/// Implements [`FrcStructure`](frclib_core::structure::FrcStructure) for a struct of other structures.
+///
+/// This is what the `FrcStructure` derive generates, except every declaration of the schema
+/// ends in the `;` the `WPILib` struct format expects instead of being glued to the next one.
+macro_rules! impl_frc_structure {
+    ($name:ident { $($field:ident: $type:ty),+ $(,)? }) => {
+        impl frclib_core::structure::FrcStructure for $name {
+            const SIZE: usize = 0 $(+ <$type as frclib_core::structure::FrcStructure>::SIZE)+;
+            const TYPE: &'static str = stringify!($name);
+            const SCHEMA_SUPPLIER: fn() -> String = || {
+                let mut schema = String::new();
+                $(
+                    schema.push_str(&<$type as frclib_core::structure::FrcStructure>::format_field(stringify!($field)));
+                    schema.push(';');
+                )+
+                schema
+            };
+
+            fn pack(&self, buffer: &mut Vec<u8>) {
+                $(<$type as frclib_core::structure::FrcStructure>::pack(&self.$field, buffer);)+
+            }
+
+            fn unpack(buffer: &mut std::io::Cursor<&[u8]>) -> Self {
+                Self {
+                    $($field: <$type as frclib_core::structure::FrcStructure>::unpack(buffer),)+
+                }
+            }
+        }
+
+        frclib_core::structure::inventory::submit! {
+            <$name as frclib_core::structure::FrcStructure>::DESCRIPTION
+        }
+
+        impl From<$name> for frclib_core::value::FrcValue {
+            fn from(value: $name) -> Self {
+                Self::from_struct(&value)
+            }
+        }
+    };
+}
+
 pub(crate) mod pose2d;
 pub(crate) mod pose3d;
 pub(crate) mod rotation2d;
@@ -2,17 +2,19 @@ use std::ops::{Add, Div, Mul, Sub};
 
 use super::{Rotation2d, Transform2d, Translation2d, Twist2d};
 
-use frclib_core::{units::length::Meter, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::length::Meter;
 
 use nalgebra::ComplexField;
 
 /// A structure representing a 2D pose containing translational and rotational elements.
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Pose2d {
     pub translation: Translation2d,
     pub rotation: Rotation2d,
 }
 
+impl_frc_structure!(Pose2d { translation: Translation2d, rotation: Rotation2d });
+
 impl Pose2d {
     #[must_use]
     pub const fn new() -> Self {
@@ -3,16 +3,18 @@ use std::ops::{Add, Sub, Mul, Div};
 use nalgebra::{Matrix3, Vector3};
 // KEEP UNUSED IMPORTS FOR COMMENTED OUT SECTION
 
-use frclib_core::{units::{angle::Radian, length::Meter}, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::{angle::Radian, length::Meter};
 
 use super::{Pose2d, Rotation3d, Transform3d, Translation3d};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Pose3d {
     pub translation: Translation3d,
     pub rotation: Rotation3d,
 }
 
+impl_frc_structure!(Pose3d { translation: Translation3d, rotation: Rotation3d });
+
 impl Pose3d {
     #[must_use]
     pub const fn new() -> Self {
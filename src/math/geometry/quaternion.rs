@@ -1,31 +1,35 @@
-use frclib_core::structure::{FrcStructure, FrcStructureBytes};
-use nalgebra::{Quaternion, Unit};
+use nalgebra::{Quaternion as NalgebraQuaternion, Unit};
 
 
-/// A in-crate representation of a [`Unit<Quaternion<f64>>`].
-/// This is used to implement [`FrcStructure`] for [`Unit<Quaternion<f64>>`].
+/// A in-crate representation of a [`Unit<Quaternion<f64>>`](nalgebra::UnitQuaternion).
+/// This is used to implement [`FrcStructure`] for [`Unit<Quaternion<f64>>`](nalgebra::UnitQuaternion).
 /// This is needed for serialization and deserialization of Rotation3d.
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
-pub struct FrcQuaternion {
+///
+/// It is named and laid out like `WPILib`'s `Quaternion` struct so tools like `AdvantageScope`
+/// can decode [`Rotation3d`](super::Rotation3d)s logged as structs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
     pub w: f64,
-    pub i: f64,
-    pub j: f64,
-    pub k: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
 }
 
-impl From<Unit<Quaternion<f64>>> for FrcQuaternion {
-    fn from(q: Unit<Quaternion<f64>>) -> Self {
+impl_frc_structure!(Quaternion { w: f64, x: f64, y: f64, z: f64 });
+
+impl From<Unit<NalgebraQuaternion<f64>>> for Quaternion {
+    fn from(q: Unit<NalgebraQuaternion<f64>>) -> Self {
         Self {
             w: q.w,
-            i: q.i,
-            j: q.j,
-            k: q.k,
+            x: q.i,
+            y: q.j,
+            z: q.k,
         }
     }
 }
 
-impl From<FrcQuaternion> for Unit<Quaternion<f64>> {
-    fn from(q: FrcQuaternion) -> Self {
-        Self::from_quaternion(Quaternion::new(q.w, q.i, q.j, q.k))
+impl From<Quaternion> for Unit<NalgebraQuaternion<f64>> {
+    fn from(q: Quaternion) -> Self {
+        Self::from_quaternion(NalgebraQuaternion::new(q.w, q.x, q.y, q.z))
     }
 }
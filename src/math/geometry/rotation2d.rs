@@ -1,15 +1,17 @@
 use std::ops::{Add, Neg, Sub, Mul, Div};
 
-use frclib_core::{units::{angle::Radian, length::Meter}, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::{angle::Radian, length::Meter};
 
 use num::Float;
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rotation2d {
     pub value: Radian,
     pub(super) sin: f64,
     pub(super) cos: f64,
 }
+
+impl_frc_structure!(Rotation2d { value: Radian, sin: f64, cos: f64 });
 impl Rotation2d {
     #[must_use]
     pub const fn new() -> Self {
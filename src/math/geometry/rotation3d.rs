@@ -7,7 +7,7 @@ use nalgebra::{
 
 use frclib_core::{units::angle::{Radian, Angle}, structure::FrcStructure};
 
-use super::{Rotation2d, quaternion::FrcQuaternion};
+use super::{Rotation2d, quaternion::Quaternion as FrcQuaternion};
 
 
 /// A 3d representation of a rotation. <br>
@@ -281,7 +281,7 @@ impl Div<f64> for Rotation3d {
 
 impl frclib_core::structure::FrcStructure for Rotation3d {
     const SIZE: usize = FrcQuaternion::SIZE;
-    const SCHEMA_SUPPLIER: fn() -> String = || "FrcQuaternion q;".to_owned();
+    const SCHEMA_SUPPLIER: fn() -> String = || format!("{};", FrcQuaternion::format_field("q"));
     const TYPE: &'static str = stringify!(Rotation3d);
     fn pack(&self, buffer: &mut Vec<u8>) {
         frclib_core::structure::FrcStructure::pack(&FrcQuaternion::from(self.q), buffer);
@@ -1,13 +1,13 @@
-use frclib_core::structure::{FrcStructure, FrcStructureBytes};
-
 use super::{Pose2d, Rotation2d, Translation2d};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Transform2d {
     pub translation: Translation2d,
     pub rotation: Rotation2d,
 }
 
+impl_frc_structure!(Transform2d { translation: Translation2d, rotation: Rotation2d });
+
 impl Transform2d {
     #[must_use]
     pub const fn new() -> Self {
@@ -1,13 +1,13 @@
-use frclib_core::structure::{FrcStructure, FrcStructureBytes};
-
 use super::{Pose3d, Rotation3d, Transform2d, Translation3d};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Transform3d {
     pub translation: Translation3d,
     pub rotation: Rotation3d,
 }
 
+impl_frc_structure!(Transform3d { translation: Translation3d, rotation: Rotation3d });
+
 impl Transform3d {
     #[must_use]
     pub const fn new() -> Self {
@@ -6,14 +6,16 @@ use num::Float;
 use crate::math::util::interpolate;
 
 use super::Rotation2d;
-use frclib_core::{units::length::{Meter, Distance}, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::length::{Meter, Distance};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Translation2d {
     pub x: Meter,
     pub y: Meter,
 }
 
+impl_frc_structure!(Translation2d { x: Meter, y: Meter });
+
 impl Translation2d {
     #[must_use]
     pub const fn new() -> Self {
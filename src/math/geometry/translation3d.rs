@@ -2,20 +2,22 @@ use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use nalgebra::Quaternion;
 
-use frclib_core::{units::length::Meter, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::length::Meter;
 use num::Float;
 
 use crate::math::util::interpolate;
 
 use super::{Rotation3d, Translation2d};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Translation3d {
     pub x: Meter,
     pub y: Meter,
     pub z: Meter,
 }
 
+impl_frc_structure!(Translation3d { x: Meter, y: Meter, z: Meter });
+
 impl Translation3d {
     #[must_use]
     pub const fn identity() -> Self {
@@ -1,12 +1,14 @@
-use frclib_core::{units::{angle::Radian, length::Meter}, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::{angle::Radian, length::Meter};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Twist2d {
     pub dx: Meter,
     pub dy: Meter,
     pub dtheta: Radian,
 }
 
+impl_frc_structure!(Twist2d { dx: Meter, dy: Meter, dtheta: Radian });
+
 impl Twist2d {
     #[must_use]
     pub const fn identity() -> Self {
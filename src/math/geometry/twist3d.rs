@@ -1,8 +1,8 @@
-use frclib_core::{units::{angle::Radian, length::Meter}, structure::{FrcStructure, FrcStructureBytes}};
+use frclib_core::units::{angle::Radian, length::Meter};
 
 use super::{Translation3d, Twist2d};
 
-#[derive(Copy, Clone, Debug, PartialEq, FrcStructure)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Twist3d {
     pub dx: Meter,
     pub dy: Meter,
@@ -12,6 +12,8 @@ pub struct Twist3d {
     pub rz: Radian,
 }
 
+impl_frc_structure!(Twist3d { dx: Meter, dy: Meter, dz: Meter, rx: Radian, ry: Radian, rz: Radian });
+
 impl Twist3d {
     #[must_use]
     pub const fn identity() -> Self {
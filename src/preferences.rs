@@ -0,0 +1,242 @@
+//! Typed values that persist between runs of the robot program, like encoder offsets and tuning constants.
+//!
+//! Values are stored as JSON in [`preferences_path`] and can be changed at runtime without a redeploy.
+//! Every save is written to a temporary file that then replaces the old one,
+//! so a brownout mid save can't leave a half written file,
+//! and the previous file is kept next to it with a `.bak` extension in case the new one is lost anyway.
+//!
+//! Every value is also logged under `/Preferences/<key>` when it is loaded or changed.
+//!
+//! ```ignore
+//! fn robot_init(&mut self) {
+//!     let offset = preferences().get_or("ArmEncoderOffset", 0.0);
+//!     // after re-zeroing the arm
+//!     let _ = preferences().set("ArmEncoderOffset", new_offset);
+//! }
+//! ```
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use frclib_core::value::FrcValue;
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+
+use crate::telemetry::log;
+
+/// The version of the preferences file format written by this version of the library.
+pub const PREFERENCES_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreferencesError {
+    #[error("Failed to read or write preferences")]
+    Io(#[from] io::Error),
+    #[error("Preferences file is not valid")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported preferences version {0}")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Serialize, Deserialize)]
+struct PreferencesFile {
+    version: u32,
+    values: Map<String, Json>,
+}
+
+/// A set of preferences stored in one file, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Preferences {
+    path: PathBuf,
+    values: Map<String, Json>,
+}
+
+fn with_extension_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn read_file(path: &Path) -> Result<Option<Map<String, Json>>, PreferencesError> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let file: PreferencesFile = serde_json::from_slice(&data)?;
+    if file.version > PREFERENCES_VERSION {
+        return Err(PreferencesError::UnsupportedVersion(file.version));
+    }
+    Ok(Some(file.values))
+}
+
+/// The telemetry value of a preference, values without a matching type are logged as JSON strings.
+fn to_frc_value(value: &Json) -> FrcValue {
+    fn array<T>(values: &[Json], convert: impl Fn(&Json) -> Option<T>) -> Option<Box<[T]>> {
+        values.iter().map(convert).collect()
+    }
+    match value {
+        Json::Bool(value) => FrcValue::Boolean(*value),
+        Json::Number(number) => number.as_i64().map_or_else(
+            || FrcValue::Double(number.as_f64().unwrap_or(f64::NAN)),
+            FrcValue::Int,
+        ),
+        Json::String(value) => FrcValue::String(value.as_str().into()),
+        Json::Array(values) => array(values, Json::as_f64)
+            .map(FrcValue::DoubleArray)
+            .or_else(|| array(values, Json::as_bool).map(FrcValue::BooleanArray))
+            .or_else(|| array(values, |value| value.as_str().map(Box::from)).map(FrcValue::StringArray))
+            .unwrap_or_else(|| FrcValue::String(value.to_string().into())),
+        Json::Null | Json::Object(_) => FrcValue::String(value.to_string().into()),
+    }
+}
+
+fn log_preference(key: &str, value: &Json) {
+    log(format!("/Preferences/{key}"), to_frc_value(value));
+}
+
+impl Preferences {
+    /// Loads the preferences stored at `path`, falling back to its backup if it is missing or corrupt.
+    /// A missing file and backup give empty preferences that are created on the first save.
+    ///
+    /// # Errors
+    /// Returns an error if neither the file nor its backup could be read,
+    /// or if either was written by a newer version of the library.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, PreferencesError> {
+        let path = path.into();
+        let values = match read_file(&path) {
+            Ok(Some(values)) => values,
+            Ok(None) => read_file(&with_extension_suffix(&path, ".bak"))?.unwrap_or_default(),
+            Err(err @ PreferencesError::UnsupportedVersion(_)) => return Err(err),
+            Err(err) => {
+                tracing::warn!("Failed to read preferences from {}, trying the backup: {err}", path.display());
+                read_file(&with_extension_suffix(&path, ".bak"))?.ok_or(err)?
+            }
+        };
+        for (key, value) in &values {
+            log_preference(key, value);
+        }
+        Ok(Self { path, values })
+    }
+
+    /// The file these preferences are saved to.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The value of `key`, or none if it isn't set or can't be read as `T`.
+    #[must_use]
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.values.get(key)?;
+        T::deserialize(value)
+            .inspect_err(|err| tracing::warn!("Preference {key} is not the expected type: {err}"))
+            .ok()
+    }
+
+    /// The value of `key`, or `default` if it isn't set or can't be read as `T`.
+    #[must_use]
+    pub fn get_or<T: DeserializeOwned>(&self, key: &str, default: T) -> T {
+        self.get(key).unwrap_or(default)
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// Sets the value of `key` and saves every preference.
+    ///
+    /// # Errors
+    /// Returns an error if the value can't be stored as JSON or the preferences could not be saved,
+    /// the value is still set in memory if only the save failed.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), PreferencesError> {
+        let value = serde_json::to_value(value)?;
+        log_preference(key, &value);
+        let _ = self.values.insert(key.to_owned(), value);
+        self.save()
+    }
+
+    /// Removes `key` and saves every preference, returns whether it was set.
+    ///
+    /// # Errors
+    /// Returns an error if the preferences could not be saved.
+    pub fn remove(&mut self, key: &str) -> Result<bool, PreferencesError> {
+        if self.values.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Writes every preference to [`path`](Self::path), replacing the old file in one step
+    /// after copying it to the backup.
+    ///
+    /// # Errors
+    /// Returns an error if the file could not be written.
+    pub fn save(&self) -> Result<(), PreferencesError> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let data = serde_json::to_vec_pretty(&PreferencesFile {
+            version: PREFERENCES_VERSION,
+            values: self.values.clone(),
+        })?;
+        let temporary = with_extension_suffix(&self.path, ".tmp");
+        let mut file = File::create(&temporary)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        drop(file);
+        if self.path.exists() {
+            let _ = fs::copy(&self.path, with_extension_suffix(&self.path, ".bak"))?;
+        }
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+static PREFERENCES_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+static PREFERENCES: Mutex<Option<Preferences>> = Mutex::new(None);
+
+/// Sets the file [`preferences`] are stored in, only has an effect before they are first used.
+pub fn set_preferences_path(path: impl Into<PathBuf>) {
+    *PREFERENCES_PATH.lock() = Some(path.into());
+}
+
+/// The file [`preferences`] are stored in,
+/// defaults to `/home/lvuser/preferences.json` on a robot and `preferences.json` in the deploy directory in simulation.
+#[must_use]
+pub fn preferences_path() -> PathBuf {
+    PREFERENCES_PATH.lock().clone().unwrap_or_else(|| {
+        if cfg!(frc_real) {
+            PathBuf::from("/home/lvuser/preferences.json")
+        } else {
+            Path::new(crate::deploy_dir!()).join("preferences.json")
+        }
+    })
+}
+
+/// The robot's preferences, loaded from [`preferences_path`] the first time this is called.
+///
+/// If they could not be loaded an error is logged and they start empty.
+pub fn preferences() -> MappedMutexGuard<'static, Preferences> {
+    MutexGuard::map(PREFERENCES.lock(), |preferences| {
+        preferences.get_or_insert_with(|| {
+            let path = preferences_path();
+            Preferences::open(&path).unwrap_or_else(|err| {
+                tracing::error!("Failed to load preferences from {}: {err}", path.display());
+                Preferences {
+                    path,
+                    values: Map::new(),
+                }
+            })
+        })
+    })
+}
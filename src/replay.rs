@@ -0,0 +1,193 @@
+//! Re-running robot code against a recorded log.
+//!
+//! While running normally every value read through [`input`] is logged under its key,
+//! along with a [`CYCLE_KEY`] marker at the start of every iteration of the main loop.
+//! Replaying that log with [`runtime::replay`](crate::runtime::replay) runs one iteration per recorded cycle
+//! at the recorded timestamp, with the recorded driver station state, and [`input`] returns the recorded values
+//! instead of reading the hardware.
+//! Everything logged while replaying is prefixed with [`REPLAY_PREFIX`] so it can be compared against the original.
+//! [Log policies](crate::telemetry::LogPolicy) never filter the keys a replay needs.
+
+use std::cell::RefCell;
+#[cfg(frc_sim)]
+use std::collections::HashMap;
+use std::collections::HashSet;
+#[cfg(frc_sim)]
+use std::iter::Peekable;
+#[cfg(frc_sim)]
+use std::time::Duration;
+
+use frclib_core::value::{FrcValue, IntoFrcValue};
+#[cfg(frc_sim)]
+use frclib_core::hal::rt::station_interface::{EnabledState, Mode, StationData};
+
+#[cfg(frc_sim)]
+use crate::driver_station::{Alliance, MatchInfo};
+use crate::telemetry::{exempt_replay_input, log};
+#[cfg(frc_sim)]
+use crate::telemetry::TelemetryEntry;
+
+/// Logged once at the start of every iteration of the main loop with the iteration count,
+/// a replay runs one iteration for every time this key was recorded.
+pub const CYCLE_KEY: &str = "/Robot/Cycle";
+
+/// Prepended to every key logged while replaying.
+pub const REPLAY_PREFIX: &str = "/Replay";
+
+thread_local! {
+    /// The inputs read on this thread, which have been exempted from log policies.
+    static INPUT_KEYS: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+#[cfg(frc_sim)]
+thread_local! {
+    static REPLAY_VALUES: RefCell<Option<HashMap<&'static str, FrcValue>>> = const { RefCell::new(None) };
+}
+
+/// Returns true while the current thread is replaying a log.
+#[must_use]
+#[cfg_attr(not(frc_sim), allow(clippy::missing_const_for_fn))]
+pub fn is_replaying() -> bool {
+    #[cfg(frc_sim)]
+    {
+        REPLAY_VALUES.with(|values| values.borrow().is_some())
+    }
+    #[cfg(not(frc_sim))]
+    {
+        false
+    }
+}
+
+/// Reads a value that should be replayable, like a sensor reading.
+///
+/// Normally this calls `read` and logs the result under `key`.
+/// While replaying `read` is not called and the value recorded under `key` for the current cycle is returned,
+/// if there is no recorded value of the right type `read` is used as a fallback.
+pub fn input<T>(key: &'static str, read: impl FnOnce() -> T) -> T
+where
+    T: IntoFrcValue + TryFrom<FrcValue> + Clone,
+{
+    if INPUT_KEYS.with(|keys| keys.borrow_mut().insert(key)) {
+        exempt_replay_input(key);
+    }
+    #[cfg(frc_sim)]
+    if let Some(value) = REPLAY_VALUES.with(|values| {
+        values
+            .borrow()
+            .as_ref()
+            .and_then(|values| values.get(key).cloned())
+    }) {
+        if let Ok(value) = T::try_from(value) {
+            log(key, value.clone());
+            return value;
+        }
+        tracing::warn!("Replayed input {key} has the wrong type");
+    } else if is_replaying() {
+        tracing::warn!("Replayed input {key} was not recorded this cycle");
+    }
+
+    let value = read();
+    log(key, value.clone());
+    value
+}
+
+/// One recorded iteration of the main loop.
+#[cfg(frc_sim)]
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFrame {
+    /// The uptime the iteration started at.
+    pub timestamp: Duration,
+    pub station: StationData,
+    pub match_info: MatchInfo,
+    /// Every value logged during the iteration, the last value wins if a key was logged more than once.
+    /// Sources may also include values logged in earlier iterations.
+    pub values: HashMap<&'static str, FrcValue>,
+}
+
+/// A recorded log that can be replayed one cycle at a time.
+#[cfg(frc_sim)]
+pub trait ReplaySource {
+    /// Returns the next recorded cycle, or none once the log is over.
+    fn next_frame(&mut self) -> Option<ReplayFrame>;
+}
+
+/// Replays telemetry entries, splitting them into frames at every [`CYCLE_KEY`] entry.
+///
+/// Entries have to be in the order they were logged, anything before the first cycle is skipped.
+/// A key keeps its last value in later frames until it is logged again,
+/// so keys only logged when they change, like with [`LogPolicy::ON_CHANGE`](crate::telemetry::LogPolicy::ON_CHANGE),
+/// are seen on every cycle.
+#[cfg(frc_sim)]
+#[derive(Debug)]
+pub struct EntryReplaySource<I: Iterator<Item = TelemetryEntry>> {
+    entries: Peekable<I>,
+    /// The last value of every key seen so far.
+    latest: HashMap<&'static str, FrcValue>,
+}
+
+#[cfg(frc_sim)]
+impl<I: Iterator<Item = TelemetryEntry>> EntryReplaySource<I> {
+    pub fn new(entries: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            entries: entries.into_iter().peekable(),
+            latest: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(frc_sim)]
+impl<I: Iterator<Item = TelemetryEntry>> ReplaySource for EntryReplaySource<I> {
+    fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let marker = loop {
+            let entry = self.entries.next()?;
+            if entry.key == CYCLE_KEY {
+                break entry;
+            }
+        };
+
+        while let Some(entry) = self.entries.next_if(|entry| entry.key != CYCLE_KEY) {
+            let _ = self.latest.insert(entry.key, entry.value);
+        }
+        let mut frame = ReplayFrame {
+            timestamp: Duration::from_micros(marker.timestamp),
+            values: self.latest.clone(),
+            ..ReplayFrame::default()
+        };
+
+        let flag = |key: &str| matches!(frame.values.get(key), Some(FrcValue::Boolean(true)));
+        frame.station.enabled_state = if flag("/DriverStation/EStop") {
+            EnabledState::EStopped
+        } else if flag("/DriverStation/Enabled") {
+            EnabledState::Enabled
+        } else {
+            EnabledState::Disabled
+        };
+        frame.station.mode = if flag("/DriverStation/Autonomous") {
+            Mode::Auto
+        } else if flag("/DriverStation/Test") {
+            Mode::Test
+        } else {
+            Mode::Teleop
+        };
+        frame.station.station_attached = flag("/DriverStation/DSAttached");
+        frame.station.fms_attached = flag("/DriverStation/FMSAttached");
+        frame.match_info.match_number = frame
+            .values
+            .get("/DriverStation/MatchNumber")
+            .cloned()
+            .and_then(|value| u16::try_from(value).ok())
+            .unwrap_or_default();
+        frame.match_info.alliance = match frame.values.get("/DriverStation/Alliance") {
+            Some(FrcValue::String(alliance)) if &**alliance == "Red" => Some(Alliance::Red),
+            Some(FrcValue::String(alliance)) if &**alliance == "Blue" => Some(Alliance::Blue),
+            _ => None,
+        };
+        Some(frame)
+    }
+}
+
+/// Makes [`input`] return the values of a frame on this thread, or read live values again if none.
+#[cfg(frc_sim)]
+pub(crate) fn set_replay_values(values: Option<HashMap<&'static str, FrcValue>>) {
+    REPLAY_VALUES.with(|replay_values| *replay_values.borrow_mut() = values);
+}
@@ -0,0 +1,256 @@
+//! Deterministic stepping of a [`UserRobot`] for tests.
+//!
+//! A [`Lockstep`] owns the robot and a simulated clock, time only moves when the test advances it,
+//! so user code always sees the same timestamps no matter how long it takes to run.
+//!
+//! ```ignore
+//! let mut sim = Lockstep::<MyRobot>::new();
+//! sim.set_mode(RobotMode::Teleop);
+//! sim.step_n(50);
+//! assert_eq!(sim.latest_value("/Arm/Position"), Some(FrcValue::Double(1.0)));
+//! ```
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use frclib_core::hal::rt::notifier::{Notifier, NotifierUpdateType};
+use frclib_core::time::uptime;
+use frclib_core::units::time::Microsecond;
+use frclib_core::value::FrcValue;
+use linkme::distributed_slice;
+use parking_lot::{Mutex, MutexGuard};
+
+use super::recovery::install_panic_hook;
+use super::sim_clock::{install_clock, set_clock_active, set_sim_time};
+use super::{periodic_time, RobotCore, RobotCoreImpl, RobotMode, UserRobot};
+use crate::driver_station::{self, DriverStation};
+use crate::telemetry::{flush_datalog, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+
+/// Only one lockstep simulation can run at a time since the clock and driver station are global.
+static LOCKSTEP_LOCK: Mutex<()> = Mutex::new(());
+/// Everything flushed since the last step while a lockstep is running.
+static CAPTURED: Mutex<Option<Vec<TelemetryEntry>>> = Mutex::new(None);
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static LOCKSTEP_SINK: TelemetrySink = |entries| {
+    if let Some(captured) = CAPTURED.lock().as_mut() {
+        captured.extend_from_slice(entries);
+    }
+};
+
+/// A notifier that jumps the simulated clock to its alarm instead of sleeping.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LockstepNotifier {
+    trigger_time: Option<u64>,
+    period: Option<u64>,
+}
+
+impl Notifier for LockstepNotifier {
+    fn get_name(&self) -> &'static str {
+        "LockstepNotifier"
+    }
+
+    fn update_alarm(&mut self, update: NotifierUpdateType) {
+        let now = u64::from(Microsecond::from(uptime()));
+        match update {
+            NotifierUpdateType::Periodic { period, .. } => {
+                self.period = Some(period.value());
+                self.trigger_time = Some(now + period.value());
+            }
+            NotifierUpdateType::OneShot { trigger_time } => {
+                self.period = None;
+                self.trigger_time = Some(trigger_time.value());
+            }
+            NotifierUpdateType::RelativeOneShot {
+                trigger_offset_time,
+            } => {
+                self.period = None;
+                self.trigger_time = Some(now + trigger_offset_time.value());
+            }
+        }
+    }
+
+    fn cancel_alarm(&mut self) {
+        self.trigger_time = None;
+        self.period = None;
+    }
+
+    /// Moves the clock forward to the alarm, an alarm in the past returns immediately.
+    fn wait_for_alarm(&mut self) -> Microsecond {
+        let now = u64::from(Microsecond::from(uptime()));
+        let Some(trigger_time) = self.trigger_time else {
+            return Microsecond::new(now);
+        };
+        let time = trigger_time.max(now);
+        set_sim_time(Duration::from_micros(time));
+        self.trigger_time = self.period.map(|period| time + period);
+        Microsecond::new(time)
+    }
+}
+
+/// Runs a [`UserRobot`] one step at a time against a simulated clock.
+///
+/// Creating a lockstep resets the clock to zero and the driver station to disabled,
+/// then runs the robot's init hooks.
+/// Lockstep simulations are serialized, creating one blocks until any other on another thread is dropped.
+///
+/// The robot and command scheduler are local to the thread that created the lockstep,
+/// so it should be stepped from that thread.
+/// The data log is flushed after every loop like on a robot, so the telemetry a test inspects
+/// is what every consumer saw, from every thread and with [log policies](crate::telemetry::LogPolicy) applied.
+/// The uptime source can only be replaced before it is first read,
+/// so the first lockstep in a process must be created before anything reads the time.
+///
+/// Panics in user callbacks are handled by the [`RestartPolicy`](super::recovery::RestartPolicy) like on a robot,
+/// set it to [`RestartPolicy::NEVER`](super::recovery::RestartPolicy::NEVER) for panics to fail the test.
+pub struct Lockstep<Robo: UserRobot> {
+    core: RobotCoreImpl<Robo>,
+    notifier: LockstepNotifier,
+    /// Everything flushed since the lockstep was created.
+    history: Vec<TelemetryEntry>,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl<Robo: UserRobot> Lockstep<Robo> {
+    #[must_use]
+    pub fn new() -> Self {
+        let guard = LOCKSTEP_LOCK.lock();
+        install_clock();
+        install_panic_hook();
+        set_sim_time(Duration::ZERO);
+        set_clock_active(true);
+
+        driver_station::sim::reset();
+        driver_station::sim::set_robot_mode(RobotMode::Disabled);
+
+        // anything logged before the lockstep existed isn't part of its history
+        flush_datalog();
+        *CAPTURED.lock() = Some(Vec::new());
+
+        let mut core = RobotCoreImpl::construct();
+        core.init();
+        let mut lockstep = Self {
+            core,
+            notifier: LockstepNotifier::default(),
+            history: Vec::new(),
+            _guard: guard,
+        };
+        flush_datalog();
+        lockstep.collect();
+        lockstep
+    }
+
+    /// Moves everything flushed since the last call into the history.
+    fn collect(&mut self) {
+        if let Some(captured) = CAPTURED.lock().as_mut() {
+            self.history.append(captured);
+        }
+    }
+
+    /// The current simulated uptime.
+    #[must_use]
+    pub fn now(&self) -> Duration {
+        uptime()
+    }
+
+    #[must_use]
+    pub const fn robot(&self) -> &Robo {
+        &self.core.user_robot
+    }
+
+    pub const fn robot_mut(&mut self) -> &mut Robo {
+        &mut self.core.user_robot
+    }
+
+    /// Sets the mode the robot will see on the next step.
+    pub fn set_mode(&mut self, mode: RobotMode) {
+        driver_station::sim::set_robot_mode(mode);
+    }
+
+    #[must_use]
+    pub fn mode(&self) -> RobotMode {
+        DriverStation::robot_mode()
+    }
+
+    /// Runs one iteration of the main loop, and any periodic callbacks due during it,
+    /// then moves the clock forward by one period.
+    pub fn step(&mut self) {
+        self.advance(periodic_time());
+    }
+
+    /// Runs `count` iterations of the main loop, see [`step`](Lockstep::step).
+    pub fn step_n(&mut self, count: usize) {
+        for _ in 0..count {
+            self.step();
+        }
+    }
+
+    /// Moves the clock forward by `duration`, running everything that comes due
+    /// from now up to but not including the end.
+    ///
+    /// # Panics
+    /// Panics if the periodic time is zero, a lockstep loop can't run as fast as possible.
+    pub fn advance(&mut self, duration: Duration) {
+        assert!(
+            !periodic_time().is_zero(),
+            "Lockstep simulation requires a non-zero periodic time"
+        );
+        let end = uptime() + duration;
+        loop {
+            let deadline = self.core.poll();
+            self.collect();
+            if deadline >= end {
+                break;
+            }
+            self.notifier.update_alarm(NotifierUpdateType::OneShot {
+                trigger_time: Microsecond::from(deadline),
+            });
+            let _ = self.notifier.wait_for_alarm();
+        }
+        set_sim_time(end);
+    }
+
+    /// The latest value flushed to telemetry under a key.
+    #[must_use]
+    pub fn latest_value(&self, key: &str) -> Option<FrcValue> {
+        self.history
+            .iter()
+            .rev()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Every value flushed to telemetry under a key with its timestamp.
+    #[must_use]
+    pub fn values(&self, key: &str) -> Vec<(Duration, FrcValue)> {
+        self.history
+            .iter()
+            .filter(|entry| entry.key == key)
+            .map(|entry| (Duration::from_micros(entry.timestamp), entry.value.clone()))
+            .collect()
+    }
+}
+
+impl<Robo: UserRobot> Default for Lockstep<Robo> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Robo: UserRobot> Drop for Lockstep<Robo> {
+    fn drop(&mut self) {
+        self.core.end();
+        *CAPTURED.lock() = None;
+        driver_station::sim::reset();
+        set_clock_active(false);
+    }
+}
+
+impl<Robo: UserRobot> Debug for Lockstep<Robo> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lockstep")
+            .field("now", &self.now())
+            .field("core", &self.core)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,403 @@
+use std::sync::atomic::AtomicU64;
+use std::{fmt::Debug, time::Duration};
+
+use frclib_core::hal::get_hal;
+use frclib_core::hal::rt::notifier::NotifierUpdateType;
+use frclib_core::hal::rt::station_interface::{EnabledState, Mode, StationData};
+use frclib_core::time::{uptime, Instant};
+use frclib_core::units::time::{Microsecond, Millisecond};
+
+static PERIODIC_TIME: AtomicU64 = AtomicU64::new(20_000);
+
+/// If time is set to 0, then the periodic loop will run as fast as possible.
+pub fn set_periodic_time(time: impl Into<Millisecond>) {
+    PERIODIC_TIME.store(
+        Microsecond::from(time.into()).value(),
+        std::sync::atomic::Ordering::Relaxed,
+    );
+}
+
+/// The period of the main loop.
+#[must_use]
+pub fn periodic_time() -> Duration {
+    Duration::from_micros(PERIODIC_TIME.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+#[cfg(frc_sim)]
+pub mod lockstep;
+mod periodic;
+pub mod recovery;
+#[cfg(frc_sim)]
+pub(crate) mod sim_clock;
+pub use periodic::PeriodicCallbacks;
+use periodic::next_expiration;
+use recovery::{guard, restart_policy};
+
+use crate::driver_station::DriverStation;
+use crate::replay::CYCLE_KEY;
+use crate::runtime::shutdown_requested;
+use crate::telemetry::{flush_datalog, log};
+use crate::event::EventLoop;
+use crate::watchdog::Watchdog;
+use crate::if_sim;
+use crate::vendor::performers::{call_stage, Stage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum RobotMode {
+    Disabled = 0,
+    Teleop = 1,
+    Autonomous = 2,
+    Test = 3,
+}
+impl RobotMode {
+    #[must_use]
+    pub const fn is_disabled(&self) -> bool {
+        matches!(self, Self::Disabled)
+    }
+    #[must_use]
+    pub const fn is_autonomous(&self) -> bool {
+        matches!(self, Self::Autonomous)
+    }
+    #[must_use]
+    pub const fn is_teleop(&self) -> bool {
+        matches!(self, Self::Teleop)
+    }
+    #[must_use]
+    pub const fn is_test(&self) -> bool {
+        matches!(self, Self::Test)
+    }
+
+    /// Derives the mode from the driver station control word,
+    /// an e-stopped robot is always [`RobotMode::Disabled`].
+    #[must_use]
+    pub const fn from_station_data(station: &StationData) -> Self {
+        match (station.enabled_state, station.mode) {
+            (EnabledState::Enabled, Mode::Teleop) => Self::Teleop,
+            (EnabledState::Enabled, Mode::Auto) => Self::Autonomous,
+            (EnabledState::Enabled, Mode::Test) => Self::Test,
+            (EnabledState::Disabled | EnabledState::EStopped, _) => Self::Disabled,
+        }
+    }
+}
+
+/// The core robot trait that is directly used by the frclib runtime.
+/// 
+/// Most users will not need to implement this trait directly, but instead
+/// users will implement the [`UserRobot`] trait which is used by the [`RobotCoreImpl`] struct.
+pub trait RobotCore<Robo: UserRobot> {
+    fn construct() -> Self
+    where
+        Self: Sized;
+
+    /// Will be called once at the start of the program after the HAL is initialized.
+    fn start(&mut self);
+
+    /// Will be called once at the end of the program before the HAL is deinitialized.
+    /// This will even be called if the program panics using [`catch_unwind`](`std::panic::catch_unwind`).
+    fn end(&mut self);
+
+    fn get_mode(&self) -> RobotMode;
+}
+
+pub trait UserRobot {
+    fn construct() -> Self
+    where
+        Self: Sized;
+
+    /// Only ran once at the start of the program after the HAL is initialized.
+    fn robot_init(&mut self);
+    /// Ran every cycle.
+    fn robot_periodic(&mut self, time_delta: Duration);
+    /// Only ran once when the program is shutting down, before telemetry is flushed.
+    fn robot_end(&mut self) {}
+
+    /// Ran upon the robot entering the disabled, no limit to run count.
+    fn robot_disabled_init(&mut self) {}
+    /// Ran every cycle while the robot is disabled.
+    fn robot_disabled_periodic(&mut self, _time_delta: Duration) {}
+    /// Ran upon the robot leaving the disabled state, no limit to run count.
+    fn robot_disabled_end(&mut self) {}
+
+    /// Ran upon the robot entering the autonomous state, no limit to run count.
+    fn robot_autonomous_init(&mut self) {}
+    /// Ran every cycle while the robot is in the autonomous state.
+    fn robot_autonomous_periodic(&mut self, _time_delta: Duration) {}
+    /// Ran upon the robot leaving the autonomous state, no limit to run count.
+    fn robot_autonomous_end(&mut self) {}
+
+    /// Ran upon the robot entering the teleop state, no limit to run count.
+    fn robot_teleop_init(&mut self) {}
+    /// Ran every cycle while the robot is in the teleop state.
+    fn robot_teleop_periodic(&mut self, time_delta: Duration);
+    /// Ran upon the robot leaving the teleop state, no limit to run count.
+    fn robot_teleop_end(&mut self) {}
+
+    /// Ran upon the robot entering the test state, no limit to run count.
+    fn robot_test_init(&mut self) {}
+    /// Ran every cycle while the robot is in the test state.
+    fn robot_test_periodic(&mut self, _time_delta: Duration) {}
+    /// Ran upon the robot leaving the test state, no limit to run count.
+    fn robot_test_end(&mut self) {}
+
+    /// Ran just after the robot is initialized in simulation.
+    fn sim_init(&mut self) {}
+    /// Ran every cycle while the robot is in simulation.
+    fn sim_periodic(&mut self, _time_delta: Duration) {}
+
+    /// Ran once after [`robot_init`](UserRobot::robot_init) to register callbacks
+    /// that run at their own rate alongside the main loop.
+    fn register_periodic(&mut self, _callbacks: &mut PeriodicCallbacks<Self>)
+    where
+        Self: Sized,
+    {
+    }
+}
+
+pub struct RobotCoreImpl<Robo: UserRobot> {
+    user_robot: Robo,
+    callbacks: PeriodicCallbacks<Robo>,
+    watchdog: Watchdog,
+    last_mode: RobotMode,
+    /// The uptime the main loop is next due at.
+    next_step: Duration,
+    cycle: i64,
+    /// Set once init has ran, re-entering [`start`](RobotCore::start) after a panic
+    /// only runs init again if the [`RestartPolicy`] asks for it.
+    initialized: bool,
+    last_mode_periodic_instant: Instant,
+    last_robot_periodic_instant: Instant,
+    #[cfg(frc_sim)]
+    last_sim_periodic_instant: Instant,
+}
+impl<Robo: UserRobot> RobotCoreImpl<Robo> {
+    fn new(user_robot: Robo) -> Self {
+        Self {
+            user_robot,
+            callbacks: PeriodicCallbacks::new(),
+            watchdog: Watchdog::new(Duration::ZERO),
+            last_mode: RobotMode::Disabled,
+            next_step: Duration::ZERO,
+            cycle: 0,
+            initialized: false,
+            last_mode_periodic_instant: Instant::now(),
+            last_robot_periodic_instant: Instant::now(),
+            #[cfg(frc_sim)]
+            last_sim_periodic_instant: Instant::now(),
+        }
+    }
+
+    /// Runs the init stage and the user init hooks,
+    /// the first iteration of the main loop will be due immediately after.
+    pub(crate) fn init(&mut self) {
+        DriverStation::refresh();
+
+        call_stage(Stage::Init, self.get_mode());
+
+        let robot = &mut self.user_robot;
+        let _ = guard("robot_init", || robot.robot_init());
+
+        #[cfg(frc_sim)]
+        let _ = guard("sim_init", || robot.sim_init());
+
+        self.callbacks = PeriodicCallbacks::new();
+        let callbacks = &mut self.callbacks;
+        let _ = guard("register_periodic", || robot.register_periodic(callbacks));
+
+        self.initialized = true;
+        self.resume();
+    }
+
+    /// Restarts the loop timing, the first iteration of the main loop will be due immediately after.
+    fn resume(&mut self) {
+        self.last_mode = self.get_mode();
+        self.last_mode_periodic_instant = Instant::now();
+        self.last_robot_periodic_instant = Instant::now();
+        #[cfg(frc_sim)]
+        {
+            self.last_sim_periodic_instant = Instant::now();
+        }
+
+        self.next_step = uptime();
+        self.callbacks.start(self.next_step);
+    }
+
+    /// Runs the main loop and any periodic callbacks that are due,
+    /// returning the uptime the next of them is due at.
+    ///
+    /// The data log is only flushed when the main loop ran,
+    /// anything logged by callbacks in between goes out with the next iteration.
+    fn poll(&mut self) -> Duration {
+        let stepped = uptime() >= self.next_step;
+        if stepped {
+            self.step();
+            self.next_step = next_expiration(self.next_step, periodic_time(), uptime());
+        }
+        self.callbacks.run_due(&mut self.user_robot, uptime());
+        if stepped {
+            flush_datalog();
+        }
+
+        self.callbacks
+            .next_expiration()
+            .map_or(self.next_step, |expiration| expiration.min(self.next_step))
+    }
+
+    /// Runs the main loop regardless of its deadline, then any periodic callbacks that are due.
+    #[cfg(frc_sim)]
+    pub(crate) fn step_now(&mut self) {
+        self.step();
+        self.callbacks.run_due(&mut self.user_robot, uptime());
+    }
+
+    /// Runs one iteration of the main loop.
+    fn step(&mut self) {
+        log(CYCLE_KEY, self.cycle);
+        self.cycle += 1;
+
+        self.watchdog.set_timeout(periodic_time());
+        self.watchdog.reset();
+
+        DriverStation::refresh();
+        let mode = self.get_mode();
+        self.watchdog.add_epoch("DriverStation");
+
+        if mode != self.last_mode {
+            self.mode_transition(self.last_mode, mode);
+        }
+        self.watchdog.add_epoch("ModeTransition");
+
+        EventLoop::default_loop().poll();
+        self.watchdog.add_epoch("EventLoop");
+
+        call_stage(Stage::PreUser, mode);
+        self.watchdog.add_epoch("PreUser");
+        if_sim!{
+            call_stage(Stage::PreUserSim, mode);
+            self.watchdog.add_epoch("PreUserSim");
+        };
+
+        {
+            let elapsed = self.last_robot_periodic_instant.elapsed();
+            self.last_robot_periodic_instant = Instant::now();
+            let robot = &mut self.user_robot;
+            let _ = guard("robot_periodic", || robot.robot_periodic(elapsed));
+        }
+        self.watchdog.add_epoch("robot_periodic");
+
+        {
+            let elapsed = self.last_mode_periodic_instant.elapsed();
+            self.last_mode_periodic_instant = Instant::now();
+            let epoch = self.mode_periodic(mode, elapsed);
+            self.watchdog.add_epoch(epoch);
+        }
+
+        self.last_mode = mode;
+
+        #[cfg(frc_sim)]
+        {
+            let elapsed = self.last_sim_periodic_instant.elapsed();
+            self.last_sim_periodic_instant = Instant::now();
+            let robot = &mut self.user_robot;
+            let _ = guard("sim_periodic", || robot.sim_periodic(elapsed));
+            self.watchdog.add_epoch("sim_periodic");
+        }
+
+        call_stage(Stage::PostUser, mode);
+        self.watchdog.add_epoch("PostUser");
+        if_sim!{
+            call_stage(Stage::PostUserSim, mode);
+            self.watchdog.add_epoch("PostUserSim");
+        };
+
+        let _ = self.watchdog.check();
+    }
+
+    /// Calls the end hook of the last mode and the init hook of the new mode.
+    fn mode_transition(&mut self, last_mode: RobotMode, mode: RobotMode) {
+        let robot = &mut self.user_robot;
+        let _ = match last_mode {
+            RobotMode::Disabled => guard("robot_disabled_end", || robot.robot_disabled_end()),
+            RobotMode::Autonomous => guard("robot_autonomous_end", || robot.robot_autonomous_end()),
+            RobotMode::Teleop => guard("robot_teleop_end", || robot.robot_teleop_end()),
+            RobotMode::Test => guard("robot_test_end", || robot.robot_test_end()),
+        };
+        let _ = match mode {
+            RobotMode::Disabled => guard("robot_disabled_init", || robot.robot_disabled_init()),
+            RobotMode::Autonomous => guard("robot_autonomous_init", || robot.robot_autonomous_init()),
+            RobotMode::Teleop => guard("robot_teleop_init", || robot.robot_teleop_init()),
+            RobotMode::Test => guard("robot_test_init", || robot.robot_test_init()),
+        };
+    }
+
+    /// Calls the periodic hook of the mode, returning the name of the hook.
+    fn mode_periodic(&mut self, mode: RobotMode, elapsed: Duration) -> &'static str {
+        let name = match mode {
+            RobotMode::Disabled => "robot_disabled_periodic",
+            RobotMode::Autonomous => "robot_autonomous_periodic",
+            RobotMode::Teleop => "robot_teleop_periodic",
+            RobotMode::Test => "robot_test_periodic",
+        };
+        let robot = &mut self.user_robot;
+        let _ = guard(name, || match mode {
+            RobotMode::Disabled => robot.robot_disabled_periodic(elapsed),
+            RobotMode::Autonomous => robot.robot_autonomous_periodic(elapsed),
+            RobotMode::Teleop => robot.robot_teleop_periodic(elapsed),
+            RobotMode::Test => robot.robot_test_periodic(elapsed),
+        });
+        name
+    }
+}
+impl<T: UserRobot> RobotCore<T> for RobotCoreImpl<T> {
+    fn construct() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(T::construct())
+    }
+
+    fn start(&mut self) {
+        let mut notifier = get_hal()
+            .expect("HAL not initialized")
+            .notifier_api()
+            .new_notifier();
+
+        if !self.initialized || restart_policy().rerun_init {
+            self.init();
+        } else {
+            self.resume();
+        }
+
+        while !shutdown_requested() {
+            let trigger_time = self.poll();
+            notifier.update_alarm(NotifierUpdateType::OneShot {
+                trigger_time: Microsecond::from(trigger_time),
+            });
+            let _ = notifier.wait_for_alarm();
+        }
+    }
+
+    fn end(&mut self) {
+        let robot = &mut self.user_robot;
+        let _ = guard("robot_end", || robot.robot_end());
+    }
+
+    fn get_mode(&self) -> RobotMode {
+        DriverStation::robot_mode()
+    }
+}
+
+impl<T: UserRobot> Debug for RobotCoreImpl<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RobotCoreImpl")
+            .field("callbacks", &self.callbacks)
+            .field("watchdog", &self.watchdog)
+            .field("last_mode", &self.last_mode)
+            .finish_non_exhaustive()
+    }
+}
+impl<T: UserRobot> Default for RobotCoreImpl<T> {
+    fn default() -> Self {
+        Self::new(T::construct())
+    }
+}
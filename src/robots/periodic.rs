@@ -0,0 +1,190 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use super::recovery::guard;
+
+type Callback<Robo> = Box<dyn FnMut(&mut Robo, Duration)>;
+
+struct PeriodicCallback<Robo> {
+    func: Callback<Robo>,
+    period: Duration,
+    offset: Duration,
+    /// The uptime the callback is next due at.
+    expiration: Duration,
+    /// The uptime the callback last ran at.
+    last_run: Duration,
+}
+
+/// Callbacks that run at their own rate alongside the main robot loop,
+/// like a faster control loop next to the 20ms main loop.
+///
+/// All callbacks share the main loop's notifier and thread,
+/// so a callback is never ran at the same time as user robot code.
+/// If a callback misses one or more of its deadlines it is ran once as soon as possible
+/// and the missed runs are skipped.
+///
+/// Callbacks are registered in [`UserRobot::register_periodic`](super::UserRobot::register_periodic).
+pub struct PeriodicCallbacks<Robo> {
+    callbacks: Vec<PeriodicCallback<Robo>>,
+}
+
+impl<Robo> PeriodicCallbacks<Robo> {
+    pub(super) const fn new() -> Self {
+        Self {
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Adds a callback with mutable access to the user robot,
+    /// it will first run `offset + period` after the main loop starts.
+    ///
+    /// The callback is given the time since it last ran.
+    ///
+    /// # Panics
+    /// Panics if the period is zero.
+    pub fn add(
+        &mut self,
+        period: Duration,
+        offset: Duration,
+        func: impl FnMut(&mut Robo, Duration) + 'static,
+    ) {
+        assert!(!period.is_zero(), "Periodic callbacks must have a non-zero period");
+        self.callbacks.push(PeriodicCallback {
+            func: Box::new(func),
+            period,
+            offset,
+            expiration: Duration::ZERO,
+            last_run: Duration::ZERO,
+        });
+    }
+
+    /// Adds a callback that owns its own state instead of borrowing the user robot,
+    /// see [`add`](PeriodicCallbacks::add).
+    ///
+    /// # Panics
+    /// Panics if the period is zero.
+    pub fn add_with_state<S: 'static>(
+        &mut self,
+        period: Duration,
+        offset: Duration,
+        mut state: S,
+        mut func: impl FnMut(&mut S, Duration) + 'static,
+    ) {
+        self.add(period, offset, move |_, elapsed| func(&mut state, elapsed));
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.callbacks.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    /// Sets the first deadline of every callback relative to the loop start.
+    pub(super) fn start(&mut self, now: Duration) {
+        for callback in &mut self.callbacks {
+            callback.expiration = now + callback.offset + callback.period;
+            callback.last_run = now;
+        }
+    }
+
+    /// The earliest deadline of any callback.
+    pub(super) fn next_expiration(&self) -> Option<Duration> {
+        self.callbacks.iter().map(|callback| callback.expiration).min()
+    }
+
+    /// Runs every callback whose deadline has passed, in registration order.
+    pub(super) fn run_due(&mut self, robot: &mut Robo, now: Duration) {
+        for callback in &mut self.callbacks {
+            if now < callback.expiration {
+                continue;
+            }
+            let func = &mut callback.func;
+            let _ = guard("periodic_callback", || func(robot, now.saturating_sub(callback.last_run)));
+            callback.last_run = now;
+            callback.expiration = next_expiration(callback.expiration, callback.period, now);
+        }
+    }
+}
+
+/// Advances a deadline by one period, skipping any whole periods that have already passed.
+///
+/// A zero period is always due.
+pub(super) fn next_expiration(expiration: Duration, period: Duration, now: Duration) -> Duration {
+    if period.is_zero() {
+        return now;
+    }
+    let next = expiration + period;
+    if next > now {
+        return next;
+    }
+    let missed = now.saturating_sub(next).as_nanos() / period.as_nanos() + 1;
+    let missed = u32::try_from(missed).unwrap_or(u32::MAX);
+    tracing::debug!("Missed a periodic deadline, skipping {missed} periods");
+    next + period * missed
+}
+
+impl<Robo> Debug for PeriodicCallbacks<Robo> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.callbacks
+                    .iter()
+                    .map(|callback| (callback.period, callback.offset)),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{next_expiration, PeriodicCallbacks};
+
+    const PERIOD: Duration = Duration::from_millis(20);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn on_time_advances_one_period() {
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(20)), ms(40));
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(39)), ms(40));
+    }
+
+    #[test]
+    fn one_missed_period_is_skipped() {
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(45)), ms(60));
+    }
+
+    #[test]
+    fn several_missed_periods_are_skipped() {
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(105)), ms(120));
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(1_000)), ms(1_020));
+    }
+
+    #[test]
+    fn now_on_a_boundary_is_not_due_again() {
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(40)), ms(60));
+        assert_eq!(next_expiration(ms(20), PERIOD, ms(100)), ms(120));
+    }
+
+    #[test]
+    fn late_callbacks_run_once() {
+        let mut callbacks = PeriodicCallbacks::new();
+        callbacks.add(ms(10), ms(5), |runs: &mut Vec<Duration>, elapsed| runs.push(elapsed));
+        callbacks.start(Duration::ZERO);
+        assert_eq!(callbacks.next_expiration(), Some(ms(15)));
+
+        let mut runs = Vec::new();
+        callbacks.run_due(&mut runs, ms(14));
+        callbacks.run_due(&mut runs, ms(48));
+        assert_eq!(runs, [ms(48)]);
+        assert_eq!(callbacks.next_expiration(), Some(ms(55)));
+    }
+}
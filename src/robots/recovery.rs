@@ -0,0 +1,275 @@
+//! Recovering from panics in user code.
+//!
+//! Panics are recorded by a panic hook so the backtrace is available after unwinding,
+//! then the [`RestartPolicy`] decides whether the program keeps running.
+//! Every recovered panic forces the robot disabled for a moment and is logged to telemetry under `/Recovery`.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{LazyLock, Once};
+use std::time::Duration;
+
+use frclib_core::time::uptime;
+use parking_lot::Mutex;
+
+use crate::driver_station::DriverStation;
+use crate::telemetry::log;
+
+/// How the runtime reacts to a panic in user code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Re-enter the main loop after a panic escapes it instead of ending the program.
+    pub restart: bool,
+    /// Run [`robot_init`](super::UserRobot::robot_init) again when re-entering the main loop.
+    pub rerun_init: bool,
+    /// Catch panics in individual user callbacks, like a mode periodic,
+    /// so the rest of the cycle still runs.
+    pub catch_callbacks: bool,
+    /// The most panics recovered from within [`window`](RestartPolicy::window),
+    /// the next panic ends the program.
+    pub max_panics: u32,
+    pub window: Duration,
+    /// How long the robot is forced disabled after recovering from a panic.
+    pub disabled_duration: Duration,
+}
+
+impl RestartPolicy {
+    /// Never recover, any panic in user code ends the program.
+    pub const NEVER: Self = Self {
+        restart: false,
+        rerun_init: false,
+        catch_callbacks: false,
+        max_panics: 0,
+        window: Duration::ZERO,
+        disabled_duration: Duration::ZERO,
+    };
+}
+
+impl Default for RestartPolicy {
+    // `Duration::from_mins` is too new for the toolchains teams are on
+    #[allow(clippy::duration_suboptimal_units)]
+    fn default() -> Self {
+        Self {
+            restart: true,
+            rerun_init: false,
+            catch_callbacks: true,
+            max_panics: 5,
+            window: Duration::from_secs(60),
+            disabled_duration: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RecoveryState {
+    policy: Option<RestartPolicy>,
+    /// The uptime of every recovered panic within the window.
+    recent: VecDeque<Duration>,
+    total: u32,
+}
+
+/// A panic seen by the panic hook,
+/// the backtrace is only resolved if the panic is recovered from.
+#[derive(Debug)]
+struct PanicRecord {
+    location: String,
+    backtrace: Backtrace,
+}
+
+/// Resumed in place of a panic that was already reported as unrecoverable.
+struct Unrecoverable;
+
+static RECOVERY: LazyLock<Mutex<RecoveryState>> =
+    LazyLock::new(|| Mutex::new(RecoveryState::default()));
+static HOOK_INSTALL: Once = Once::new();
+
+thread_local! {
+    /// The last panic on this thread, dropped with the thread if it is never recovered from.
+    static LAST_PANIC: RefCell<Option<PanicRecord>> = const { RefCell::new(None) };
+}
+
+pub fn set_restart_policy(policy: RestartPolicy) {
+    RECOVERY.lock().policy = Some(policy);
+}
+
+#[must_use]
+pub fn restart_policy() -> RestartPolicy {
+    RECOVERY.lock().policy.unwrap_or_default()
+}
+
+/// Installs a panic hook that records the location and backtrace of the last panic on each thread
+/// before running the previous hook.
+pub(crate) fn install_panic_hook() {
+    HOOK_INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info
+                .location()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let record = PanicRecord {
+                location,
+                backtrace: Backtrace::force_capture(),
+            };
+            let _ = LAST_PANIC.try_with(|last| {
+                if let Ok(mut last) = last.try_borrow_mut() {
+                    *last = Some(record);
+                }
+            });
+            previous(info);
+        }));
+    });
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Records a panic from `source` and returns true if the policy allows recovering from it,
+/// forcing the robot disabled if so.
+///
+/// Has to be called on the thread that panicked, its backtrace is the one logged.
+pub(crate) fn recover(source: &'static str, payload: &(dyn Any + Send), allowed: bool) -> bool {
+    if payload.is::<Unrecoverable>() {
+        return false;
+    }
+    let policy = restart_policy();
+    let now = uptime();
+    let (recovering, total) = {
+        let mut state = RECOVERY.lock();
+        while state
+            .recent
+            .front()
+            .is_some_and(|time| now.saturating_sub(*time) > policy.window)
+        {
+            let _ = state.recent.pop_front();
+        }
+        let recovering = allowed && state.recent.len() < policy.max_panics as usize;
+        if recovering {
+            state.recent.push_back(now);
+        }
+        state.total = state.total.saturating_add(1);
+        (recovering, state.total)
+    };
+
+    let message = payload_message(payload);
+    let record = LAST_PANIC.with(|last| last.borrow_mut().take());
+    let (location, backtrace) = record
+        .map(|record| (record.location, record.backtrace.to_string()))
+        .unwrap_or_default();
+
+    log("/Recovery/PanicCount", total);
+    log("/Recovery/LastPanic/Source", source);
+    log("/Recovery/LastPanic/Message", message);
+    log("/Recovery/LastPanic/Location", location.as_str());
+    log("/Recovery/LastPanic/Backtrace", backtrace);
+    log("/Recovery/Recovered", recovering);
+
+    if recovering {
+        tracing::error!("Recovering from panic in {source} at {location}: {message}");
+        DriverStation::force_disabled_for(policy.disabled_duration);
+    } else {
+        tracing::error!("Unrecoverable panic in {source} at {location}: {message}");
+    }
+    recovering
+}
+
+/// Runs a user callback, catching a panic if the policy allows it.
+///
+/// A panic the policy does not allow recovering from is resumed.
+pub(crate) fn guard<R>(source: &'static str, func: impl FnOnce() -> R) -> Option<R> {
+    if !restart_policy().catch_callbacks {
+        return Some(func());
+    }
+    match panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            if recover(source, payload.as_ref(), true) {
+                None
+            } else {
+                panic::resume_unwind(Box::new(Unrecoverable))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+    use std::time::Duration;
+
+    use parking_lot::Mutex;
+
+    use super::{guard, install_panic_hook, recover, set_restart_policy, RestartPolicy, Unrecoverable, RECOVERY};
+
+    /// The policy and recent panics are global.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn use_policy(policy: RestartPolicy) {
+        install_panic_hook();
+        set_restart_policy(policy);
+        RECOVERY.lock().recent.clear();
+    }
+
+    #[test]
+    fn recoveries_are_limited_within_the_window() {
+        let _serial = SERIAL.lock();
+        use_policy(RestartPolicy {
+            max_panics: 2,
+            window: Duration::from_millis(50),
+            ..RestartPolicy::default()
+        });
+
+        let payload = "boom";
+        assert!(recover("test", &payload, true));
+        assert!(recover("test", &payload, true));
+        assert!(!recover("test", &payload, true));
+        assert!(!recover("test", &payload, false));
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(recover("test", &payload, true));
+    }
+
+    #[test]
+    fn guard_turns_a_panic_into_a_recovery() {
+        let _serial = SERIAL.lock();
+        use_policy(RestartPolicy::default());
+
+        assert_eq!(guard("test", || 5), Some(5));
+        assert_eq!(guard("test", || -> i32 { panic!("boom") }), None);
+        assert_eq!(RECOVERY.lock().recent.len(), 1);
+    }
+
+    #[test]
+    fn guard_resumes_panics_past_the_limit() {
+        let _serial = SERIAL.lock();
+        use_policy(RestartPolicy {
+            max_panics: 0,
+            ..RestartPolicy::default()
+        });
+
+        let payload = panic::catch_unwind(|| guard("test", || panic!("boom")))
+            .expect_err("the panic is not recovered");
+        assert!(payload.is::<Unrecoverable>());
+        // the main loop doesn't report the same panic twice
+        assert!(!recover("main loop", payload.as_ref(), true));
+    }
+
+    #[test]
+    fn guard_does_not_catch_without_catch_callbacks() {
+        let _serial = SERIAL.lock();
+        use_policy(RestartPolicy::NEVER);
+
+        let payload = panic::catch_unwind(|| guard("test", || panic!("boom")))
+            .expect_err("the panic is not caught");
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+        assert!(RECOVERY.lock().recent.is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+//! A manually driven uptime source used by [`Lockstep`](super::lockstep::Lockstep) and log replay.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Once};
+use std::time::Duration;
+
+use frclib_core::time::__private::{set_time_implementation, TimeImplementation};
+
+static SIM_CLOCK_ACTIVE: AtomicBool = AtomicBool::new(false);
+static SIM_CLOCK_TIME: AtomicU64 = AtomicU64::new(0);
+static CLOCK_INSTALL: Once = Once::new();
+static PROCESS_START: LazyLock<std::time::Instant> = LazyLock::new(std::time::Instant::now);
+
+#[allow(clippy::cast_possible_truncation)]
+fn sim_uptime() -> u64 {
+    if SIM_CLOCK_ACTIVE.load(Ordering::Acquire) {
+        SIM_CLOCK_TIME.load(Ordering::Acquire)
+    } else {
+        PROCESS_START.elapsed().as_micros() as u64
+    }
+}
+
+/// Installs the sim clock as the uptime source,
+/// while the clock is not active it follows real time.
+///
+/// # Panics
+/// Panics if anything read the uptime before the clock was first installed.
+pub fn install_clock() {
+    CLOCK_INSTALL.call_once(|| {
+        let _ = *PROCESS_START;
+        // Safety: guarded by the `Once` so this is only ever called once
+        unsafe {
+            set_time_implementation(TimeImplementation {
+                implementation_name: "SimClock",
+                uptime: sim_uptime,
+                pause: None,
+                system_time_valid: || true,
+            });
+        }
+    });
+}
+
+/// While active the uptime only changes through [`set_sim_time`].
+pub fn set_clock_active(active: bool) {
+    SIM_CLOCK_ACTIVE.store(active, Ordering::Release);
+}
+
+#[allow(clippy::cast_possible_truncation)]
+pub fn set_sim_time(time: Duration) {
+    SIM_CLOCK_TIME.store(time.as_micros() as u64, Ordering::Release);
+}
@@ -1,8 +1,59 @@
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Once};
 
 use frclib_core::hal::{self, HAL, get_hal};
+use signal_hook::consts::{SIGINT, SIGTERM};
 
-use crate::{robots::{RobotCore, UserRobot}, telemetry::console::setup_tracing_subscriber};
+use crate::{
+    robots::{recovery, RobotCore, UserRobot},
+    telemetry::{
+        console::{setup_tracing_subscriber, shutdown_tracing},
+        nt4, shutdown_telemetry,
+    },
+};
+#[cfg(frc_sim)]
+use crate::{
+    driver_station,
+    replay::{set_replay_values, ReplayFrame, ReplaySource, REPLAY_PREFIX},
+    robots::{sim_clock, RobotCoreImpl},
+    telemetry::{flush_datalog, set_key_prefix},
+};
+
+/// Dashboards connect to the robot over the network, in sim only local ones can.
+#[cfg(not(frc_sim))]
+const NT4_BIND_ADDRESS: &str = "0.0.0.0";
+#[cfg(frc_sim)]
+const NT4_BIND_ADDRESS: &str = "127.0.0.1";
+
+static SHUTDOWN: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+static SIGNAL_INSTALL: Once = Once::new();
+
+/// Asks the main loop to exit after its current iteration,
+/// the program then runs [`UserRobot::end`], flushes telemetry and cleans up the HAL.
+///
+/// This is also requested by SIGTERM and SIGINT, a second signal ends the program immediately.
+pub fn request_shutdown() {
+    SHUTDOWN.store(true, Ordering::Release);
+}
+
+#[must_use]
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::Acquire)
+}
+
+fn install_signal_handlers() {
+    SIGNAL_INSTALL.call_once(|| {
+        for signal in [SIGTERM, SIGINT] {
+            // the conditional shutdown is registered first so it only fires on the second signal
+            let result = signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&SHUTDOWN))
+                .and_then(|_| signal_hook::flag::register(signal, Arc::clone(&SHUTDOWN)));
+            if let Err(e) = result {
+                tracing::warn!("Failed to register handler for signal {signal}: {e}");
+            }
+        }
+    });
+}
 
 /// Is the entry point for the robot program.
 /// 
@@ -20,14 +71,7 @@ pub fn start<HALDriver: hal::HALDriver, Core: RobotCore<Robo>, Robo: UserRobot>(
     tracing::info!("Initializing HAL");
 
     HAL::init::<HALDriver>();
-    let mut core = Core::construct();
-    if let Err(e) = catch_unwind(AssertUnwindSafe(|| core.start())) {
-        tracing::error!("User code panicked: {:?}", e);
-    } else {
-        tracing::info!("User code exited normally");
-    }
-    core.end();
-    get_hal().expect("HAL not initialized").cleanup();
+    run_core::<Core, Robo>();
 }
 
 /// Is the entry point for the robot program.
@@ -46,12 +90,97 @@ pub fn start<HALDriver: hal::SimHALDriver, Core: RobotCore<Robo>, Robo: UserRobo
     tracing::info!("Initializing HAL");
 
     HAL::init_sim::<HALDriver>();
+    run_core::<Core, Robo>();
+}
+
+/// Runs the core until it exits or panics in a way the [`RestartPolicy`](crate::robots::recovery::RestartPolicy)
+/// doesn't allow recovering from, then cleans up the HAL.
+fn run_core<Core: RobotCore<Robo>, Robo: UserRobot>() {
+    install_signal_handlers();
+    recovery::install_panic_hook();
+    if let Err(e) = nt4::start_server((NT4_BIND_ADDRESS, nt4::NT4_PORT)) {
+        tracing::warn!("Failed to start NT4 server: {e}");
+    }
     let mut core = Core::construct();
-    if let Err(e) = catch_unwind(AssertUnwindSafe(|| core.start())) {
+    while !shutdown_requested() {
+        match catch_unwind(AssertUnwindSafe(|| core.start())) {
+            Ok(()) => {
+                tracing::info!("User code exited normally");
+                break;
+            }
+            Err(e) => {
+                if recovery::recover("main loop", e.as_ref(), recovery::restart_policy().restart) {
+                    tracing::warn!("Restarting the main loop");
+                } else {
+                    tracing::error!("User code panicked: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+    core.end();
+    shutdown_telemetry();
+    get_hal().expect("HAL not initialized").cleanup();
+    shutdown_tracing();
+}
+
+/// Runs the robot program against a recorded log instead of the HAL, see [`replay`](crate::replay).
+///
+/// One iteration of the main loop is ran per recorded cycle at the recorded time,
+/// the replay runs as fast as possible and returns once the log is over.
+#[cfg(frc_sim)]
+pub fn replay<Robo: UserRobot>(mut source: impl ReplaySource) {
+    sim_clock::install_clock();
+    sim_clock::set_clock_active(true);
+    install_signal_handlers();
+    recovery::install_panic_hook();
+
+    if let Err(e) = setup_tracing_subscriber() {
+        println!("Failed to set up tracing subscriber {e:?}");
+        return;
+    }
+
+    tracing::info!("Running in replay mode");
+
+    let Some(mut frame) = source.next_frame() else {
+        tracing::warn!("Replay log has no recorded cycles");
+        return;
+    };
+
+    set_key_prefix(Some(REPLAY_PREFIX));
+    let mut core = RobotCoreImpl::<Robo>::construct();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        apply_replay_frame(frame.clone());
+        core.init();
+        while !shutdown_requested() {
+            apply_replay_frame(frame);
+            core.step_now();
+            flush_datalog();
+            match source.next_frame() {
+                Some(next) => frame = next,
+                None => break,
+            }
+        }
+    }));
+    if let Err(e) = result {
         tracing::error!("User code panicked: {:?}", e);
     } else {
-        tracing::info!("User code exited normally");
+        tracing::info!("Replay finished");
     }
     core.end();
-    get_hal().expect("HAL not initialized").cleanup();
-}
\ No newline at end of file
+    shutdown_telemetry();
+
+    set_key_prefix(None);
+    set_replay_values(None);
+    driver_station::sim::reset();
+    sim_clock::set_clock_active(false);
+    shutdown_tracing();
+}
+
+#[cfg(frc_sim)]
+fn apply_replay_frame(frame: ReplayFrame) {
+    sim_clock::set_sim_time(frame.timestamp);
+    driver_station::sim::set_station_data(frame.station);
+    driver_station::sim::set_match_info(frame.match_info);
+    set_replay_values(Some(frame.values));
+}
@@ -0,0 +1,210 @@
+use std::cmp::Reverse;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+use super::{intern_key, log};
+
+/// How bad the problem an [`Alert`] describes is, dashboards group alerts by level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AlertLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The group alerts are published in by default, the same one `WPILib` uses so dashboards find it.
+pub const DEFAULT_ALERT_GROUP: &str = "/SmartDashboard/Alerts";
+
+struct AlertState {
+    group: &'static str,
+    level: AlertLevel,
+    text: String,
+    /// When the alert became active in microseconds of uptime, none while it is inactive.
+    active_since: Option<u64>,
+}
+
+/// Every alert that hasn't been dropped, in the order they were created.
+static ALERTS: Mutex<Vec<Weak<Mutex<AlertState>>>> = Mutex::new(Vec::new());
+/// Set whenever an alert changes, so the published arrays are only rebuilt when needed.
+static ALERTS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// The arrays last published for one group.
+struct PublishedGroup {
+    group: &'static str,
+    keys: [&'static str; 4],
+    /// The text of the active errors, warnings and infos.
+    levels: [Vec<Box<str>>; 3],
+}
+
+static PUBLISHED: Mutex<Vec<PublishedGroup>> = Mutex::new(Vec::new());
+
+/// A persistent message about something wrong with the robot, like a disconnected sensor,
+/// shown to drivers on dashboards for as long as it is active.
+///
+/// Every flush of the data log publishes the text of each active alert in a group
+/// as the string arrays `<group>/errors`, `<group>/warnings` and `<group>/infos`, newest first,
+/// along with `<group>/.type` set to `Alerts` so dashboards know how to show them.
+/// Alerts are also traced at their level when they become active,
+/// so warnings and errors end up in `/console` too.
+///
+/// An alert is removed once it is dropped.
+///
+/// ```ignore
+/// let gyro_disconnected = Alert::new("Gyro disconnected, field relative is disabled", AlertLevel::Warning);
+/// // every cycle
+/// gyro_disconnected.set(!gyro.is_connected());
+/// ```
+pub struct Alert(Arc<Mutex<AlertState>>);
+
+impl Alert {
+    /// An inactive alert in the [`DEFAULT_ALERT_GROUP`].
+    #[must_use]
+    pub fn new(text: impl Into<String>, level: AlertLevel) -> Self {
+        Self::in_group(DEFAULT_ALERT_GROUP, text, level)
+    }
+
+    /// An inactive alert published under `group`, like `/Drive/Alerts`.
+    #[must_use]
+    pub fn in_group(group: &str, text: impl Into<String>, level: AlertLevel) -> Self {
+        let state = Arc::new(Mutex::new(AlertState {
+            group: intern_key(group.trim_end_matches('/')),
+            level,
+            text: text.into(),
+            active_since: None,
+        }));
+        ALERTS.lock().push(Arc::downgrade(&state));
+        ALERTS_CHANGED.store(true, Ordering::Relaxed);
+        Self(state)
+    }
+
+    /// Activates or clears the alert, cheap to call every cycle when nothing changed.
+    pub fn set(&self, active: bool) {
+        let mut state = self.0.lock();
+        if active == state.active_since.is_some() {
+            return;
+        }
+        if active {
+            state.active_since = Some(u64::from(frclib_core::units::time::Microsecond::from(
+                frclib_core::time::uptime(),
+            )));
+            match state.level {
+                AlertLevel::Info => tracing::info!(target: "alerts", "{}", state.text),
+                AlertLevel::Warning => tracing::warn!(target: "alerts", "{}", state.text),
+                AlertLevel::Error => tracing::error!(target: "alerts", "{}", state.text),
+            }
+        } else {
+            state.active_since = None;
+            tracing::debug!(target: "alerts", "Cleared: {}", state.text);
+        }
+        drop(state);
+        ALERTS_CHANGED.store(true, Ordering::Relaxed);
+    }
+
+    /// Changes the message, an active alert keeps its place in the list.
+    pub fn set_text(&self, text: impl Into<String>) {
+        let text = text.into();
+        let mut state = self.0.lock();
+        if state.text == text {
+            return;
+        }
+        state.text = text;
+        drop(state);
+        ALERTS_CHANGED.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.0.lock().active_since.is_some()
+    }
+
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.0.lock().text.clone()
+    }
+
+    #[must_use]
+    pub fn level(&self) -> AlertLevel {
+        self.0.lock().level
+    }
+}
+
+impl Drop for Alert {
+    fn drop(&mut self) {
+        // cleared before flagging the change, a rebuild racing the drop could still upgrade
+        // the weak reference and would otherwise publish the alert after the flag was consumed
+        self.0.lock().active_since = None;
+        ALERTS_CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Debug for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.0.lock();
+        f.debug_struct("Alert")
+            .field("group", &state.group)
+            .field("level", &state.level)
+            .field("text", &state.text)
+            .field("active", &state.active_since.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Rebuilds the arrays of every group from the alerts that are still alive.
+fn rebuild(published: &mut Vec<PublishedGroup>) {
+    let mut active: Vec<(&'static str, AlertLevel, u64, String)> = Vec::new();
+    ALERTS.lock().retain(|alert| {
+        let Some(alert) = alert.upgrade() else {
+            return false;
+        };
+        let state = alert.lock();
+        if let Some(since) = state.active_since {
+            active.push((state.group, state.level, since, state.text.clone()));
+        }
+        true
+    });
+    // newest first, a stable sort keeps alerts activated together in creation order
+    active.sort_by_key(|(_, _, since, _)| Reverse(*since));
+    for group in published.iter_mut() {
+        group.levels.iter_mut().for_each(Vec::clear);
+    }
+    for (group, level, _, text) in active {
+        if !published.iter().any(|published| published.group == group) {
+            published.push(PublishedGroup {
+                group,
+                keys: [
+                    intern_key(&format!("{group}/.type")),
+                    intern_key(&format!("{group}/errors")),
+                    intern_key(&format!("{group}/warnings")),
+                    intern_key(&format!("{group}/infos")),
+                ],
+                levels: Default::default(),
+            });
+        }
+        let slot = match level {
+            AlertLevel::Error => 0,
+            AlertLevel::Warning => 1,
+            AlertLevel::Info => 2,
+        };
+        if let Some(published) = published.iter_mut().find(|published| published.group == group) {
+            published.levels[slot].push(text.into_boxed_str());
+        }
+    }
+}
+
+/// Logs the active alerts of every group that has had one, called before every flush of the data log.
+pub fn log_alerts() {
+    let mut published = PUBLISHED.lock();
+    if ALERTS_CHANGED.swap(false, Ordering::Relaxed) {
+        rebuild(&mut published);
+    }
+    for group in published.iter() {
+        log(group.keys[0], "Alerts");
+        for (key, texts) in group.keys[1..].iter().zip(&group.levels) {
+            log(*key, FrcValue::StringArray(texts.as_slice().into()));
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use frclib_core::structure::FrcStructureBytes;
+use frclib_core::value::{FrcType, FrcValue};
+use parking_lot::Mutex;
+
+use super::{KeyId, TelemetryEntry};
+
+/// How many entries a thread can log between flushes before the oldest are dropped.
+static THREAD_BUFFER_CAPACITY: AtomicUsize = AtomicUsize::new(2048);
+/// How many bytes of strings, arrays and structs a thread can log between flushes without allocating.
+static THREAD_ARENA_CAPACITY: AtomicUsize = AtomicUsize::new(32 * 1024);
+/// How many entries have been dropped from full buffers since the program started.
+static DROPPED_ENTRIES: AtomicU64 = AtomicU64::new(0);
+
+/// Sets how many entries each thread can log between flushes,
+/// only affects threads that have not logged anything yet.
+///
+/// Buffers are allocated once at this size, when one is full the oldest entry is dropped.
+pub fn set_thread_buffer_capacity(capacity: usize) {
+    THREAD_BUFFER_CAPACITY.store(capacity.max(1), Ordering::Relaxed);
+}
+
+/// Sets how many bytes of strings, arrays and structs each thread can log between flushes without allocating,
+/// only affects threads that have not logged anything yet.
+///
+/// Values logged with [`log_str`](super::log_str), [`log_slice`](super::log_slice)
+/// and the struct logging functions are copied into this arena,
+/// once it is full they are allocated on the heap like any other value until the next flush.
+pub fn set_thread_arena_capacity(bytes: usize) {
+    THREAD_ARENA_CAPACITY.store(bytes, Ordering::Relaxed);
+}
+
+/// How many entries have been dropped from full thread buffers since the program started.
+#[must_use]
+pub fn dropped_entries() -> u64 {
+    DROPPED_ENTRIES.load(Ordering::Relaxed)
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An element of the array values [`log_slice`](super::log_slice) copies into the thread's arena.
+pub trait ArrayElement: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    const TYPE: FrcType;
+    #[doc(hidden)]
+    const SIZE: usize;
+
+    #[doc(hidden)]
+    fn write(self, arena: &mut Vec<u8>);
+
+    #[doc(hidden)]
+    fn to_value(values: &[Self]) -> FrcValue;
+}
+
+macro_rules! array_elements {
+    ($($element:ty => $frc_type:ident),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $element {}
+            impl ArrayElement for $element {
+                const TYPE: FrcType = FrcType::$frc_type;
+                const SIZE: usize = std::mem::size_of::<$element>();
+
+                fn write(self, arena: &mut Vec<u8>) {
+                    arena.extend_from_slice(&self.to_ne_bytes());
+                }
+
+                fn to_value(values: &[Self]) -> FrcValue {
+                    FrcValue::from(values.to_vec())
+                }
+            }
+        )*
+    };
+}
+
+array_elements!(i64 => IntArray, f32 => FloatArray, f64 => DoubleArray);
+
+impl sealed::Sealed for bool {}
+impl ArrayElement for bool {
+    const TYPE: FrcType = FrcType::BooleanArray;
+    const SIZE: usize = 1;
+
+    fn write(self, arena: &mut Vec<u8>) {
+        arena.push(u8::from(self));
+    }
+
+    fn to_value(values: &[Self]) -> FrcValue {
+        FrcValue::from(values.to_vec())
+    }
+}
+
+/// A value as it is held in a thread buffer until the next flush.
+enum BufferedValue {
+    Value(FrcValue),
+    /// Bytes in the buffer's arena, turned into a value of `frc_type` holding `count` elements when flushed.
+    Arena {
+        frc_type: FrcType,
+        count: usize,
+        bytes: Range<usize>,
+    },
+}
+
+struct BufferedEntry {
+    id: KeyId,
+    key: &'static str,
+    timestamp: u64,
+    value: BufferedValue,
+}
+
+struct Buffer {
+    entries: VecDeque<BufferedEntry>,
+    arena: Vec<u8>,
+}
+
+/// A fixed size queue of the entries one thread logged since the last flush,
+/// with a fixed size arena for the variable size values among them.
+pub(super) struct ThreadBuffer {
+    buffer: Mutex<Buffer>,
+    capacity: usize,
+}
+
+impl ThreadBuffer {
+    fn push_entry(&self, buffer: &mut Buffer, entry: BufferedEntry) {
+        if buffer.entries.len() >= self.capacity {
+            let _ = buffer.entries.pop_front();
+            let _ = DROPPED_ENTRIES.fetch_add(1, Ordering::Relaxed);
+        }
+        buffer.entries.push_back(entry);
+    }
+
+    pub(super) fn push(&self, id: KeyId, key: &'static str, timestamp: u64, value: FrcValue) {
+        let mut buffer = self.buffer.lock();
+        let value = BufferedValue::Value(value);
+        self.push_entry(&mut buffer, BufferedEntry { id, key, timestamp, value });
+        drop(buffer);
+    }
+
+    /// Copies a value `size` bytes long into the arena with `write`,
+    /// or logs the value made by `fallback` if the arena doesn't have room for it.
+    pub(super) fn push_bytes(
+        &self,
+        id: KeyId,
+        key: &'static str,
+        timestamp: u64,
+        (frc_type, count, size): (FrcType, usize, usize),
+        write: impl FnOnce(&mut Vec<u8>),
+        fallback: impl FnOnce() -> FrcValue,
+    ) {
+        let mut buffer = self.buffer.lock();
+        let start = buffer.arena.len();
+        let value = if buffer.arena.capacity() - start >= size {
+            write(&mut buffer.arena);
+            BufferedValue::Arena {
+                frc_type,
+                count,
+                bytes: start..buffer.arena.len(),
+            }
+        } else {
+            BufferedValue::Value(fallback())
+        };
+        self.push_entry(&mut buffer, BufferedEntry { id, key, timestamp, value });
+        drop(buffer);
+    }
+
+    /// Runs `func` on every entry in the buffer, with arena values turned into [`FrcValue`]s.
+    #[cfg(test)]
+    pub(super) fn for_each(&self, mut func: impl FnMut(&'static str, FrcValue)) {
+        let buffer = self.buffer.lock();
+        let mut pool = ValuePool::default();
+        for entry in &buffer.entries {
+            let value = match &entry.value {
+                BufferedValue::Value(value) => value.clone(),
+                BufferedValue::Arena { frc_type, count, bytes } => {
+                    pool.value(*frc_type, *count, &buffer.arena[bytes.clone()])
+                }
+            };
+            func(entry.key, value);
+        }
+    }
+}
+
+/// The heap allocations of the values of the last flush,
+/// reused for the values of the next flush copied out of thread arenas.
+///
+/// Values keep the same size from one flush to the next for most keys, like a pose or the states of a swerve drive,
+/// so once every buffer is in the pool copying values out of the arenas doesn't allocate.
+#[derive(Default)]
+struct ValuePool {
+    /// Unused values by their variant and length in bytes.
+    free: HashMap<(u8, usize), Vec<FrcValue>>,
+}
+
+impl ValuePool {
+    /// The variant and length in bytes of a value that holds an allocation the pool can reuse.
+    fn slot(value: &FrcValue) -> Option<(u8, usize)> {
+        Some(match value {
+            FrcValue::String(value) => (0, value.len()),
+            FrcValue::BooleanArray(values) => (1, values.len()),
+            FrcValue::IntArray(values) => (2, values.len() * 8),
+            FrcValue::FloatArray(values) => (3, values.len() * 4),
+            FrcValue::DoubleArray(values) => (4, values.len() * 8),
+            FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => (5, bytes.data.len()),
+            _ => return None,
+        })
+    }
+
+    fn recycle(&mut self, value: FrcValue) {
+        if let Some(slot) = Self::slot(&value) {
+            self.free.entry(slot).or_default().push(value);
+        }
+    }
+
+    fn take(&mut self, slot: (u8, usize)) -> Option<FrcValue> {
+        self.free.get_mut(&slot).and_then(Vec::pop)
+    }
+
+    /// Drops every value no flush has reused, so the pool holds at most one flush worth of values.
+    fn trim(&mut self) {
+        self.free.values_mut().for_each(Vec::clear);
+    }
+
+    fn array<T: Clone + Default, const N: usize>(
+        reused: Option<Box<[T]>>,
+        bytes: &[u8],
+        decode: fn([u8; N]) -> T,
+    ) -> Box<[T]> {
+        let mut values = reused.unwrap_or_else(|| vec![T::default(); bytes.len() / N].into_boxed_slice());
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(N)) {
+            if let Ok(chunk) = chunk.try_into() {
+                *value = decode(chunk);
+            }
+        }
+        values
+    }
+
+    /// Turns bytes copied into an arena back into a value, reusing an allocation of the same size if there is one.
+    fn value(&mut self, frc_type: FrcType, count: usize, bytes: &[u8]) -> FrcValue {
+        match frc_type {
+            FrcType::String => {
+                let mut string = match self.take((0, bytes.len())) {
+                    Some(FrcValue::String(string)) => string.into_boxed_bytes().into_vec(),
+                    _ => Vec::with_capacity(bytes.len()),
+                };
+                string.clear();
+                string.extend_from_slice(bytes);
+                FrcValue::String(String::from_utf8(string).unwrap_or_default().into_boxed_str())
+            }
+            FrcType::BooleanArray => {
+                let reused = match self.take((1, bytes.len())) {
+                    Some(FrcValue::BooleanArray(values)) => Some(values),
+                    _ => None,
+                };
+                FrcValue::BooleanArray(Self::array(reused, bytes, |[byte]: [u8; 1]| byte != 0))
+            }
+            FrcType::IntArray => {
+                let reused = match self.take((2, bytes.len())) {
+                    Some(FrcValue::IntArray(values)) => Some(values),
+                    _ => None,
+                };
+                FrcValue::IntArray(Self::array(reused, bytes, i64::from_ne_bytes))
+            }
+            FrcType::FloatArray => {
+                let reused = match self.take((3, bytes.len())) {
+                    Some(FrcValue::FloatArray(values)) => Some(values),
+                    _ => None,
+                };
+                FrcValue::FloatArray(Self::array(reused, bytes, f32::from_ne_bytes))
+            }
+            FrcType::DoubleArray => {
+                let reused = match self.take((4, bytes.len())) {
+                    Some(FrcValue::DoubleArray(values)) => Some(values),
+                    _ => None,
+                };
+                FrcValue::DoubleArray(Self::array(reused, bytes, f64::from_ne_bytes))
+            }
+            FrcType::Struct(desc) | FrcType::StructArray(desc) => {
+                let mut structure = match self.take((5, bytes.len())) {
+                    Some(FrcValue::Struct(structure) | FrcValue::StructArray(structure)) => structure,
+                    _ => Box::new(FrcStructureBytes::from_parts(desc, count, Box::from(bytes))),
+                };
+                structure.desc = desc;
+                structure.count = count;
+                structure.data.copy_from_slice(bytes);
+                if matches!(frc_type, FrcType::Struct(_)) {
+                    FrcValue::Struct(structure)
+                } else {
+                    FrcValue::StructArray(structure)
+                }
+            }
+            _ => FrcValue::Void,
+        }
+    }
+}
+
+/// The buffer of every thread that has logged since the last flush,
+/// a buffer is removed once its thread has exited and it has been drained.
+static THREAD_BUFFERS: Mutex<Vec<Arc<ThreadBuffer>>> = Mutex::new(Vec::new());
+
+struct Merged {
+    /// Every buffer merged in timestamp order, kept between flushes so its allocation is reused.
+    entries: Vec<TelemetryEntry>,
+    pool: Option<ValuePool>,
+}
+
+static MERGED: Mutex<Merged> = Mutex::new(Merged {
+    entries: Vec::new(),
+    pool: None,
+});
+
+pub(super) fn register_thread_buffer() -> Arc<ThreadBuffer> {
+    let capacity = THREAD_BUFFER_CAPACITY.load(Ordering::Relaxed);
+    let buffer = Arc::new(ThreadBuffer {
+        buffer: Mutex::new(Buffer {
+            entries: VecDeque::with_capacity(capacity),
+            arena: Vec::with_capacity(THREAD_ARENA_CAPACITY.load(Ordering::Relaxed)),
+        }),
+        capacity,
+    });
+    THREAD_BUFFERS.lock().push(Arc::clone(&buffer));
+    buffer
+}
+
+const fn placeholder() -> TelemetryEntry {
+    TelemetryEntry {
+        id: KeyId::PLACEHOLDER,
+        key: "",
+        timestamp: 0,
+        value: FrcValue::Void,
+    }
+}
+
+/// Merges one thread's buffer into already merged entries in place, working back from the end,
+/// then empties its arena.
+///
+/// Both are expected to be in timestamp order, entries that tie keep the merged entries first,
+/// and each side keeps its own order even if it is not sorted.
+fn merge_run(merged: &mut Vec<TelemetryEntry>, run: &mut Buffer, pool: &mut ValuePool) {
+    let Buffer { entries: run, arena } = run;
+    let mut materialize = |entry: BufferedEntry| TelemetryEntry {
+        id: entry.id,
+        key: entry.key,
+        timestamp: entry.timestamp,
+        value: match entry.value {
+            BufferedValue::Value(value) => value,
+            BufferedValue::Arena { frc_type, count, bytes } => pool.value(frc_type, count, &arena[bytes]),
+        },
+    };
+    let sorted_after = match (merged.last(), run.front()) {
+        (Some(last), Some(first)) => first.timestamp >= last.timestamp,
+        _ => true,
+    };
+    if sorted_after {
+        merged.extend(run.drain(..).map(materialize));
+    } else {
+        let mut left = merged.len();
+        merged.resize_with(left + run.len(), placeholder);
+        let mut out = merged.len();
+        while let Some(entry) = run.pop_back() {
+            while left > 0 && merged[left - 1].timestamp > entry.timestamp {
+                left -= 1;
+                out -= 1;
+                merged.swap(left, out);
+            }
+            out -= 1;
+            merged[out] = materialize(entry);
+        }
+    }
+    arena.clear();
+}
+
+/// Drains every thread buffer in timestamp order and runs `func` on the result.
+///
+/// Nothing is allocated once the merge buffer has grown to the largest flush
+/// and the value pool holds the allocations of a flush,
+/// so this must not be called again from inside `func`.
+pub(super) fn drain_all(func: impl FnOnce(&mut Vec<TelemetryEntry>)) {
+    let mut merged = MERGED.lock();
+    let Merged { entries, pool } = &mut *merged;
+    let pool = pool.get_or_insert_with(ValuePool::default);
+    THREAD_BUFFERS.lock().retain(|buffer| {
+        merge_run(entries, &mut buffer.buffer.lock(), pool);
+        // the thread local is the only other owner
+        Arc::strong_count(buffer) > 1
+    });
+    pool.trim();
+    func(entries);
+    for entry in entries.drain(..) {
+        pool.recycle(entry.value);
+    }
+    drop(merged);
+}
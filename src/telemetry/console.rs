@@ -1,10 +1,21 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-
+use serde::Deserialize;
 use tracing_subscriber::Layer;
-use tracing_subscriber::filter::FilterFn;
+use tracing_subscriber::filter::{ParseError, Targets};
 use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::prelude::*;
-use tracing_appender::non_blocking::NonBlocking;
+use tracing_subscriber::Registry;
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use parking_lot::Mutex;
+
+use super::wpilog::log_directory;
+
+/// Keeps the non-blocking writer alive, dropping it flushes any buffered logs.
+static TRACING_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
 
 #[derive(Clone, Copy)]
 struct TelemetryStringWriter(&'static str);
@@ -32,58 +43,221 @@ impl<'a> MakeWriter<'a> for TelemetryStringWriter {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, thiserror::Error)]
 pub enum TracingSetupError {
     #[error("Failed to set global default tracing subscriber")]
     SetGlobalDefault(#[from] tracing::subscriber::SetGlobalDefaultError),
     #[error("Failed to create log file")]
     CreateLogFile(std::io::Error),
+    #[error("Failed to read tracing config")]
+    ReadConfig(std::io::Error),
+    #[error("Tracing config is not valid")]
+    ParseConfig(#[from] serde_json::Error),
+    #[error("Invalid tracing filter {0:?}")]
+    InvalidFilter(String, #[source] ParseError),
 }
 
-pub fn setup_tracing_subscriber() -> Result<(), TracingSetupError> {
-    let datalog_layer = tracing_subscriber::fmt::layer()
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_line_number(true)
-        .with_file(true)
-        .with_target(true)
-        .with_level(true)
-        .json()
-        .with_writer(TelemetryStringWriter("/console"))
-        .with_filter(FilterFn::new(|metadata| {
-            metadata.level() == &tracing::Level::WARN || metadata.level() == &tracing::Level::ERROR
-        }));
-
-    #[cfg(frc_real)]
-    let (writer, _guard) = NonBlocking::new(
-        std::fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open("~/frc.log")
-            .map_err(TracingSetupError::CreateLogFile)?
-    );
-    #[cfg(frc_sim)]
-    let (writer, _guard) = NonBlocking::new(
-        std::io::stdout()
-    );
+/// How traced events are written to the console or log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Full,
+    #[default]
+    Compact,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+/// Where the log file is written and how many are kept.
+///
+/// Every boot logs to `<name>.log`, the file of the boot before is moved to `<name>.1.log`,
+/// the one before that to `<name>.2.log` and so on, until [`keep`](LogFileConfig::keep) files are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LogFileConfig {
+    /// The directory the files are written in, a leading `~` is the home directory.
+    pub directory: PathBuf,
+    pub name: String,
+    /// How many boots' files are kept, including the current one.
+    pub keep: usize,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            directory: log_directory(),
+            name: "frc".to_owned(),
+            keep: 10,
+        }
+    }
+}
+
+/// How traced events are filtered and where they are written, see [`set_tracing_config`].
+///
+/// Filters use the `RUST_LOG` syntax of a default level and levels per target,
+/// like `info,frclib::telemetry=debug,tungstenite=warn`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TracingConfig {
+    /// The events written to the console or log file.
+    pub filter: String,
+    /// The events also logged to the `/console` telemetry key, so they end up in the data log.
+    pub telemetry_filter: String,
+    pub format: LogFormat,
+    /// The log file events are written to, none writes them to stdout.
+    /// Defaults to a file in the [`log_directory`] on a robot and stdout in simulation.
+    pub file: Option<LogFileConfig>,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "trace".to_owned(),
+            telemetry_filter: "warn".to_owned(),
+            format: LogFormat::Compact,
+            file: cfg!(frc_real).then(LogFileConfig::default),
+        }
+    }
+}
+
+impl TracingConfig {
+    /// Reads a config from a JSON file, fields the file leaves out keep their defaults.
+    ///
+    /// # Errors
+    /// Returns an error if the file could not be read or is not a valid config.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TracingSetupError> {
+        let data = fs::read(path).map_err(TracingSetupError::ReadConfig)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
 
-    let filelog_layer = tracing_subscriber::fmt::layer()
+static TRACING_CONFIG: Mutex<Option<TracingConfig>> = Mutex::new(None);
+
+/// Sets the config the runtime sets up tracing with, used instead of the [`tracing_config_path`] file.
+///
+/// Has to be called before the runtime is started to have any effect.
+pub fn set_tracing_config(config: TracingConfig) {
+    *TRACING_CONFIG.lock() = Some(config);
+}
+
+/// The file tracing is configured from if it exists and [`set_tracing_config`] wasn't called,
+/// `tracing.json` in the deploy directory.
+#[must_use]
+pub fn tracing_config_path() -> PathBuf {
+    Path::new(crate::deploy_dir!()).join("tracing.json")
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_owned(),
+    }
+}
+
+/// Moves the files of earlier boots back one place and removes the ones past [`LogFileConfig::keep`],
+/// returns the path of the file for this boot.
+fn rotate_log_files(config: &LogFileConfig) -> io::Result<PathBuf> {
+    let directory = expand_home(&config.directory);
+    fs::create_dir_all(&directory)?;
+    let path = |index: usize| {
+        if index == 0 {
+            directory.join(format!("{}.log", config.name))
+        } else {
+            directory.join(format!("{}.{index}.log", config.name))
+        }
+    };
+    let keep = config.keep.max(1);
+    let mut index = keep - 1;
+    while path(index).exists() {
+        fs::remove_file(path(index))?;
+        index += 1;
+    }
+    for index in (0..keep - 1).rev() {
+        if path(index).exists() {
+            fs::rename(path(index), path(index + 1))?;
+        }
+    }
+    Ok(path(0))
+}
+
+fn parse_filter(filter: &str) -> Result<Targets, TracingSetupError> {
+    Targets::from_str(filter).map_err(|e| TracingSetupError::InvalidFilter(filter.to_owned(), e))
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn output_layer<W>(format: LogFormat, writer: W, filter: Targets) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
         .with_thread_ids(true)
         .with_thread_names(true)
         .with_line_number(true)
         .with_file(true)
         .with_target(true)
         .with_level(true)
-        .compact()
         .with_writer(writer);
+    match format {
+        LogFormat::Full => layer.with_filter(filter).boxed(),
+        LogFormat::Compact => layer.compact().with_filter(filter).boxed(),
+        LogFormat::Json => layer.json().with_filter(filter).boxed(),
+    }
+}
 
-    let subscriber = tracing_subscriber::Registry::default()
-        .with(datalog_layer)
-        .with(filelog_layer);
+/// Sets up tracing with the config set by [`set_tracing_config`], or read from [`tracing_config_path`] if it exists,
+/// or the default config.
+///
+/// The `RUST_LOG` environment variable replaces the [`filter`](TracingConfig::filter) of the config if it is set.
+/// A config file that can't be read falls back to the default config with a warning.
+///
+/// # Errors
+/// Returns an error if a filter is invalid, the log file could not be created
+/// or a subscriber was already set up.
+pub fn setup_tracing_subscriber() -> Result<(), TracingSetupError> {
+    let mut warning = None;
+    let mut config = TRACING_CONFIG.lock().clone().unwrap_or_else(|| {
+        let path = tracing_config_path();
+        if !path.exists() {
+            return TracingConfig::default();
+        }
+        TracingConfig::load(&path).unwrap_or_else(|e| {
+            warning = Some(format!("Failed to load tracing config {}, using the default: {e}", path.display()));
+            TracingConfig::default()
+        })
+    });
+    if let Ok(filter) = std::env::var("RUST_LOG") {
+        config.filter = filter;
+    }
+
+    let datalog_layer = output_layer(
+        LogFormat::Json,
+        TelemetryStringWriter("/console"),
+        parse_filter(&config.telemetry_filter)?,
+    );
+
+    let filter = parse_filter(&config.filter)?;
+    let (output_layer, guard) = if let Some(file) = &config.file {
+        let path = rotate_log_files(file).map_err(TracingSetupError::CreateLogFile)?;
+        let (writer, guard) = NonBlocking::new(fs::File::create(path).map_err(TracingSetupError::CreateLogFile)?);
+        (output_layer(config.format, writer, filter), guard)
+    } else {
+        let (writer, guard) = NonBlocking::new(std::io::stdout());
+        (output_layer(config.format, writer, filter), guard)
+    };
+
+    let subscriber = Registry::default().with(vec![datalog_layer, output_layer]);
 
     tracing::subscriber::set_global_default(subscriber)?;
+    *TRACING_GUARD.lock() = Some(guard);
+    if let Some(warning) = warning {
+        tracing::warn!("{warning}");
+    }
 
     Ok(())
+}
+
+/// Flushes the log file and stops its writer thread, anything traced after this is not written to the file.
+pub fn shutdown_tracing() {
+    drop(TRACING_GUARD.lock().take());
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+//! Wide CSV tables of telemetry for spreadsheets and dataframes.
+//!
+//! Once [`start_csv_log`] has been called every flush of the data log is written as one row
+//! of a CSV file in the [`log_directory`], with a column for every key.
+//! Rows are written to a `.csv.part` file as they come in and the finished `.csv`,
+//! with a header naming every column, is written when the log is stopped or the program shuts down.
+//! The header is also kept in a `.csv.header` file that is rewritten whenever a column is added,
+//! so if the program dies before the log is stopped the header file followed by the part file is the table.
+//!
+//! [`wpilog_to_csv`] converts a `.wpilog` file to the same layout after the fact.
+//!
+//! A key only has a value in the rows of flushes it was logged in,
+//! with pandas `df.ffill()` fills in the rows between.
+
+mod writer;
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use linkme::distributed_slice;
+use parking_lot::Mutex;
+
+pub use writer::CsvWriter;
+
+use super::wpilog::{log_directory, WpiLog};
+use super::{TELEMETRY_FINALIZERS, TELEMETRY_SINKS};
+
+struct CsvLog {
+    writer: CsvWriter<BufWriter<File>>,
+    part: PathBuf,
+    header: PathBuf,
+    path: PathBuf,
+    /// How many columns the header file names.
+    columns: usize,
+}
+
+impl CsvLog {
+    /// Rewrites the header file if columns were added since it was last written.
+    fn write_header(&mut self) -> io::Result<()> {
+        let columns = self.writer.columns().len();
+        if columns == self.columns && self.header.exists() {
+            return Ok(());
+        }
+        let temporary = self.header.with_extension("header.tmp");
+        fs::write(&temporary, self.writer.header())?;
+        fs::rename(&temporary, &self.header)?;
+        self.columns = columns;
+        Ok(())
+    }
+}
+
+static CSV_LOG: Mutex<Option<CsvLog>> = Mutex::new(None);
+
+/// Starts writing every flush of the data log to a new CSV file in the [`log_directory`],
+/// returns the path the finished file will be written to or the existing one if it was already started.
+///
+/// # Errors
+/// Returns an error if the file could not be created.
+pub fn start_csv_log() -> io::Result<PathBuf> {
+    let mut log = CSV_LOG.lock();
+    if let Some(log) = log.as_ref() {
+        return Ok(log.path.clone());
+    }
+    let directory = log_directory();
+    fs::create_dir_all(&directory)?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = directory.join(format!("frc_{started}.csv"));
+    let part = directory.join(format!("frc_{started}.csv.part"));
+    let header = directory.join(format!("frc_{started}.csv.header"));
+    tracing::info!("Writing CSV log to {}", path.display());
+    let mut started = CsvLog {
+        writer: CsvWriter::new(BufWriter::new(File::create(&part)?)),
+        part,
+        header,
+        path: path.clone(),
+        columns: 0,
+    };
+    started.write_header()?;
+    *log = Some(started);
+    drop(log);
+    Ok(path)
+}
+
+fn finish(log: CsvLog) -> io::Result<()> {
+    let header = log.writer.header();
+    drop(log.writer.into_inner()?);
+    let mut file = BufWriter::new(File::create(&log.path)?);
+    file.write_all(header.as_bytes())?;
+    let _ = io::copy(&mut BufReader::new(File::open(&log.part)?), &mut file)?;
+    file.flush()?;
+    fs::remove_file(&log.part)?;
+    fs::remove_file(&log.header)
+}
+
+/// Stops the CSV log started with [`start_csv_log`] and writes the finished file.
+pub fn stop_csv_log() {
+    let Some(log) = CSV_LOG.lock().take() else {
+        return;
+    };
+    let path = log.path.clone();
+    if let Err(e) = finish(log) {
+        tracing::error!("Failed to finish CSV log {}: {e}", path.display());
+    }
+}
+
+/// Writes a `.wpilog` file as a wide CSV table with a header, one row per `row_period` of the log.
+///
+/// Each row has the last value of every key logged in its period and is timestamped with the last of them,
+/// a `row_period` of zero gives a row for every distinct timestamp.
+/// Use the robot's loop period to get about one row per loop.
+///
+/// # Errors
+/// Returns an error if `writer` failed.
+pub fn wpilog_to_csv<W: Write>(log: &WpiLog, mut writer: W, row_period: Duration) -> io::Result<W> {
+    let period = u64::try_from(row_period.as_micros()).unwrap_or(u64::MAX);
+    let entries = log.entries();
+    let mut rows = CsvWriter::new(Vec::new());
+    let mut start = 0;
+    for index in 1..=entries.len() {
+        let next = entries.get(index).map(|entry| entry.timestamp);
+        let row_start = entries[start].timestamp;
+        let last = entries[index - 1].timestamp;
+        if next.is_some_and(|next| next <= last || next.saturating_sub(row_start) < period) {
+            continue;
+        }
+        rows.write_row(last, &entries[start..index])?;
+        start = index;
+    }
+    writer.write_all(rows.header().as_bytes())?;
+    writer.write_all(&rows.into_inner()?)?;
+    writer.flush()?;
+    Ok(writer)
+}
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static CSV_SINK: super::TelemetrySink = |entries| {
+    let mut log = CSV_LOG.lock();
+    let (Some(csv), Some(last)) = (log.as_mut(), entries.last()) else {
+        return;
+    };
+    if let Err(e) = csv
+        .writer
+        .write_row(last.timestamp, entries)
+        .and_then(|()| csv.write_header())
+    {
+        tracing::error!("Failed to write CSV log, logging to it is stopped: {e}");
+        drop(log.take());
+    }
+};
+
+#[distributed_slice(TELEMETRY_FINALIZERS)]
+static CSV_FINALIZER: super::TelemetryFinalizer = stop_csv_log;
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use frclib_core::structure::FrcStructureBytes;
+use frclib_core::value::FrcValue;
+
+use crate::telemetry::{struct_layout, KeyId, TelemetryEntry};
+
+/// Appends a value as the text of one cell, quoting it if needed.
+fn push_cell(cell: &mut String, value: &FrcValue) {
+    fn join<T>(cell: &mut String, values: &[T], push: impl Fn(&mut String, &T)) {
+        cell.push('[');
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                cell.push_str(", ");
+            }
+            push(cell, value);
+        }
+        cell.push(']');
+    }
+    fn hex(cell: &mut String, bytes: &[u8]) {
+        for byte in bytes {
+            let _ = write!(cell, "{byte:02x}");
+        }
+    }
+    let start = cell.len();
+    match value {
+        FrcValue::Void => {}
+        FrcValue::Boolean(value) => {
+            let _ = write!(cell, "{value}");
+        }
+        FrcValue::Int(value) => {
+            let _ = write!(cell, "{value}");
+        }
+        FrcValue::Double(value) => {
+            let _ = write!(cell, "{value}");
+        }
+        FrcValue::Float(value) => {
+            let _ = write!(cell, "{value}");
+        }
+        FrcValue::String(value) => cell.push_str(value),
+        FrcValue::Raw(bytes) => hex(cell, bytes),
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => hex(cell, &bytes.data),
+        FrcValue::BooleanArray(values) => join(cell, values, |cell, value| {
+            let _ = write!(cell, "{value}");
+        }),
+        FrcValue::IntArray(values) => join(cell, values, |cell, value| {
+            let _ = write!(cell, "{value}");
+        }),
+        FrcValue::FloatArray(values) => join(cell, values, |cell, value| {
+            let _ = write!(cell, "{value}");
+        }),
+        FrcValue::DoubleArray(values) => join(cell, values, |cell, value| {
+            let _ = write!(cell, "{value}");
+        }),
+        FrcValue::StringArray(values) => join(cell, values, |cell, value| cell.push_str(value)),
+    }
+    if cell[start..].contains([',', '"', '\n', '\r']) {
+        let quoted = cell[start..].replace('"', "\"\"");
+        cell.truncate(start);
+        cell.push('"');
+        cell.push_str(&quoted);
+        cell.push('"');
+    }
+}
+
+fn push_name(line: &mut String, name: &str) {
+    if name.contains([',', '"', '\n', '\r']) {
+        line.push('"');
+        line.push_str(&name.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(name);
+    }
+}
+
+/// Writes telemetry as a wide CSV table, with one column per key and one row per batch of entries.
+///
+/// Columns are added as new keys show up so the header is only known once everything is written,
+/// it is not written by this and has to be put in front of the rows with [`header`](Self::header).
+/// Rows leave out the empty cells at their end, so earlier rows are shorter than the header.
+///
+/// The first column is the timestamp of the row in seconds.
+/// Structs with a registered schema get a column per field named by its path, like `/Pose/translation/x`,
+/// and struct arrays a column per field of each element, like `/Poses/0/translation/x`.
+/// Other arrays are written as one cell.
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    names: Vec<String>,
+    /// The column of every key logged as a single value by [`KeyId`] index.
+    columns: Vec<Option<usize>>,
+    /// The columns of every key logged as structs, every field of every element in order.
+    struct_columns: HashMap<KeyId, Vec<usize>>,
+    /// The cells of the row being built, kept between rows so their allocations are reused.
+    cells: Vec<String>,
+    line: String,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            names: Vec::new(),
+            columns: Vec::new(),
+            struct_columns: HashMap::new(),
+            cells: Vec::new(),
+            line: String::new(),
+        }
+    }
+
+    /// The names of every column added so far, not including the timestamp.
+    #[must_use]
+    pub fn columns(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The header line for every column added so far, ending in a newline.
+    #[must_use]
+    pub fn header(&self) -> String {
+        let mut line = String::from("timestamp");
+        for name in &self.names {
+            line.push(',');
+            push_name(&mut line, name);
+        }
+        line.push('\n');
+        line
+    }
+
+    fn add_column(&mut self, name: String) -> usize {
+        self.names.push(name);
+        self.cells.push(String::new());
+        self.names.len() - 1
+    }
+
+    fn column(&mut self, key: KeyId, name: &str) -> usize {
+        let id = key.index();
+        if id >= self.columns.len() {
+            self.columns.resize(KeyId::count().max(id + 1), None);
+        }
+        if let Some(column) = self.columns[id] {
+            return column;
+        }
+        let column = self.add_column(name.to_owned());
+        self.columns[id] = Some(column);
+        column
+    }
+
+    /// Fills the cells of every field of a struct value, returns false if its type has no usable schema.
+    fn fill_struct(&mut self, entry: &TelemetryEntry, bytes: &FrcStructureBytes, array: bool) -> bool {
+        let key = entry.key;
+        let desc = bytes.desc;
+        let Some(layout) = struct_layout(desc) else {
+            return false;
+        };
+        let count = bytes.data.len().checked_div(desc.size).unwrap_or(0);
+        let needed = layout.len() * count;
+        let mut columns = self.struct_columns.remove(&entry.id).unwrap_or_default();
+        while columns.len() < needed {
+            let element = columns.len() / layout.len();
+            let field = &layout[columns.len() % layout.len()];
+            let name = if array {
+                format!("{key}/{element}/{}", field.name)
+            } else {
+                format!("{key}/{}", field.name)
+            };
+            columns.push(self.add_column(name));
+        }
+        for element in 0..count {
+            let data = &bytes.data[element * desc.size..(element + 1) * desc.size];
+            for (field, column) in layout.iter().zip(&columns[element * layout.len()..]) {
+                let cell = &mut self.cells[*column];
+                cell.clear();
+                if let Some(value) = field.read(data) {
+                    push_cell(cell, &value);
+                }
+            }
+        }
+        let _ = self.struct_columns.insert(entry.id, columns);
+        true
+    }
+
+    /// Writes one row with the latest value of every key in `entries`, keys not in it are left empty.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_row(&mut self, timestamp: u64, entries: &[TelemetryEntry]) -> io::Result<()> {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+        for entry in entries {
+            if entry.key.starts_with("/.schema/") {
+                continue;
+            }
+            let filled = match &entry.value {
+                FrcValue::Struct(bytes) => self.fill_struct(entry, bytes, false),
+                FrcValue::StructArray(bytes) => self.fill_struct(entry, bytes, true),
+                _ => false,
+            };
+            if !filled {
+                let column = self.column(entry.id, entry.key);
+                let cell = &mut self.cells[column];
+                cell.clear();
+                push_cell(cell, &entry.value);
+            }
+        }
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        let _ = write!(line, "{:.6}", timestamp as f64 / 1_000_000.0);
+        let last = self.cells.iter().rposition(|cell| !cell.is_empty()).map_or(0, |last| last + 1);
+        for cell in &self.cells[..last] {
+            line.push(',');
+            line.push_str(cell);
+        }
+        line.push('\n');
+        let result = self.writer.write_all(line.as_bytes());
+        self.line = line;
+        result
+    }
+
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if the final flush failed.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> std::fmt::Debug for CsvWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CsvWriter")
+            .field("columns", &self.names.len())
+            .finish_non_exhaustive()
+    }
+}
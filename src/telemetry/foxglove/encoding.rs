@@ -0,0 +1,275 @@
+//! JSON messages and JSON schemas for telemetry values, the encoding used by Foxglove channels and MCAP files.
+
+use std::io::Cursor;
+
+use frclib_core::structure::{FrcStructDesc, FrcStructure, FrcStructureBytes};
+use frclib_core::value::{FrcType, FrcValue};
+use serde_json::{json, Map, Value as Json};
+
+use crate::math::geometry::{Pose2d, Pose3d};
+use crate::telemetry::{struct_layout, StructField};
+
+/// The frame poses are published in, Foxglove's 3D panel needs one to place them.
+pub const POSE_FRAME: &str = "field";
+
+/// The schema of the messages of one channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSchema {
+    /// The name of the schema, Foxglove's own names like `foxglove.PoseInFrame` for values it knows how to show.
+    pub name: String,
+    /// The JSON schema of the messages.
+    pub schema: String,
+}
+
+fn number_schema(frc_type: FrcType) -> Json {
+    match frc_type {
+        FrcType::Boolean | FrcType::BooleanArray => json!({ "type": "boolean" }),
+        FrcType::Int | FrcType::IntArray | FrcType::Raw => json!({ "type": "integer" }),
+        FrcType::String | FrcType::StringArray => json!({ "type": "string" }),
+        _ => json!({ "type": "number" }),
+    }
+}
+
+fn field_schema(field: &StructField) -> Json {
+    match field.type_str {
+        "bool" => json!({ "type": "boolean" }),
+        "char" => json!({ "type": "string" }),
+        "float" | "float32" | "double" | "float64" => json!({ "type": "number" }),
+        _ => json!({ "type": "integer" }),
+    }
+}
+
+/// Inserts `value` into nested objects following a `/` separated path.
+fn insert_path(object: &mut Map<String, Json>, path: &str, value: Json) {
+    match path.split_once('/') {
+        Some((first, rest)) => {
+            if let Json::Object(child) = object.entry(first).or_insert_with(|| Json::Object(Map::new())) {
+                insert_path(child, rest, value);
+            }
+        }
+        None => {
+            let _ = object.insert(path.to_owned(), value);
+        }
+    }
+}
+
+fn object_schema(properties: Map<String, Json>) -> Json {
+    json!({ "type": "object", "properties": Json::Object(properties) })
+}
+
+/// The schema of an object holding `fields`, nesting the ones with a `/` in their path.
+fn fields_schema(fields: Vec<(&str, Json)>) -> Json {
+    let mut properties = Map::new();
+    let mut nested: Vec<(&str, Vec<(&str, Json)>)> = Vec::new();
+    for (path, schema) in fields {
+        match path.split_once('/') {
+            Some((first, rest)) => match nested.iter_mut().find(|(name, _)| *name == first) {
+                Some((_, fields)) => fields.push((rest, schema)),
+                None => nested.push((first, vec![(rest, schema)])),
+            },
+            None => {
+                let _ = properties.insert(path.to_owned(), schema);
+            }
+        }
+    }
+    for (name, fields) in nested {
+        let _ = properties.insert(name.to_owned(), fields_schema(fields));
+    }
+    object_schema(properties)
+}
+
+/// The schema of one struct with every field of its layout, none if it has no usable schema.
+fn struct_schema(desc: &'static FrcStructDesc) -> Option<Json> {
+    let layout = struct_layout(desc)?;
+    Some(fields_schema(
+        layout
+            .iter()
+            .map(|field| (field.name.as_str(), field_schema(field)))
+            .collect(),
+    ))
+}
+
+fn time_schema() -> Json {
+    object_schema(
+        [("sec", json!({ "type": "integer" })), ("nsec", json!({ "type": "integer" }))]
+            .into_iter()
+            .map(|(name, schema)| (name.to_owned(), schema))
+            .collect(),
+    )
+}
+
+fn pose_schema() -> Json {
+    let vector = |names: &[&str]| {
+        object_schema(
+            names
+                .iter()
+                .map(|name| ((*name).to_owned(), json!({ "type": "number" })))
+                .collect(),
+        )
+    };
+    json!({
+        "type": "object",
+        "properties": {
+            "position": vector(&["x", "y", "z"]),
+            "orientation": vector(&["x", "y", "z", "w"]),
+        }
+    })
+}
+
+fn is_pose(desc: &FrcStructDesc) -> bool {
+    desc.type_str == Pose2d::TYPE || desc.type_str == Pose3d::TYPE
+}
+
+/// The schema of the channel values of a type are published on, none for [`FrcType::Void`]
+/// and structs without a usable schema.
+///
+/// Scalars and arrays are objects with a single `value` field.
+/// [`Pose2d`] and [`Pose3d`] use `foxglove.PoseInFrame` and arrays of them `foxglove.PosesInFrame`
+/// so they can be shown in 3D scenes, other structs are objects with a field for each of theirs.
+#[must_use]
+pub fn channel_schema(frc_type: FrcType) -> Option<ChannelSchema> {
+    let (name, schema) = match frc_type {
+        FrcType::Void => return None,
+        FrcType::Struct(desc) if is_pose(desc) => (
+            "foxglove.PoseInFrame".to_owned(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "timestamp": time_schema(),
+                    "frame_id": { "type": "string" },
+                    "pose": pose_schema(),
+                }
+            }),
+        ),
+        FrcType::StructArray(desc) if is_pose(desc) => (
+            "foxglove.PosesInFrame".to_owned(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "timestamp": time_schema(),
+                    "frame_id": { "type": "string" },
+                    "poses": { "type": "array", "items": pose_schema() },
+                }
+            }),
+        ),
+        FrcType::Struct(desc) => (desc.type_str.to_owned(), struct_schema(desc)?),
+        FrcType::StructArray(desc) => (
+            format!("{}[]", desc.type_str),
+            json!({
+                "type": "object",
+                "properties": { "value": { "type": "array", "items": struct_schema(desc)? } }
+            }),
+        ),
+        FrcType::BooleanArray
+        | FrcType::IntArray
+        | FrcType::FloatArray
+        | FrcType::DoubleArray
+        | FrcType::StringArray
+        | FrcType::Raw => (
+            format!("frclib.{frc_type:?}"),
+            json!({
+                "type": "object",
+                "properties": { "value": { "type": "array", "items": number_schema(frc_type) } }
+            }),
+        ),
+        _ => (
+            format!("frclib.{frc_type:?}"),
+            json!({ "type": "object", "properties": { "value": number_schema(frc_type) } }),
+        ),
+    };
+    Some(ChannelSchema {
+        name,
+        schema: schema.to_string(),
+    })
+}
+
+fn number(value: f64) -> Json {
+    serde_json::Number::from_f64(value).map_or(Json::Null, Json::Number)
+}
+
+fn struct_json(layout: &[StructField], data: &[u8]) -> Json {
+    let mut object = Map::new();
+    for field in layout {
+        let value = match field.read(data) {
+            Some(FrcValue::Boolean(value)) => Json::Bool(value),
+            Some(FrcValue::Int(value)) => Json::from(value),
+            Some(FrcValue::Float(value)) => number(f64::from(value)),
+            Some(FrcValue::Double(value)) => number(value),
+            Some(FrcValue::String(value)) => Json::from(&*value),
+            _ => Json::Null,
+        };
+        insert_path(&mut object, &field.name, value);
+    }
+    Json::Object(object)
+}
+
+fn pose_json(bytes: &FrcStructureBytes, data: &[u8]) -> Json {
+    let mut cursor = Cursor::new(data);
+    let (position, orientation) = if bytes.desc.type_str == Pose3d::TYPE {
+        let pose = Pose3d::unpack(&mut cursor);
+        let q = pose.rotation.q;
+        (
+            [pose.translation.x.value(), pose.translation.y.value(), pose.translation.z.value()],
+            [q.i, q.j, q.k, q.w],
+        )
+    } else {
+        let pose = Pose2d::unpack(&mut cursor);
+        let half = pose.rotation.value.value() / 2.0;
+        (
+            [pose.translation.x.value(), pose.translation.y.value(), 0.0],
+            [0.0, 0.0, half.sin(), half.cos()],
+        )
+    };
+    json!({
+        "position": { "x": number(position[0]), "y": number(position[1]), "z": number(position[2]) },
+        "orientation": {
+            "x": number(orientation[0]),
+            "y": number(orientation[1]),
+            "z": number(orientation[2]),
+            "w": number(orientation[3]),
+        },
+    })
+}
+
+/// Every struct packed in `bytes`.
+fn elements(bytes: &FrcStructureBytes) -> impl Iterator<Item = &[u8]> {
+    bytes.data.chunks_exact(bytes.desc.size.max(1))
+}
+
+/// Encodes a value as a message matching its [`channel_schema`], `timestamp` is in microseconds.
+#[must_use]
+pub fn encode_message(value: &FrcValue, timestamp: u64) -> Option<Json> {
+    fn array<T>(values: &[T], convert: impl Fn(&T) -> Json) -> Json {
+        json!({ "value": values.iter().map(convert).collect::<Vec<_>>() })
+    }
+    let stamp = || json!({ "sec": timestamp / 1_000_000, "nsec": (timestamp % 1_000_000) * 1000 });
+    Some(match value {
+        FrcValue::Void => return None,
+        FrcValue::Boolean(value) => json!({ "value": value }),
+        FrcValue::Int(value) => json!({ "value": value }),
+        FrcValue::Double(value) => json!({ "value": number(*value) }),
+        FrcValue::Float(value) => json!({ "value": number(f64::from(*value)) }),
+        FrcValue::String(value) => json!({ "value": &**value }),
+        FrcValue::Raw(bytes) => array(bytes, |byte| Json::from(*byte)),
+        FrcValue::BooleanArray(values) => array(values, |value| Json::Bool(*value)),
+        FrcValue::IntArray(values) => array(values, |value| Json::from(*value)),
+        FrcValue::FloatArray(values) => array(values, |value| number(f64::from(*value))),
+        FrcValue::DoubleArray(values) => array(values, |value| number(*value)),
+        FrcValue::StringArray(values) => array(values, |value| Json::from(&**value)),
+        FrcValue::Struct(bytes) if is_pose(bytes.desc) => json!({
+            "timestamp": stamp(),
+            "frame_id": POSE_FRAME,
+            "pose": pose_json(bytes, &bytes.data),
+        }),
+        FrcValue::StructArray(bytes) if is_pose(bytes.desc) => json!({
+            "timestamp": stamp(),
+            "frame_id": POSE_FRAME,
+            "poses": elements(bytes).map(|data| pose_json(bytes, data)).collect::<Vec<_>>(),
+        }),
+        FrcValue::Struct(bytes) => struct_json(&struct_layout(bytes.desc)?, &bytes.data),
+        FrcValue::StructArray(bytes) => {
+            let layout = struct_layout(bytes.desc)?;
+            json!({ "value": elements(bytes).map(|data| struct_json(&layout, data)).collect::<Vec<_>>() })
+        }
+    })
+}
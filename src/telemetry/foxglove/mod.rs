@@ -0,0 +1,511 @@
+//! A Foxglove WebSocket server for viewing telemetry live in Foxglove.
+//!
+//! Everything flushed through the data log is advertised as a channel named after its key,
+//! with JSON messages and a JSON schema generated from its type, see [`channel_schema`].
+//! [`Pose2d`](crate::math::geometry::Pose2d) and [`Pose3d`](crate::math::geometry::Pose3d) values
+//! use Foxglove's own pose schemas in the [`POSE_FRAME`] frame, so they show up in the 3D panel
+//! once its display frame is set to it.
+//!
+//! Each client has a bounded queue of messages, when a message doesn't fit only the latest message
+//! of its channel is sent once the client catches up, clients that stay behind are disconnected.
+//!
+//! The server isn't started by the runtime, call [`start_foxglove_server`] with [`FOXGLOVE_PORT`]
+//! and connect to `ws://<robot>:8765` with the Foxglove WebSocket connection.
+
+mod encoding;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use frclib_core::value::FrcType;
+use linkme::distributed_slice;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value as Json};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::http::HeaderValue;
+use tungstenite::{Message, WebSocket};
+
+pub use encoding::{channel_schema, encode_message, ChannelSchema, POSE_FRAME};
+
+use super::{TelemetryEntry, TELEMETRY_FINALIZERS, TELEMETRY_SINKS};
+
+/// The port Foxglove connects to by default.
+pub const FOXGLOVE_PORT: u16 = 8765;
+
+const SUBPROTOCOL: &str = "foxglove.websocket.v1";
+/// The opcode of a binary message carrying a message of a subscribed channel.
+const MESSAGE_DATA: u8 = 0x01;
+
+/// How long a client connection waits for a message before sending anything queued for it.
+const CLIENT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How many messages can be queued for a client before they start being coalesced.
+const CLIENT_QUEUE_DEPTH: usize = 1024;
+/// How many publishes in a row a client can miss messages in before it is disconnected.
+const MAX_LAGGING_PUBLISHES: u32 = 100;
+
+struct Channel {
+    id: u64,
+    frc_type: FrcType,
+    /// The channel as it is listed in an `advertise` message.
+    advertisement: Json,
+    /// The timestamp in nanoseconds and payload of the latest message, sent to new subscribers.
+    latest: Option<(u64, Vec<u8>)>,
+}
+
+struct Client {
+    sender: SyncSender<Message>,
+    /// The channel of every subscription by its id.
+    subscriptions: HashMap<u32, u64>,
+    /// Subscriptions whose latest message did not fit in the client's queue.
+    stale: HashSet<u32>,
+    /// How many publishes in a row left messages stale.
+    lagging_publishes: u32,
+    /// Whether a message that can't be coalesced did not fit, the client is disconnected after the publish.
+    behind: bool,
+}
+
+impl Client {
+    fn new(sender: SyncSender<Message>) -> Self {
+        Self {
+            sender,
+            subscriptions: HashMap::new(),
+            stale: HashSet::new(),
+            lagging_publishes: 0,
+            behind: false,
+        }
+    }
+
+    fn send_message(&mut self, subscription: u32, timestamp: u64, payload: &[u8]) {
+        let mut data = Vec::with_capacity(13 + payload.len());
+        data.push(MESSAGE_DATA);
+        data.extend_from_slice(&subscription.to_le_bytes());
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        data.extend_from_slice(payload);
+        match self.sender.try_send(Message::Binary(data)) {
+            Ok(()) => {
+                let _ = self.stale.remove(&subscription);
+            }
+            Err(TrySendError::Full(_)) => {
+                let _ = self.stale.insert(subscription);
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    fn send_text(&mut self, message: Message) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(message) {
+            self.behind = true;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Subscription {
+    id: u32,
+    #[serde(rename = "channelId")]
+    channel_id: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum ClientMessage {
+    Subscribe {
+        subscriptions: Vec<Subscription>,
+    },
+    Unsubscribe {
+        #[serde(rename = "subscriptionIds")]
+        subscription_ids: Vec<u32>,
+    },
+}
+
+fn advertise(channels: &[Json]) -> Message {
+    Message::Text(json!({ "op": "advertise", "channels": channels }).to_string())
+}
+
+#[derive(Default)]
+struct ServerInner {
+    channels: HashMap<&'static str, Channel>,
+    clients: HashMap<u64, Client>,
+    next_channel_id: u64,
+    next_client_id: u64,
+}
+
+impl ServerInner {
+    /// The channel of a key, creating and advertising it if it does not exist yet.
+    /// Returns none if the channel exists with a different type or the type can't be published.
+    fn channel<'a>(
+        channels: &'a mut HashMap<&'static str, Channel>,
+        clients: &mut HashMap<u64, Client>,
+        next_channel_id: &mut u64,
+        key: &'static str,
+        frc_type: FrcType,
+    ) -> Option<&'a mut Channel> {
+        if !channels.contains_key(key) {
+            let schema = channel_schema(frc_type)?;
+            let advertisement = json!({
+                "id": *next_channel_id,
+                "topic": key,
+                "encoding": "json",
+                "schemaName": schema.name,
+                "schema": schema.schema,
+                "schemaEncoding": "jsonschema",
+            });
+            for client in clients.values_mut() {
+                client.send_text(advertise(std::slice::from_ref(&advertisement)));
+            }
+            let _ = channels.insert(
+                key,
+                Channel {
+                    id: *next_channel_id,
+                    frc_type,
+                    advertisement,
+                    latest: None,
+                },
+            );
+            *next_channel_id += 1;
+        }
+        channels
+            .get_mut(key)
+            .filter(|channel| channel.frc_type == frc_type)
+    }
+
+    fn publish(&mut self, entries: &[TelemetryEntry]) {
+        for entry in entries {
+            // schemas are only needed by readers of the struct format
+            if entry.key.starts_with("/.schema/") {
+                continue;
+            }
+            let Some(payload) = encode_message(&entry.value, entry.timestamp)
+                .and_then(|message| serde_json::to_vec(&message).ok())
+            else {
+                continue;
+            };
+            let frc_type = entry.value.get_type();
+            let Self {
+                channels,
+                clients,
+                next_channel_id,
+                ..
+            } = self;
+            let Some(channel) = Self::channel(channels, clients, next_channel_id, entry.key, frc_type) else {
+                tracing::debug!("Not publishing {} to Foxglove, its type changed to {frc_type:?}", entry.key);
+                continue;
+            };
+            let timestamp = entry.timestamp.saturating_mul(1000);
+            for client in clients.values_mut() {
+                let subscriptions: Vec<u32> = client
+                    .subscriptions
+                    .iter()
+                    .filter(|(_, channel_id)| **channel_id == channel.id)
+                    .map(|(&subscription, _)| subscription)
+                    .collect();
+                for subscription in subscriptions {
+                    client.send_message(subscription, timestamp, &payload);
+                }
+            }
+            channel.latest = Some((timestamp, payload));
+        }
+        self.catch_up();
+    }
+
+    /// Sends the latest message of every subscription that did not fit in its client's queue,
+    /// disconnecting clients that have fallen too far behind.
+    fn catch_up(&mut self) {
+        let Self { channels, clients, .. } = self;
+        if clients.values().all(|client| client.stale.is_empty() && !client.behind) {
+            return;
+        }
+        let latest: HashMap<u64, &(u64, Vec<u8>)> = channels
+            .values()
+            .filter_map(|channel| Some((channel.id, channel.latest.as_ref()?)))
+            .collect();
+        clients.retain(|id, client| {
+            for subscription in std::mem::take(&mut client.stale) {
+                let message = client
+                    .subscriptions
+                    .get(&subscription)
+                    .and_then(|channel| latest.get(channel));
+                if let Some((timestamp, payload)) = message {
+                    client.send_message(subscription, *timestamp, payload);
+                }
+            }
+            if client.stale.is_empty() {
+                client.lagging_publishes = 0;
+            } else {
+                client.lagging_publishes += 1;
+            }
+            if client.behind || client.lagging_publishes > MAX_LAGGING_PUBLISHES {
+                // dropping the sender tells the client thread to disconnect
+                tracing::warn!("Disconnecting Foxglove client {id}, it fell too far behind");
+                return false;
+            }
+            true
+        });
+    }
+
+    fn handle_text(&mut self, client_id: u64, message: ClientMessage) {
+        let Some(client) = self.clients.get_mut(&client_id) else {
+            return;
+        };
+        match message {
+            ClientMessage::Subscribe { subscriptions } => {
+                for subscription in subscriptions {
+                    let _ = client
+                        .subscriptions
+                        .insert(subscription.id, subscription.channel_id);
+                    let latest = self
+                        .channels
+                        .values()
+                        .find(|channel| channel.id == subscription.channel_id)
+                        .and_then(|channel| channel.latest.as_ref());
+                    if let Some((timestamp, payload)) = latest {
+                        client.send_message(subscription.id, *timestamp, payload);
+                    }
+                }
+            }
+            ClientMessage::Unsubscribe { subscription_ids } => {
+                for id in subscription_ids {
+                    let _ = client.subscriptions.remove(&id);
+                }
+            }
+        }
+    }
+}
+
+struct ServerState {
+    inner: Mutex<ServerInner>,
+    running: AtomicBool,
+}
+
+/// A Foxglove WebSocket server, see the [module docs](self).
+///
+/// The server runs on its own threads, one to accept connections and one per client,
+/// dropping it disconnects every client and stops listening.
+pub struct FoxgloveServer {
+    state: Arc<ServerState>,
+    address: SocketAddr,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl FoxgloveServer {
+    /// Starts listening for clients on an address, port 0 picks any free port.
+    ///
+    /// # Errors
+    /// Returns an error if the address could not be bound.
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            inner: Mutex::new(ServerInner::default()),
+            running: AtomicBool::new(true),
+        });
+        let accept_state = Arc::clone(&state);
+        let accept_thread = std::thread::Builder::new()
+            .name("foxglove-accept".to_owned())
+            .spawn(move || accept_clients(&accept_state, &listener))?;
+        tracing::info!("Foxglove server listening on {address}");
+        Ok(Self {
+            state,
+            address,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Publishes entries as messages on their channels, advertising any channels that don't exist yet.
+    pub fn publish(&self, entries: &[TelemetryEntry]) {
+        self.state.inner.lock().publish(entries);
+    }
+
+    #[must_use]
+    pub fn client_count(&self) -> usize {
+        self.state.inner.lock().clients.len()
+    }
+}
+
+impl Drop for FoxgloveServer {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Release);
+        if let Some(thread) = self.accept_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Debug for FoxgloveServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FoxgloveServer")
+            .field("address", &self.address)
+            .field("clients", &self.client_count())
+            .finish_non_exhaustive()
+    }
+}
+
+fn accept_clients(state: &Arc<ServerState>, listener: &TcpListener) {
+    while state.running.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, address)) => {
+                let client_state = Arc::clone(state);
+                let spawned = std::thread::Builder::new()
+                    .name(format!("foxglove-client-{address}"))
+                    .spawn(move || {
+                        if let Err(e) = serve_client(&client_state, stream) {
+                            tracing::debug!("Foxglove client {address} disconnected: {e}");
+                        }
+                    });
+                if let Err(e) = spawned {
+                    tracing::warn!("Failed to spawn Foxglove client thread: {e}");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL_INTERVAL),
+            Err(e) => {
+                tracing::warn!("Failed to accept Foxglove client: {e}");
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+// tungstenite errors are large but only returned once per connection
+#[allow(clippy::result_large_err)]
+fn handshake(stream: TcpStream) -> Result<WebSocket<TcpStream>, tungstenite::Error> {
+    tungstenite::accept_hdr(stream, |_: &Request, mut response: Response| {
+        let _ = response
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(SUBPROTOCOL));
+        Ok(response)
+    })
+    .map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+        }
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn serve_client(state: &ServerState, stream: TcpStream) -> Result<(), tungstenite::Error> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let mut websocket = handshake(stream)?;
+    websocket.get_ref().set_read_timeout(Some(CLIENT_POLL_INTERVAL))?;
+
+    let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE_DEPTH);
+    let client_id = {
+        let mut inner = state.inner.lock();
+        let id = inner.next_client_id;
+        inner.next_client_id += 1;
+        let _ = sender.send(Message::Text(
+            json!({
+                "op": "serverInfo",
+                "name": "frclib",
+                "capabilities": [],
+                "supportedEncodings": [],
+                "metadata": {},
+                "sessionId": format!("{}", std::process::id()),
+            })
+            .to_string(),
+        ));
+        let channels: Vec<Json> = inner
+            .channels
+            .values()
+            .map(|channel| channel.advertisement.clone())
+            .collect();
+        if !channels.is_empty() {
+            let _ = sender.send(advertise(&channels));
+        }
+        let _ = inner.clients.insert(id, Client::new(sender));
+        id
+    };
+    tracing::info!("Foxglove client {client_id} connected");
+    let result = client_loop(state, client_id, &mut websocket, &receiver);
+    let _ = state.inner.lock().clients.remove(&client_id);
+    tracing::info!("Foxglove client {client_id} disconnected");
+    result
+}
+
+#[allow(clippy::result_large_err)]
+fn client_loop(
+    state: &ServerState,
+    client_id: u64,
+    websocket: &mut WebSocket<TcpStream>,
+    receiver: &Receiver<Message>,
+) -> Result<(), tungstenite::Error> {
+    while state.running.load(Ordering::Acquire) {
+        loop {
+            match receiver.try_recv() {
+                Ok(message) => websocket.write(message)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = websocket.close(None);
+                    let _ = websocket.flush();
+                    return Ok(());
+                }
+            }
+        }
+        websocket.flush()?;
+
+        match websocket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(message) => state.inner.lock().handle_text(client_id, message),
+                Err(e) => tracing::debug!("Unsupported Foxglove message: {e}"),
+            },
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let _ = websocket.close(None);
+    let _ = websocket.flush();
+    Ok(())
+}
+
+static GLOBAL_SERVER: Mutex<Option<FoxgloveServer>> = Mutex::new(None);
+
+/// Starts the server every flush of the data log is published to,
+/// returns the address it is listening on or the existing address if it was already started.
+///
+/// # Errors
+/// Returns an error if the address could not be bound.
+pub fn start_foxglove_server(address: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    let mut server = GLOBAL_SERVER.lock();
+    if let Some(server) = server.as_ref() {
+        return Ok(server.local_addr());
+    }
+    let started = FoxgloveServer::bind(address)?;
+    let address = started.local_addr();
+    *server = Some(started);
+    drop(server);
+    Ok(address)
+}
+
+/// Stops the server started with [`start_foxglove_server`], disconnecting every client.
+pub fn stop_foxglove_server() {
+    drop(GLOBAL_SERVER.lock().take());
+}
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static FOXGLOVE_SINK: super::TelemetrySink = |entries| {
+    if let Some(server) = GLOBAL_SERVER.lock().as_ref() {
+        server.publish(entries);
+    }
+};
+
+#[distributed_slice(TELEMETRY_FINALIZERS)]
+static FOXGLOVE_FINALIZER: super::TelemetryFinalizer = stop_foxglove_server;
@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use parking_lot::RwLock;
+
+/// A telemetry key interned into a small integer, cheap to copy, compare and hash.
+///
+/// Every key gets the same id for the life of the program,
+/// so consumers can index per key state with [`KeyId::index`] instead of hashing strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyId(u32);
+
+#[derive(Default)]
+struct KeyTable {
+    ids: HashMap<&'static str, KeyId>,
+    names: Vec<&'static str>,
+}
+
+static KEY_TABLE: LazyLock<RwLock<KeyTable>> = LazyLock::new(|| RwLock::new(KeyTable::default()));
+
+thread_local! {
+    /// Ids of static keys by address, so logging the same literal again doesn't hash the string.
+    static KEY_CACHE: RefCell<HashMap<(usize, usize), KeyId>> = RefCell::new(HashMap::new());
+}
+
+impl KeyId {
+    /// An id no key has, for entries that are about to be overwritten.
+    pub(super) const PLACEHOLDER: Self = Self(u32::MAX);
+
+    /// The id of a static key, looked up by address after the first time a thread sees it.
+    #[must_use]
+    pub fn of(key: &'static str) -> Self {
+        let address = (key.as_ptr() as usize, key.len());
+        KEY_CACHE.with(|cache| {
+            if let Some(id) = cache.borrow().get(&address) {
+                return *id;
+            }
+            let id = Self::insert(key, || key);
+            let _ = cache.borrow_mut().insert(address, id);
+            id
+        })
+    }
+
+    /// The id of a key built at runtime, the key is leaked the first time it is seen
+    /// so this should only be used for a bounded set of keys.
+    #[must_use]
+    pub fn intern(key: &str) -> Self {
+        Self::insert(key, || Box::leak(Box::from(key)))
+    }
+
+    /// The id of a key if it has been used before, unlike [`intern`](Self::intern) this never adds a key.
+    #[must_use]
+    pub fn lookup(key: &str) -> Option<Self> {
+        KEY_TABLE.read().ids.get(key).copied()
+    }
+
+    fn insert(key: &str, leak: impl FnOnce() -> &'static str) -> Self {
+        if let Some(id) = KEY_TABLE.read().ids.get(key) {
+            return *id;
+        }
+        let mut table = KEY_TABLE.write();
+        if let Some(id) = table.ids.get(key) {
+            return *id;
+        }
+        let name = leak();
+        let id = Self(u32::try_from(table.names.len()).expect("Too many telemetry keys"));
+        table.names.push(name);
+        let _ = table.ids.insert(name, id);
+        id
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        KEY_TABLE.read().names[self.index()]
+    }
+
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// How many keys have been interned, every id's index is less than this.
+    #[must_use]
+    pub fn count() -> usize {
+        KEY_TABLE.read().names.len()
+    }
+}
+
+/// Returns a static version of a key, see [`KeyId::intern`].
+pub fn intern_key(key: &str) -> &'static str {
+    KeyId::intern(key).name()
+}
@@ -0,0 +1,99 @@
+//! Exporting telemetry as MCAP files for Foxglove and other robotics tools.
+//!
+//! Once [`start_mcap_log`] has been called every flush of the data log is written to an `.mcap` file
+//! in the [`log_directory`], with a channel per key and JSON messages
+//! in the same encoding the [Foxglove server](super::foxglove) uses,
+//! so poses can be shown in Foxglove's 3D panel.
+//! The file is finished when the log is stopped or the program shuts down.
+//!
+//! [`wpilog_to_mcap`] converts a `.wpilog` file after the fact.
+//!
+//! Only JSON schemas are written, flatbuffer and protobuf encodings are not supported.
+
+mod writer;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use linkme::distributed_slice;
+use parking_lot::Mutex;
+
+pub use writer::McapWriter;
+
+use super::wpilog::{log_directory, WpiLog};
+use super::{TELEMETRY_FINALIZERS, TELEMETRY_SINKS};
+
+/// The magic bytes every MCAP file starts and ends with, version 0.
+pub const MCAP_MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+
+struct McapLog {
+    writer: McapWriter<BufWriter<File>>,
+    path: PathBuf,
+}
+
+static MCAP_LOG: Mutex<Option<McapLog>> = Mutex::new(None);
+
+/// Starts writing every flush of the data log to a new MCAP file in the [`log_directory`],
+/// returns its path or the existing one if it was already started.
+///
+/// # Errors
+/// Returns an error if the file could not be created.
+pub fn start_mcap_log() -> io::Result<PathBuf> {
+    let mut log = MCAP_LOG.lock();
+    if let Some(log) = log.as_ref() {
+        return Ok(log.path.clone());
+    }
+    let directory = log_directory();
+    fs::create_dir_all(&directory)?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = directory.join(format!("frc_{started}.mcap"));
+    tracing::info!("Writing MCAP log to {}", path.display());
+    *log = Some(McapLog {
+        writer: McapWriter::new(BufWriter::new(File::create(&path)?))?,
+        path: path.clone(),
+    });
+    drop(log);
+    Ok(path)
+}
+
+/// Stops the MCAP log started with [`start_mcap_log`] and finishes the file.
+pub fn stop_mcap_log() {
+    let Some(log) = MCAP_LOG.lock().take() else {
+        return;
+    };
+    if let Err(e) = log.writer.finish() {
+        tracing::error!("Failed to finish MCAP log {}: {e}", log.path.display());
+    }
+}
+
+/// Writes every entry of a `.wpilog` file as an MCAP file.
+///
+/// # Errors
+/// Returns an error if `writer` failed.
+pub fn wpilog_to_mcap<W: Write>(log: &WpiLog, writer: W) -> io::Result<W> {
+    let mut mcap = McapWriter::new(writer)?;
+    for entry in log.entries() {
+        mcap.append(entry)?;
+    }
+    mcap.finish()
+}
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static MCAP_SINK: super::TelemetrySink = |entries| {
+    let mut log = MCAP_LOG.lock();
+    let Some(mcap) = log.as_mut() else {
+        return;
+    };
+    if let Err(e) = entries.iter().try_for_each(|entry| mcap.writer.append(entry)) {
+        tracing::error!("Failed to write MCAP log, logging to it is stopped: {e}");
+        drop(log.take());
+    }
+};
+
+#[distributed_slice(TELEMETRY_FINALIZERS)]
+static MCAP_FINALIZER: super::TelemetryFinalizer = stop_mcap_log;
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use frclib_core::value::FrcType;
+
+use super::MCAP_MAGIC;
+use crate::telemetry::foxglove::{channel_schema, encode_message};
+use crate::telemetry::{KeyId, TelemetryEntry};
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_STATISTICS: u8 = 0x0B;
+const OP_DATA_END: u8 = 0x0F;
+
+#[allow(clippy::cast_possible_truncation)]
+fn push_str(buffer: &mut Vec<u8>, string: &str) {
+    buffer.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+/// Writes telemetry as an MCAP file with JSON messages, readable by Foxglove and the `mcap` tools.
+///
+/// Each key gets its own channel, with a schema generated from its type by
+/// [`channel_schema`](crate::telemetry::foxglove::channel_schema) and shared by every channel of that type.
+/// If the type of a key changes a new channel is started on the same topic.
+/// Values of types without a usable schema and the struct schemas of a data log are skipped.
+///
+/// Messages are written unchunked and without indexes as they come in,
+/// the schemas, channels and statistics are repeated in the summary when the file is finished.
+/// A file that was never finished is missing its summary and footer,
+/// most readers can still read its messages.
+pub struct McapWriter<W: Write> {
+    writer: W,
+    /// How many bytes have been written, where the summary starts once the data is done.
+    position: u64,
+    /// The id of every schema by name.
+    schemas: HashMap<String, u16>,
+    /// The channel id, type and sequence number of every key by [`KeyId`] index.
+    channels: Vec<Option<(u16, FrcType, u32)>>,
+    /// Every schema and channel record written, repeated in the summary.
+    summary: Vec<u8>,
+    message_count: u64,
+    message_counts: BTreeMap<u16, u64>,
+    time_range: Option<(u64, u64)>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> McapWriter<W> {
+    /// Writes the magic and file header.
+    ///
+    /// # Errors
+    /// Returns an error if the header could not be written.
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut this = Self {
+            writer,
+            position: 0,
+            schemas: HashMap::new(),
+            channels: Vec::new(),
+            summary: Vec::new(),
+            message_count: 0,
+            message_counts: BTreeMap::new(),
+            time_range: None,
+            buffer: Vec::new(),
+        };
+        this.write_all(MCAP_MAGIC)?;
+        let mut header = Vec::new();
+        push_str(&mut header, "");
+        push_str(&mut header, "frclib");
+        this.write_record(OP_HEADER, &header)?;
+        Ok(this)
+    }
+
+    /// Appends a message for an entry, writing its schema and channel first if needed.
+    /// Timestamps are in microseconds, they are written as nanoseconds like MCAP expects.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn append(&mut self, entry: &TelemetryEntry) -> io::Result<()> {
+        // schemas are only needed by readers of the struct format
+        if entry.key.starts_with("/.schema/") {
+            return Ok(());
+        }
+        let Some(message) = encode_message(&entry.value, entry.timestamp) else {
+            return Ok(());
+        };
+        let frc_type = entry.value.get_type();
+        let key = entry.id.index();
+        if key >= self.channels.len() {
+            self.channels.resize(KeyId::count().max(key + 1), None);
+        }
+        let (channel, sequence) = match self.channels[key] {
+            Some((id, existing, sequence)) if existing == frc_type => (id, sequence),
+            _ => {
+                let Some(id) = self.start_channel(entry.key, frc_type)? else {
+                    return Ok(());
+                };
+                (id, 0)
+            }
+        };
+        self.channels[key] = Some((channel, frc_type, sequence.wrapping_add(1)));
+
+        let time = entry.timestamp.saturating_mul(1000);
+        let mut record = std::mem::take(&mut self.buffer);
+        record.clear();
+        record.extend_from_slice(&channel.to_le_bytes());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        record.extend_from_slice(&time.to_le_bytes());
+        record.extend_from_slice(&time.to_le_bytes());
+        serde_json::to_writer(&mut record, &message)?;
+        let result = self.write_record(OP_MESSAGE, &record);
+        self.buffer = record;
+        result?;
+
+        self.message_count += 1;
+        *self.message_counts.entry(channel).or_default() += 1;
+        self.time_range = Some(match self.time_range {
+            Some((start, end)) => (start.min(time), end.max(time)),
+            None => (time, time),
+        });
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Writes the summary and footer that end the file, then flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_record(OP_DATA_END, &0u32.to_le_bytes())?;
+        let summary_start = self.position;
+        let summary = std::mem::take(&mut self.summary);
+        self.write_all(&summary)?;
+
+        let (start, end) = self.time_range.unwrap_or_default();
+        let mut statistics = Vec::new();
+        statistics.extend_from_slice(&self.message_count.to_le_bytes());
+        statistics.extend_from_slice(&(self.schemas.len() as u16).to_le_bytes());
+        statistics.extend_from_slice(&(self.message_counts.len() as u32).to_le_bytes());
+        // attachments, metadata and chunks
+        statistics.extend_from_slice(&[0; 12]);
+        statistics.extend_from_slice(&start.to_le_bytes());
+        statistics.extend_from_slice(&end.to_le_bytes());
+        statistics.extend_from_slice(&((self.message_counts.len() * 10) as u32).to_le_bytes());
+        for (channel, count) in &self.message_counts {
+            statistics.extend_from_slice(&channel.to_le_bytes());
+            statistics.extend_from_slice(&count.to_le_bytes());
+        }
+        self.write_record(OP_STATISTICS, &statistics)?;
+
+        let mut footer = Vec::with_capacity(20);
+        footer.extend_from_slice(&summary_start.to_le_bytes());
+        // no summary offsets and no crc
+        footer.extend_from_slice(&[0; 12]);
+        self.write_record(OP_FOOTER, &footer)?;
+        self.write_all(MCAP_MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a channel for a key and the schema of its type if it is new,
+    /// none if the type has no usable schema or every channel id is taken.
+    fn start_channel(&mut self, key: &str, frc_type: FrcType) -> io::Result<Option<u16>> {
+        let Some(schema) = channel_schema(frc_type) else {
+            return Ok(None);
+        };
+        let Ok(channel) = u16::try_from(self.channel_count()) else {
+            return Ok(None);
+        };
+        let schema_id = if let Some(id) = self.schemas.get(&schema.name) {
+            *id
+        } else {
+            // schema id 0 means a channel has no schema
+            let Ok(id) = u16::try_from(self.schemas.len() + 1) else {
+                return Ok(None);
+            };
+            let mut record = Vec::new();
+            record.extend_from_slice(&id.to_le_bytes());
+            push_str(&mut record, &schema.name);
+            push_str(&mut record, "jsonschema");
+            push_str(&mut record, &schema.schema);
+            self.write_summary_record(OP_SCHEMA, &record)?;
+            let _ = self.schemas.insert(schema.name, id);
+            id
+        };
+        let mut record = Vec::new();
+        record.extend_from_slice(&channel.to_le_bytes());
+        record.extend_from_slice(&schema_id.to_le_bytes());
+        push_str(&mut record, key);
+        push_str(&mut record, "json");
+        // no metadata
+        record.extend_from_slice(&0u32.to_le_bytes());
+        self.write_summary_record(OP_CHANNEL, &record)?;
+        let _ = self.message_counts.entry(channel).or_default();
+        Ok(Some(channel))
+    }
+
+    fn channel_count(&self) -> usize {
+        self.message_counts.len()
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    fn write_record(&mut self, opcode: u8, body: &[u8]) -> io::Result<()> {
+        let mut header = [0u8; 9];
+        header[0] = opcode;
+        header[1..].copy_from_slice(&(body.len() as u64).to_le_bytes());
+        self.write_all(&header)?;
+        self.write_all(body)
+    }
+
+    /// Writes a record that is also repeated in the summary.
+    fn write_summary_record(&mut self, opcode: u8, body: &[u8]) -> io::Result<()> {
+        self.summary.push(opcode);
+        self.summary.extend_from_slice(&(body.len() as u64).to_le_bytes());
+        self.summary.extend_from_slice(body);
+        self.write_record(opcode, body)
+    }
+}
+
+impl<W: Write> std::fmt::Debug for McapWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McapWriter")
+            .field("channels", &self.channel_count())
+            .field("messages", &self.message_count)
+            .finish_non_exhaustive()
+    }
+}
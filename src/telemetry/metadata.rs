@@ -0,0 +1,285 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use frclib_core::units::{
+    angle, angular_acceleration, angular_velocity, data, data_rate, energy, length, linear_acceleration,
+    linear_velocity, mass, moment_of_inertia, temperature, time, torque,
+};
+use frclib_core::value::{FrcValue, IntoFrcValue};
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::{Map, Value as Json};
+
+use super::{log, KeyId, TelemetryKey};
+
+/// What a telemetry key holds, published with it so dashboards can label it
+/// and log analysis can catch unit mix-ups.
+///
+/// Log files store it as a JSON object in the metadata of the key's entry
+/// and `NetworkTables` publishes each field as a topic property, fields that aren't set are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyMetadata {
+    /// The unit values are in, like `meters` or `volts`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Where the key is logged from, like the file and line that described it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl KeyMetadata {
+    /// The fields that are set as a JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> Map<String, Json> {
+        match serde_json::to_value(self) {
+            Ok(Json::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    /// Every field as a `NetworkTables` property update, fields that aren't set are null so they are removed.
+    pub(crate) fn property_update(&self) -> Map<String, Json> {
+        let mut update = self.to_json();
+        for field in ["unit", "description", "source"] {
+            let _ = update.entry(field).or_insert(Json::Null);
+        }
+        update
+    }
+}
+
+/// Bumped every time the metadata of a key changes.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+struct MetadataEntry {
+    key: &'static str,
+    /// The generation the metadata last changed in.
+    generation: u64,
+    metadata: KeyMetadata,
+}
+
+/// The metadata of every key that has some by [`KeyId`] index.
+static METADATA: RwLock<Vec<Option<MetadataEntry>>> = RwLock::new(Vec::new());
+
+thread_local! {
+    /// The unit every key logged in a unit on this thread was last recorded with.
+    static LOGGED_UNITS: RefCell<HashMap<KeyId, &'static str>> = RefCell::new(HashMap::new());
+}
+
+fn update(key: KeyId, func: impl FnOnce(&mut KeyMetadata)) {
+    let mut metadata = METADATA.write();
+    if key.index() >= metadata.len() {
+        metadata.resize_with(KeyId::count().max(key.index() + 1), || None);
+    }
+    let entry = metadata[key.index()].get_or_insert_with(|| MetadataEntry {
+        key: key.name(),
+        generation: 0,
+        metadata: KeyMetadata::default(),
+    });
+    let before = entry.metadata.clone();
+    func(&mut entry.metadata);
+    if entry.metadata != before {
+        entry.generation = GENERATION.fetch_add(1, Ordering::AcqRel) + 1;
+    }
+    drop(metadata);
+}
+
+/// Replaces the metadata of a key.
+pub fn set_key_metadata(key: impl TelemetryKey, metadata: KeyMetadata) {
+    update(KeyId::of(key.into_key()), |current| *current = metadata);
+}
+
+/// Sets the description of a key, recording the file and line this is called from as its source.
+#[track_caller]
+pub fn describe_key(key: impl TelemetryKey, description: impl Into<String>) {
+    let caller = Location::caller();
+    let description = description.into();
+    update(KeyId::of(key.into_key()), |current| {
+        current.description = Some(description);
+        current.source = Some(format!("{}:{}", caller.file(), caller.line()));
+    });
+}
+
+/// Sets the unit of a key, like `meters`.
+pub fn set_key_unit(key: impl TelemetryKey, unit: &str) {
+    update(KeyId::of(key.into_key()), |current| current.unit = Some(unit.to_owned()));
+}
+
+/// The metadata of a key, none if none was set.
+#[must_use]
+pub fn key_metadata(key: &str) -> Option<KeyMetadata> {
+    let id = KeyId::lookup(key)?;
+    METADATA
+        .read()
+        .get(id.index())
+        .and_then(Option::as_ref)
+        .map(|entry| entry.metadata.clone())
+}
+
+/// The current metadata generation, it changes whenever the metadata of any key does.
+pub fn metadata_generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Every key whose metadata changed after `generation` with its new metadata.
+pub fn metadata_changed_since(generation: u64) -> Vec<(&'static str, KeyMetadata)> {
+    METADATA
+        .read()
+        .iter()
+        .flatten()
+        .filter(|entry| entry.generation > generation)
+        .map(|entry| (entry.key, entry.metadata.clone()))
+        .collect()
+}
+
+/// A unit from [`frclib_core::units`] that can be logged with [`log`], recording the unit in the key's metadata.
+pub trait TelemetryUnit: Copy + Send + Sync {
+    /// The name of the unit as it is published, like `meters`.
+    const UNIT: &'static str;
+
+    fn into_frc_value(self) -> FrcValue;
+}
+
+macro_rules! telemetry_units {
+    ($($module:ident::$unit:ident => $name:literal),* $(,)?) => {
+        $(
+            impl TelemetryUnit for $module::$unit {
+                const UNIT: &'static str = $name;
+
+                fn into_frc_value(self) -> FrcValue {
+                    FrcValue::Double(self.value())
+                }
+            }
+        )*
+    };
+}
+
+telemetry_units!(
+    angle::Degree => "degrees",
+    angle::Radian => "radians",
+    angle::Rotation => "rotations",
+    angular_acceleration::DegreePerSecSqr => "degrees per second squared",
+    angular_acceleration::RadianPerSecSqr => "radians per second squared",
+    angular_acceleration::RotationPerSecSqr => "rotations per second squared",
+    angular_acceleration::RotationPerMinSqr => "rotations per minute squared",
+    angular_velocity::DegreePerSec => "degrees per second",
+    angular_velocity::RadianPerSec => "radians per second",
+    angular_velocity::RotationPerSec => "rotations per second",
+    angular_velocity::RotationPerMin => "rotations per minute",
+    data::Byte => "bytes",
+    data::Kilobyte => "kilobytes",
+    data::Megabyte => "megabytes",
+    data::Gigabyte => "gigabytes",
+    data_rate::BytesPerSecond => "bytes per second",
+    data_rate::KilobytesPerSecond => "kilobytes per second",
+    data_rate::MegabytesPerSecond => "megabytes per second",
+    data_rate::GigabytesPerHour => "gigabytes per hour",
+    energy::Joule => "joules",
+    energy::Volt => "volts",
+    energy::Amp => "amps",
+    energy::Watt => "watts",
+    energy::WattHour => "watt hours",
+    energy::Ohm => "ohms",
+    length::Meter => "meters",
+    length::Foot => "feet",
+    length::Inch => "inches",
+    length::Centimeter => "centimeters",
+    linear_acceleration::MetersPerSecSqr => "meters per second squared",
+    linear_acceleration::KilometersPerHrSqr => "kilometers per hour squared",
+    linear_acceleration::MilesPerHrSqr => "miles per hour squared",
+    linear_acceleration::FeetPerSecSqr => "feet per second squared",
+    linear_velocity::MetersPerSecond => "meters per second",
+    linear_velocity::KilometersPerHour => "kilometers per hour",
+    linear_velocity::MilesPerHour => "miles per hour",
+    linear_velocity::FeetPerSecond => "feet per second",
+    mass::Kilogram => "kilograms",
+    mass::Gram => "grams",
+    mass::Pound => "pounds",
+    mass::Ounce => "ounces",
+    moment_of_inertia::KilogramSquareMeter => "kilogram square meters",
+    moment_of_inertia::PoundSquareFoot => "pound square feet",
+    temperature::Celsius => "celsius",
+    temperature::Fahrenheit => "fahrenheit",
+    temperature::Kelvin => "kelvin",
+    time::Hour => "hours",
+    time::Minute => "minutes",
+    time::Second => "seconds",
+    time::Millisecond => "milliseconds",
+    torque::NewtonMeter => "newton meters",
+    torque::NewtonCentimeter => "newton centimeters",
+    torque::KilogramMeter => "kilogram meters",
+    torque::FootPound => "foot pounds",
+    torque::InchPound => "inch pounds",
+);
+
+impl TelemetryUnit for time::Microsecond {
+    const UNIT: &'static str = "microseconds";
+
+    fn into_frc_value(self) -> FrcValue {
+        FrcValue::Int(i64::try_from(self.value()).unwrap_or(i64::MAX))
+    }
+}
+
+/// A value [`log`] accepts, anything that converts into an [`FrcValue`] or a [`TelemetryUnit`].
+///
+/// The marker only keeps the two kinds of values apart, it is inferred and never has to be named.
+pub trait TelemetryValue<Marker> {
+    /// The unit recorded in the metadata of the key the value is logged under.
+    const UNIT: Option<&'static str>;
+
+    fn into_telemetry_value(self) -> FrcValue;
+}
+
+/// The [`TelemetryValue`] marker of values that convert into an [`FrcValue`].
+#[derive(Debug, Clone, Copy)]
+pub enum PlainValue {}
+
+/// The [`TelemetryValue`] marker of [`TelemetryUnit`]s.
+#[derive(Debug, Clone, Copy)]
+pub enum UnitValue {}
+
+impl<T: IntoFrcValue> TelemetryValue<PlainValue> for T {
+    const UNIT: Option<&'static str> = None;
+
+    fn into_telemetry_value(self) -> FrcValue {
+        self.into_frc_value()
+    }
+}
+
+impl<U: TelemetryUnit> TelemetryValue<UnitValue> for U {
+    const UNIT: Option<&'static str> = Some(U::UNIT);
+
+    fn into_telemetry_value(self) -> FrcValue {
+        TelemetryUnit::into_frc_value(self)
+    }
+}
+
+/// Records the unit a key is logged in.
+///
+/// This only takes a lock the first time a key is logged on a thread or its unit changes,
+/// logging a key in a different unit than before replaces its unit and warns about it.
+pub(super) fn record_unit(id: KeyId, key: &str, unit: &'static str) {
+    LOGGED_UNITS.with(|units| {
+        let mut units = units.borrow_mut();
+        if units.get(&id).is_some_and(|logged| *logged == unit) {
+            return;
+        }
+        let _ = units.insert(id, unit);
+        drop(units);
+        update(id, |current| {
+            if let Some(previous) = current.unit.as_deref().filter(|previous| *previous != unit) {
+                tracing::warn!("{key} was logged in {previous} and is now logged in {unit}");
+            }
+            current.unit = Some(unit.to_owned());
+        });
+    });
+}
+
+/// Logs a unit value as a number and records its unit in the key's metadata,
+/// the same as [`log`] but only accepting units.
+pub fn log_unit<U: TelemetryUnit>(key: impl TelemetryKey, value: U) {
+    log(key, value);
+}
@@ -1,70 +1,269 @@
+mod alerts;
+mod buffer;
 pub(crate) mod console;
+pub mod csv;
+pub mod foxglove;
+mod keys;
+pub mod mcap;
+mod metadata;
+mod namespace;
+pub mod nt4;
+mod policy;
+mod schema;
+mod tunable;
+pub mod wpilog;
 
-use std::{cell::RefCell, sync::Arc};
+use std::{cell::{Cell, RefCell}, collections::HashMap, sync::Arc};
 
-use frclib_core::{value::{FrcEntry, IntoFrcValue}, units::time::{Time, Microsecond}};
+use frclib_core::{structure::FrcStructure, value::{FrcEntry, FrcType, FrcValue, IntoFrcValue}, units::time::{Time, Microsecond}};
+
+pub use alerts::{Alert, AlertLevel, DEFAULT_ALERT_GROUP};
+use buffer::{drain_all, register_thread_buffer, ThreadBuffer};
+pub use buffer::{dropped_entries, set_thread_arena_capacity, set_thread_buffer_capacity, ArrayElement};
+pub use console::{set_tracing_config, tracing_config_path, LogFileConfig, LogFormat, TracingConfig, TracingSetupError};
+pub(crate) use keys::intern_key;
+pub use keys::KeyId;
+pub(crate) use metadata::{metadata_changed_since, metadata_generation};
+pub use metadata::{
+    describe_key, key_metadata, log_unit, set_key_metadata, set_key_unit, KeyMetadata, PlainValue, TelemetryUnit,
+    TelemetryValue, UnitValue,
+};
+pub use namespace::{TelemetryKey, TelemetryNamespace, TelemetryTree};
+use policy::apply_log_policies;
+pub(crate) use policy::exempt_replay_input;
+pub use policy::{budget_dropped_entries, clear_log_policy, log_policy, set_log_policy, set_telemetry_budget, LogPolicy};
+pub use schema::{schema_dependencies, schema_key, struct_layout, struct_schema, StructField};
+pub use tunable::{set_tunable, Tunable};
+
+/// A value logged under a key, what [`TelemetrySink`]s are handed every flush.
+///
+/// Like an [`FrcEntry`] with the interned id of its key,
+/// so consumers can index per key state with [`KeyId::index`] instead of hashing the key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryEntry {
+    pub id: KeyId,
+    pub key: &'static str,
+    /// When the value was logged in microseconds, usually the robot's uptime.
+    pub timestamp: u64,
+    pub value: FrcValue,
+}
+
+impl TelemetryEntry {
+    #[must_use]
+    pub fn new(key: &'static str, timestamp: u64, value: FrcValue) -> Self {
+        Self {
+            id: KeyId::of(key),
+            key,
+            timestamp,
+            value,
+        }
+    }
+}
+
+impl From<FrcEntry> for TelemetryEntry {
+    fn from(entry: FrcEntry) -> Self {
+        Self::new(entry.key, entry.timestamp, entry.value)
+    }
+}
+
+impl From<&TelemetryEntry> for FrcEntry {
+    fn from(entry: &TelemetryEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            value: entry.value.clone(),
+            key: entry.key,
+        }
+    }
+}
 
 /// A function that will be called when the data log is flushed.
 ///
-/// Takes an Arc slice of all the data logged since the last flush,
-/// this data type allows for low cost cloning for receivers that don't need ownership to log.
+/// Takes a slice of all the data logged since the last flush in timestamp order,
+/// the slice and the allocations of its values are reused for the next flush
+/// so sinks that need to keep entries have to clone them.
+/// Every built in consumer, like the [`wpilog`] writer and the [`nt4`] server, is a sink.
+pub type TelemetrySink = fn(&[TelemetryEntry]);
+
+#[linkme::distributed_slice]
+pub static TELEMETRY_SINKS: [TelemetrySink];
+
+/// A function that will be called when the data log is flushed with an owned copy of everything flushed.
+///
+/// Kept for consumers written before [`TelemetrySink`]s,
+/// every flush allocates a copy of its entries if any consumer is registered so new code should use a sink.
 pub type TelemetryConsumer = fn(Arc<[FrcEntry]>);
 
 #[linkme::distributed_slice]
 pub static TELEMETRY_CONSUMERS: [TelemetryConsumer];
 
+/// A function that will be called once when the program shuts down,
+/// after the final flush of the data log.
+///
+/// Consumers that buffer data, like log files, should write out everything they hold here.
+pub type TelemetryFinalizer = fn();
+
+#[linkme::distributed_slice]
+pub static TELEMETRY_FINALIZERS: [TelemetryFinalizer];
+
 thread_local! {
-    static TELEMETRY_CACHE: RefCell<Vec<FrcEntry>> = RefCell::new(Vec::with_capacity(128));
+    static TELEMETRY_CACHE: Arc<ThreadBuffer> = register_thread_buffer();
+    /// Prepended to every key logged on this thread, used to separate replayed outputs.
+    static KEY_PREFIX: Cell<Option<&'static str>> = const { Cell::new(None) };
+    /// The prefixed version of every key logged on this thread since the prefix was set.
+    static PREFIXED_KEYS: RefCell<HashMap<KeyId, (KeyId, &'static str)>> = RefCell::new(HashMap::new());
 }
 
+/// Sets a prefix for every key logged on this thread from now on.
+#[cfg(frc_sim)]
+pub(crate) fn set_key_prefix(prefix: Option<&'static str>) {
+    KEY_PREFIX.with(|key_prefix| key_prefix.set(prefix));
+    PREFIXED_KEYS.with(|keys| keys.borrow_mut().clear());
+}
 
+/// Drains everything logged on every thread since the last flush and hands it to every [`TelemetrySink`]
+/// and [`TelemetryConsumer`].
+///
+/// The active [`Alert`]s are logged first so every flush includes them,
+/// then entries filtered out by their key's [`LogPolicy`] are removed.
+///
+/// Entries are in timestamp order, entries with the same timestamp from one thread keep the order they were logged in.
+/// Each thread logs into a preallocated buffer and arena, and the merge reuses its allocation
+/// and those of the values of the last flush, so logging scalars, strings with [`log_str`],
+/// arrays with [`log_slice`] or structs and flushing them does not allocate once every buffer has been used.
+///
+/// The runtime calls this at the end of every iteration of the main loop,
+/// it should only be called from one thread so consumers see batches in order.
 pub fn flush_datalog() {
-    TELEMETRY_CACHE.with(|thread_cache| {
-        //get cache capacity
-        let capacity = thread_cache.borrow().capacity();
-        //swap out the cache
-        let cache: Vec<FrcEntry> = thread_cache.replace(Vec::with_capacity(capacity));
-        let rc_cache: Arc<[FrcEntry]> = Arc::from(cache);
-        //call all consumers
-        for consumer in TELEMETRY_CONSUMERS {
-            consumer(rc_cache.clone());
+    alerts::log_alerts();
+    drain_all(|entries| {
+        apply_log_policies(entries);
+        for sink in TELEMETRY_SINKS {
+            sink(entries);
+        }
+        if !TELEMETRY_CONSUMERS.is_empty() {
+            let entries: Arc<[FrcEntry]> = entries.iter().map(FrcEntry::from).collect();
+            for consumer in TELEMETRY_CONSUMERS {
+                consumer(Arc::clone(&entries));
+            }
         }
     });
 }
 
-fn log_entry(data: FrcEntry) {
-    TELEMETRY_CACHE.with(|thread_cache| {
-        thread_cache.borrow_mut().push(data);
-    });
+/// Flushes the data log one last time then runs every [`TelemetryFinalizer`].
+pub(crate) fn shutdown_telemetry() {
+    flush_datalog();
+    for finalizer in TELEMETRY_FINALIZERS {
+        finalizer();
+    }
 }
 
-pub fn log(key: &'static str, value: impl IntoFrcValue) {
-    log_entry(FrcEntry {
-        timestamp: u64::from(Microsecond::from(frclib_core::time::uptime())),
-        value: value.into_frc_value(),
-        key
-    });
+/// The key an entry is buffered under, with this thread's key prefix if one is set.
+fn prefixed(id: KeyId, key: &'static str) -> (KeyId, &'static str) {
+    let Some(prefix) = KEY_PREFIX.with(Cell::get) else {
+        return (id, key);
+    };
+    PREFIXED_KEYS.with(|keys| {
+        *keys.borrow_mut().entry(id).or_insert_with(|| {
+            let prefixed = KeyId::intern(&format!("{prefix}{key}"));
+            (prefixed, prefixed.name())
+        })
+    })
 }
 
-pub fn log_with_timestamp(key: &'static str, value: impl IntoFrcValue, timestamp: impl Time) {
-    log_entry(FrcEntry {
-        timestamp: u64::from(Microsecond::from(timestamp.standard())),
-        value: value.into_frc_value(),
-        key
-    });
+fn now() -> u64 {
+    u64::from(Microsecond::from(frclib_core::time::uptime()))
 }
 
+fn log_entry<M, V: TelemetryValue<M>>(key: impl TelemetryKey, timestamp: u64, value: V) {
+    let (id, key) = key.into_key_id();
+    if let Some(unit) = V::UNIT {
+        metadata::record_unit(id, key, unit);
+    }
+    let (id, key) = prefixed(id, key);
+    TELEMETRY_CACHE.with(|thread_cache| thread_cache.push(id, key, timestamp, value.into_telemetry_value()));
+}
 
-#[cfg(test)]
-use frclib_core::value::FrcValue;
+/// Logs a value copied into the thread's arena, `size` bytes long, see [`ThreadBuffer::push_bytes`].
+fn log_bytes(
+    key: impl TelemetryKey,
+    value: (FrcType, usize, usize),
+    write: impl FnOnce(&mut Vec<u8>),
+    fallback: impl FnOnce() -> FrcValue,
+) {
+    let (id, key) = key.into_key_id();
+    let (id, key) = prefixed(id, key);
+    let timestamp = now();
+    TELEMETRY_CACHE.with(|thread_cache| thread_cache.push_bytes(id, key, timestamp, value, write, fallback));
+}
+
+/// Logs a value under a key, see [`TelemetryKey`] for what can be used as a key.
+///
+/// Units from [`frclib_core::units`] are logged as numbers with their unit recorded in the key's metadata.
+/// Converting strings and slices into a value allocates,
+/// [`log_str`] and [`log_slice`] copy them into a preallocated arena instead.
+pub fn log<M, V: TelemetryValue<M>>(key: impl TelemetryKey, value: V) {
+    log_entry(key, now(), value);
+}
+
+pub fn log_with_timestamp<M, V: TelemetryValue<M>>(key: impl TelemetryKey, value: V, timestamp: impl Time) {
+    log_entry(key, u64::from(Microsecond::from(timestamp.standard())), value);
+}
+
+/// Logs a string without allocating, it is copied into the thread's arena until the next flush.
+pub fn log_str(key: impl TelemetryKey, value: &str) {
+    log_bytes(
+        key,
+        (FrcType::String, value.len(), value.len()),
+        |arena| arena.extend_from_slice(value.as_bytes()),
+        || value.into_frc_value(),
+    );
+}
+
+/// Logs an array of booleans or numbers without allocating, it is copied into the thread's arena until the next flush.
+pub fn log_slice<T: ArrayElement>(key: impl TelemetryKey, values: &[T]) {
+    log_bytes(
+        key,
+        (T::TYPE, values.len(), values.len() * T::SIZE),
+        |arena| values.iter().for_each(|value| value.write(arena)),
+        || T::to_value(values),
+    );
+}
+
+/// Logs a struct as a `WPILib` struct value,
+/// consumers publish the schema of its type the first time they see it.
+///
+/// The struct is packed into the thread's arena until the next flush, so this doesn't allocate.
+pub fn log_struct<T: FrcStructure>(key: impl TelemetryKey, value: &T) {
+    log_bytes(
+        key,
+        (FrcType::Struct(&T::DESCRIPTION), 1, T::SIZE),
+        |arena| value.pack(arena),
+        || FrcValue::from_struct(value),
+    );
+}
+
+/// Logs a slice of structs as a `WPILib` struct array value, like a trajectory of poses.
+pub fn log_struct_array<T: FrcStructure>(key: impl TelemetryKey, values: &[T]) {
+    log_bytes(
+        key,
+        (FrcType::StructArray(&T::DESCRIPTION), values.len(), values.len() * T::SIZE),
+        |arena| values.iter().for_each(|value| value.pack(arena)),
+        || FrcValue::from_struct_array(values),
+    );
+}
 
 #[cfg(test)]
 #[must_use]
 pub fn get_latest_value(key: &'static str) -> Option<FrcValue> {
+    let mut latest = None;
     TELEMETRY_CACHE.with(|thread_cache| {
-        let cache = thread_cache.borrow();
-        cache.iter().rev().find(|entry| entry.key == key).cloned().map(|e| e.value)
-    })
+        thread_cache.for_each(|entry_key, value| {
+            if entry_key == key {
+                latest = Some(value);
+            }
+        });
+    });
+    latest
 }
 
 /// Will assert that the latest value logged for the given key is equal to the given value.
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+use super::{log, KeyId, TelemetryValue};
+use crate::math::geometry::{
+    Pose2d, Pose3d, Rotation2d, Rotation3d, Transform2d, Transform3d, Translation2d, Translation3d,
+    Twist2d, Twist3d,
+};
+
+/// Anything that can be used as a telemetry key.
+///
+/// Static strings are used as is, runtime strings are interned the first time they are logged
+/// so only a bounded set of them should be used.
+/// Building a key with `format!` every cycle works but allocates,
+/// prefer building it once with a [`TelemetryNamespace`] or [`KeyId::intern`].
+pub trait TelemetryKey: Sized {
+    fn into_key(self) -> &'static str;
+
+    /// The key with its id.
+    fn into_key_id(self) -> (KeyId, &'static str) {
+        let key = self.into_key();
+        (KeyId::of(key), key)
+    }
+}
+
+impl TelemetryKey for &'static str {
+    fn into_key(self) -> &'static str {
+        self
+    }
+}
+
+impl TelemetryKey for KeyId {
+    fn into_key(self) -> &'static str {
+        self.name()
+    }
+
+    fn into_key_id(self) -> (KeyId, &'static str) {
+        (self, self.name())
+    }
+}
+
+impl TelemetryKey for String {
+    fn into_key(self) -> &'static str {
+        KeyId::intern(&self).name()
+    }
+
+    fn into_key_id(self) -> (KeyId, &'static str) {
+        let id = KeyId::intern(&self);
+        (id, id.name())
+    }
+}
+
+impl TelemetryKey for &String {
+    fn into_key(self) -> &'static str {
+        KeyId::intern(self).name()
+    }
+
+    fn into_key_id(self) -> (KeyId, &'static str) {
+        let id = KeyId::intern(self);
+        (id, id.name())
+    }
+}
+
+impl TelemetryKey for &TelemetryNamespace {
+    fn into_key(self) -> &'static str {
+        self.path()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ChildKey {
+    /// A static name by address.
+    Name(usize, usize),
+    Index(usize),
+}
+
+struct NamespaceInner {
+    path: &'static str,
+    children: Mutex<HashMap<ChildKey, TelemetryNamespace>>,
+}
+
+/// A path in the telemetry key tree, like `/Drive/Module0`, that keys can be logged under.
+///
+/// Child namespaces and keys are built once and cached, so logging through a namespace every cycle
+/// doesn't allocate or leak after the first cycle.
+/// Cloning a namespace is cheap and clones share their cache.
+///
+/// ```ignore
+/// let drive = TelemetryNamespace::new("/Drive");
+/// for (i, module) in modules.iter().enumerate() {
+///     let module_namespace = drive.index(i);
+///     module_namespace.log("Velocity", module.velocity());
+/// }
+/// drive.log_tree("Pose", &pose);
+/// ```
+#[derive(Clone)]
+pub struct TelemetryNamespace(Arc<NamespaceInner>);
+
+impl TelemetryNamespace {
+    /// A namespace at `path`, a trailing `/` is ignored.
+    #[must_use]
+    pub fn new(path: &str) -> Self {
+        Self::from_path(KeyId::intern(path.trim_end_matches('/')).name())
+    }
+
+    fn from_path(path: &'static str) -> Self {
+        Self(Arc::new(NamespaceInner {
+            path,
+            children: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// The full path of the namespace, also the key values logged with [`log_value`](Self::log_value) use.
+    #[must_use]
+    pub fn path(&self) -> &'static str {
+        self.0.path
+    }
+
+    fn cached_child(&self, key: ChildKey, name: impl FnOnce() -> String) -> Self {
+        self.0
+            .children
+            .lock()
+            .entry(key)
+            .or_insert_with(|| {
+                let path = format!("{}/{}", self.0.path, name().trim_start_matches('/'));
+                Self::from_path(KeyId::intern(&path).name())
+            })
+            .clone()
+    }
+
+    /// The namespace `name` under this one.
+    #[must_use]
+    pub fn child(&self, name: &'static str) -> Self {
+        self.cached_child(ChildKey::Name(name.as_ptr() as usize, name.len()), || {
+            name.to_owned()
+        })
+    }
+
+    /// The namespace named after an index under this one, like a swerve module number.
+    #[must_use]
+    pub fn index(&self, index: usize) -> Self {
+        self.cached_child(ChildKey::Index(index), || index.to_string())
+    }
+
+    /// The full key of `name` under this namespace.
+    #[must_use]
+    pub fn key(&self, name: &'static str) -> &'static str {
+        self.child(name).path()
+    }
+
+    /// Logs a value under `name` in this namespace.
+    pub fn log<M, V: TelemetryValue<M>>(&self, name: &'static str, value: V) {
+        log(self.key(name), value);
+    }
+
+    /// Logs a value at the path of this namespace itself.
+    pub fn log_value<M, V: TelemetryValue<M>>(&self, value: V) {
+        log(self.path(), value);
+    }
+
+    /// Logs a nested structure under `name` in this namespace, see [`TelemetryTree`].
+    pub fn log_tree(&self, name: &'static str, tree: &(impl TelemetryTree + ?Sized)) {
+        tree.log_tree(&self.child(name));
+    }
+}
+
+impl Debug for TelemetryNamespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TelemetryNamespace").field(&self.0.path).finish()
+    }
+}
+
+/// A structure that expands into a tree of keys when logged.
+///
+/// Values like numbers and poses are leaves logged at the namespace they are given,
+/// structs log each field under a child namespace named after the field,
+/// and sequences log each element under a child named after its index.
+/// Structs can implement this with [`telemetry_tree!`](crate::telemetry_tree).
+pub trait TelemetryTree {
+    fn log_tree(&self, namespace: &TelemetryNamespace);
+}
+
+macro_rules! telemetry_leaf {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl TelemetryTree for $type {
+                fn log_tree(&self, namespace: &TelemetryNamespace) {
+                    namespace.log_value(self.clone());
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! struct_leaf {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl TelemetryTree for $type {
+                fn log_tree(&self, namespace: &TelemetryNamespace) {
+                    namespace.log_value(FrcValue::from_struct(self));
+                }
+            }
+        )*
+    };
+}
+
+telemetry_leaf!(bool, i8, i16, i32, i64, u8, u16, u32, f32, f64, String, Box<str>);
+
+struct_leaf!(
+    Pose2d, Pose3d, Rotation2d, Rotation3d, Transform2d, Transform3d, Translation2d, Translation3d,
+    Twist2d, Twist3d,
+);
+
+impl TelemetryTree for str {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        namespace.log_value(self);
+    }
+}
+
+impl<T: TelemetryTree + ?Sized> TelemetryTree for &T {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        (**self).log_tree(namespace);
+    }
+}
+
+/// Only logs anything if there is a value.
+impl<T: TelemetryTree> TelemetryTree for Option<T> {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        if let Some(value) = self {
+            value.log_tree(namespace);
+        }
+    }
+}
+
+impl<T: TelemetryTree> TelemetryTree for [T] {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        for (index, value) in self.iter().enumerate() {
+            value.log_tree(&namespace.index(index));
+        }
+    }
+}
+
+impl<T: TelemetryTree, const N: usize> TelemetryTree for [T; N] {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        self.as_slice().log_tree(namespace);
+    }
+}
+
+impl<T: TelemetryTree> TelemetryTree for Vec<T> {
+    fn log_tree(&self, namespace: &TelemetryNamespace) {
+        self.as_slice().log_tree(namespace);
+    }
+}
@@ -0,0 +1,710 @@
+//! A `NetworkTables` 4 server so dashboards can connect to the robot.
+//!
+//! Everything flushed through the data log is published as a topic named after its key,
+//! clients like `AdvantageScope`, Glass and Elastic subscribe to the topics they want over a websocket.
+//! Values published by clients are kept as topics too and can be read with [`Nt4Server::latest_value`].
+//! The [`KeyMetadata`](super::KeyMetadata) of a key is published as properties of its topic.
+//!
+//! Each client has a bounded queue of frames, values that don't fit are coalesced to the latest value
+//! of their topic and sent once the client catches up, clients that stay behind are disconnected.
+//!
+//! The server started by the runtime listens on [`NT4_PORT`],
+//! more servers can be bound to other addresses, which is mostly useful for tests.
+
+mod protocol;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use frclib_core::structure::FrcStructDesc;
+use frclib_core::value::{FrcType, FrcValue};
+use linkme::distributed_slice;
+use parking_lot::Mutex;
+use serde_json::{Map, Value as Json};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::http::HeaderValue;
+use tungstenite::{Message, WebSocket};
+
+use self::protocol::{ClientMessage, SubscribeOptions, SUBPROTOCOLS, TIME_SYNC_ID};
+use super::{
+    key_metadata, metadata_changed_since, metadata_generation, schema_dependencies, schema_key, struct_schema,
+    TelemetryEntry, TELEMETRY_FINALIZERS, TELEMETRY_SINKS,
+};
+
+/// The port dashboards expect a `NetworkTables` 4 server on.
+pub const NT4_PORT: u16 = 5810;
+
+/// How long a client connection waits for a message before sending anything queued for it.
+const CLIENT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How many frames can be queued for a client before its values start being coalesced.
+const CLIENT_QUEUE_DEPTH: usize = 64;
+/// How many sends in a row a client can miss values in before it is disconnected.
+const MAX_LAGGING_SENDS: u32 = 100;
+
+fn server_time() -> u64 {
+    u64::try_from(frclib_core::time::uptime().as_micros()).unwrap_or(u64::MAX)
+}
+
+struct Topic {
+    id: i64,
+    type_str: String,
+    properties: Map<String, Json>,
+    value: Option<(u64, FrcValue)>,
+    /// The client and pubuid that created the topic, none if the robot did.
+    publisher: Option<(u64, i64)>,
+}
+
+struct Subscription {
+    topics: Vec<String>,
+    options: SubscribeOptions,
+}
+
+impl Subscription {
+    fn matches(&self, name: &str) -> bool {
+        self.topics.iter().any(|topic| {
+            if self.options.prefix {
+                name.starts_with(topic.as_str())
+            } else {
+                name == topic
+            }
+        })
+    }
+}
+
+struct Client {
+    sender: SyncSender<Message>,
+    /// Topics whose latest value did not fit in the client's queue.
+    stale: HashSet<i64>,
+    /// How many sends in a row left values stale.
+    lagging_sends: u32,
+    subscriptions: HashMap<i64, Subscription>,
+    announced: HashSet<i64>,
+    /// The topic name of every pubuid the client is publishing.
+    publishers: HashMap<i64, String>,
+}
+
+impl Client {
+    /// None if the client is not subscribed to a topic,
+    /// otherwise whether it wants values or only the announcement.
+    fn subscribed(&self, name: &str) -> Option<bool> {
+        self.subscriptions
+            .values()
+            .filter(|subscription| subscription.matches(name))
+            .map(|subscription| !subscription.options.topics_only)
+            .reduce(|a, b| a || b)
+    }
+}
+
+/// Messages queued for a client while the server is locked, sent as one text and one binary frame.
+#[derive(Default)]
+struct Outgoing {
+    text: Vec<Json>,
+    binary: Vec<u8>,
+    /// The topics with a value in `binary`.
+    topics: Vec<i64>,
+}
+
+impl Outgoing {
+    fn value(&mut self, topic: i64, timestamp: u64, value: &FrcValue) {
+        protocol::encode_value(&mut self.binary, topic, timestamp, value);
+        self.topics.push(topic);
+    }
+}
+
+#[derive(Default)]
+struct Outbox(HashMap<u64, Outgoing>);
+
+impl Outbox {
+    fn to(&mut self, client: u64) -> &mut Outgoing {
+        self.0.entry(client).or_default()
+    }
+
+    /// Queues everything for its client, disconnecting clients that have fallen too far behind.
+    fn send(mut self, inner: &mut ServerInner) {
+        for (&id, client) in &inner.clients {
+            if !client.stale.is_empty() {
+                let _ = self.to(id);
+            }
+        }
+        let mut lagging = Vec::new();
+        for (id, mut outgoing) in self.0 {
+            let Some(client) = inner.clients.get_mut(&id) else {
+                continue;
+            };
+            if !outgoing.text.is_empty() {
+                let text = Message::Text(Json::Array(std::mem::take(&mut outgoing.text)).to_string());
+                if let Err(e) = client.sender.try_send(text) {
+                    // announcements can't be coalesced, the client would not know what the values are
+                    if matches!(e, TrySendError::Full(_)) {
+                        lagging.push(id);
+                    }
+                    continue;
+                }
+            }
+            if !client.stale.is_empty() {
+                for (name, topic) in &inner.topics {
+                    if !client.stale.contains(&topic.id) || outgoing.topics.contains(&topic.id) {
+                        continue;
+                    }
+                    if let (Some(true), Some((timestamp, value))) = (client.subscribed(name), &topic.value) {
+                        outgoing.value(topic.id, *timestamp, value);
+                    }
+                }
+            }
+            if outgoing.binary.is_empty() {
+                client.stale.clear();
+                continue;
+            }
+            match client.sender.try_send(Message::Binary(outgoing.binary)) {
+                Ok(()) => {
+                    client.stale.clear();
+                    client.lagging_sends = 0;
+                }
+                Err(TrySendError::Full(_)) => {
+                    client.stale.extend(outgoing.topics);
+                    client.lagging_sends += 1;
+                    if client.lagging_sends > MAX_LAGGING_SENDS {
+                        lagging.push(id);
+                    }
+                }
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+        for id in lagging {
+            // dropping the sender tells the client thread to disconnect
+            let _ = inner.clients.remove(&id);
+            tracing::warn!("Disconnecting NT4 client {id}, it fell too far behind");
+        }
+    }
+}
+
+#[derive(Default)]
+struct ServerInner {
+    topics: HashMap<String, Topic>,
+    clients: HashMap<u64, Client>,
+    next_topic_id: i64,
+    next_client_id: u64,
+    /// The metadata generation published so far.
+    metadata_generation: u64,
+}
+
+impl ServerInner {
+    fn announce_to(client_id: u64, client: &mut Client, name: &str, topic: &Topic, outbox: &mut Outbox) {
+        if !client.announced.insert(topic.id) {
+            return;
+        }
+        let pubuid = topic
+            .publisher
+            .filter(|(publisher, _)| *publisher == client_id)
+            .map(|(_, pubuid)| pubuid);
+        outbox.to(client_id).text.push(protocol::announce(
+            name,
+            topic.id,
+            &topic.type_str,
+            pubuid,
+            &topic.properties,
+        ));
+    }
+
+    /// The id of a topic, creating and announcing it if it does not exist yet.
+    /// Returns none if the topic exists with a different type.
+    fn topic(
+        &mut self,
+        name: &str,
+        type_str: &str,
+        properties: Map<String, Json>,
+        publisher: Option<(u64, i64)>,
+        outbox: &mut Outbox,
+    ) -> Option<i64> {
+        if let Some(topic) = self.topics.get(name) {
+            return (topic.type_str == type_str).then_some(topic.id);
+        }
+        let topic = Topic {
+            id: self.next_topic_id,
+            type_str: type_str.to_owned(),
+            properties,
+            value: None,
+            publisher,
+        };
+        self.next_topic_id += 1;
+        for (&client_id, client) in &mut self.clients {
+            if client.subscribed(name).is_some()
+                || publisher.is_some_and(|(publisher, _)| publisher == client_id)
+            {
+                Self::announce_to(client_id, client, name, &topic, outbox);
+            }
+        }
+        let id = topic.id;
+        let _ = self.topics.insert(name.to_owned(), topic);
+        Some(id)
+    }
+
+    /// Stores the latest value of a topic and sends it to every subscriber except the one it came from.
+    fn set_value(&mut self, name: &str, timestamp: u64, value: FrcValue, from: Option<u64>, outbox: &mut Outbox) {
+        let Some(topic) = self.topics.get_mut(name) else {
+            return;
+        };
+        for (&client_id, client) in &mut self.clients {
+            if Some(client_id) == from || client.subscribed(name) != Some(true) {
+                continue;
+            }
+            Self::announce_to(client_id, client, name, topic, outbox);
+            outbox.to(client_id).value(topic.id, timestamp, &value);
+        }
+        topic.value = Some((timestamp, value));
+    }
+
+    /// Publishes the schema of a struct type and every struct type it uses, if they aren't already.
+    fn publish_schema(&mut self, desc: &'static FrcStructDesc, timestamp: u64, outbox: &mut Outbox) {
+        if self.topics.contains_key(&schema_key(desc.type_str)) {
+            return;
+        }
+        for desc in schema_dependencies(desc) {
+            let key = schema_key(desc.type_str);
+            if self.topics.contains_key(&key) {
+                continue;
+            }
+            let _ = self.topic(&key, "structschema", Map::new(), None, outbox);
+            let value = FrcValue::Raw(struct_schema(desc).into_bytes().into());
+            self.set_value(&key, timestamp, value, None, outbox);
+        }
+    }
+
+    /// Updates the properties of a topic and tells every client that knows about it,
+    /// `from` is the client that asked for the update.
+    fn set_properties(&mut self, name: &str, update: &Map<String, Json>, from: Option<u64>, outbox: &mut Outbox) {
+        let Some(topic) = self.topics.get_mut(name) else {
+            return;
+        };
+        for (key, value) in update {
+            if value.is_null() {
+                let _ = topic.properties.remove(key);
+            } else {
+                let _ = topic.properties.insert(key.clone(), value.clone());
+            }
+        }
+        for (&id, client) in &self.clients {
+            if Some(id) == from || client.announced.contains(&topic.id) {
+                outbox
+                    .to(id)
+                    .text
+                    .push(protocol::properties(name, update, Some(id) == from));
+            }
+        }
+    }
+
+    /// Updates the properties of every robot topic whose [`KeyMetadata`](super::KeyMetadata) changed.
+    fn publish_changed_metadata(&mut self, outbox: &mut Outbox) {
+        let generation = metadata_generation();
+        if generation == self.metadata_generation {
+            return;
+        }
+        for (key, metadata) in metadata_changed_since(self.metadata_generation) {
+            if self.topics.get(key).is_some_and(|topic| topic.publisher.is_none()) {
+                self.set_properties(key, &metadata.property_update(), None, outbox);
+            }
+        }
+        self.metadata_generation = generation;
+    }
+
+    fn publish(&mut self, entries: &[TelemetryEntry], outbox: &mut Outbox) {
+        self.publish_changed_metadata(outbox);
+        for entry in entries {
+            let frc_type = entry.value.get_type();
+            let Some(type_str) = protocol::type_string(frc_type) else {
+                continue;
+            };
+            if let FrcType::Struct(desc) | FrcType::StructArray(desc) = frc_type {
+                self.publish_schema(desc, entry.timestamp, outbox);
+            }
+            let properties = if self.topics.contains_key(entry.key) {
+                Map::new()
+            } else {
+                key_metadata(entry.key).map(|metadata| metadata.to_json()).unwrap_or_default()
+            };
+            if self.topic(entry.key, type_str, properties, None, outbox).is_none() {
+                tracing::debug!("Not publishing {} to NT4, its type changed to {type_str}", entry.key);
+                continue;
+            }
+            self.set_value(entry.key, entry.timestamp, entry.value.clone(), None, outbox);
+        }
+    }
+
+    fn handle_text(&mut self, client_id: u64, message: ClientMessage, outbox: &mut Outbox) {
+        match message {
+            ClientMessage::Publish {
+                name,
+                pubuid,
+                type_str,
+                properties,
+            } => {
+                if self
+                    .topic(&name, &type_str, properties, Some((client_id, pubuid)), outbox)
+                    .is_none()
+                {
+                    tracing::warn!("NT4 client tried to publish {name} as {type_str} but it has a different type");
+                    return;
+                }
+                if let (Some(client), Some(topic)) = (self.clients.get_mut(&client_id), self.topics.get(&name)) {
+                    Self::announce_to(client_id, client, &name, topic, outbox);
+                    let _ = client.publishers.insert(pubuid, name);
+                }
+            }
+            ClientMessage::Unpublish { pubuid } => {
+                if let Some(client) = self.clients.get_mut(&client_id) {
+                    let _ = client.publishers.remove(&pubuid);
+                }
+            }
+            ClientMessage::SetProperties { name, update } => {
+                self.set_properties(&name, &update, Some(client_id), outbox);
+            }
+            ClientMessage::Subscribe {
+                topics,
+                subuid,
+                options,
+            } => {
+                let Some(client) = self.clients.get_mut(&client_id) else {
+                    return;
+                };
+                let _ = client
+                    .subscriptions
+                    .insert(subuid, Subscription { topics, options });
+                for (name, topic) in &self.topics {
+                    let Some(values) = client.subscribed(name) else {
+                        continue;
+                    };
+                    Self::announce_to(client_id, client, name, topic, outbox);
+                    if let (true, Some((timestamp, value))) = (values, &topic.value) {
+                        outbox.to(client_id).value(topic.id, *timestamp, value);
+                    }
+                }
+            }
+            ClientMessage::Unsubscribe { subuid } => {
+                if let Some(client) = self.clients.get_mut(&client_id) {
+                    let _ = client.subscriptions.remove(&subuid);
+                }
+            }
+        }
+    }
+
+    fn handle_binary(&mut self, client_id: u64, data: &[u8], outbox: &mut Outbox) {
+        for message in protocol::decode_binary(data) {
+            if message.id == TIME_SYNC_ID {
+                protocol::encode_raw(
+                    &mut outbox.to(client_id).binary,
+                    TIME_SYNC_ID,
+                    server_time(),
+                    message.type_index,
+                    message.value,
+                );
+                continue;
+            }
+            let Some(name) = self
+                .clients
+                .get(&client_id)
+                .and_then(|client| client.publishers.get(&message.id))
+                .cloned()
+            else {
+                continue;
+            };
+            let Some(value) = self
+                .topics
+                .get(&name)
+                .and_then(|topic| protocol::from_msgpack(&topic.type_str, &message.value))
+            else {
+                tracing::debug!("NT4 client sent a value of the wrong type for {name}");
+                continue;
+            };
+            self.set_value(&name, server_time(), value, Some(client_id), outbox);
+        }
+    }
+}
+
+struct ServerState {
+    inner: Mutex<ServerInner>,
+    running: AtomicBool,
+}
+
+impl ServerState {
+    /// Runs a function with the server locked then sends everything it queued.
+    fn with_outbox<R>(&self, func: impl FnOnce(&mut ServerInner, &mut Outbox) -> R) -> R {
+        let mut inner = self.inner.lock();
+        let mut outbox = Outbox::default();
+        let result = func(&mut inner, &mut outbox);
+        outbox.send(&mut inner);
+        drop(inner);
+        result
+    }
+}
+
+/// A `NetworkTables` 4 server, see the [module docs](self).
+///
+/// The server runs on its own threads, one to accept connections and one per client,
+/// dropping it disconnects every client and stops listening.
+pub struct Nt4Server {
+    state: Arc<ServerState>,
+    address: SocketAddr,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl Nt4Server {
+    /// Starts listening for clients on an address, port 0 picks any free port.
+    ///
+    /// # Errors
+    /// Returns an error if the address could not be bound.
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            inner: Mutex::new(ServerInner::default()),
+            running: AtomicBool::new(true),
+        });
+        let accept_state = Arc::clone(&state);
+        let accept_thread = std::thread::Builder::new()
+            .name("nt4-accept".to_owned())
+            .spawn(move || accept_clients(&accept_state, &listener))?;
+        tracing::info!("NT4 server listening on {address}");
+        Ok(Self {
+            state,
+            address,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Publishes entries as topics, creating any topics that don't exist yet.
+    pub fn publish(&self, entries: &[TelemetryEntry]) {
+        self.state
+            .with_outbox(|inner, outbox| inner.publish(entries, outbox));
+    }
+
+    /// The latest value of a topic, published by the robot or a client.
+    #[must_use]
+    pub fn latest_value(&self, name: &str) -> Option<FrcValue> {
+        self.state
+            .inner
+            .lock()
+            .topics
+            .get(name)
+            .and_then(|topic| topic.value.as_ref().map(|(_, value)| value.clone()))
+    }
+
+    #[must_use]
+    pub fn client_count(&self) -> usize {
+        self.state.inner.lock().clients.len()
+    }
+}
+
+impl Drop for Nt4Server {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Release);
+        if let Some(thread) = self.accept_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Debug for Nt4Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Nt4Server")
+            .field("address", &self.address)
+            .field("clients", &self.client_count())
+            .finish_non_exhaustive()
+    }
+}
+
+fn accept_clients(state: &Arc<ServerState>, listener: &TcpListener) {
+    while state.running.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, address)) => {
+                let client_state = Arc::clone(state);
+                let spawned = std::thread::Builder::new()
+                    .name(format!("nt4-client-{address}"))
+                    .spawn(move || {
+                        if let Err(e) = serve_client(&client_state, stream) {
+                            tracing::debug!("NT4 client {address} disconnected: {e}");
+                        }
+                    });
+                if let Err(e) = spawned {
+                    tracing::warn!("Failed to spawn NT4 client thread: {e}");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL_INTERVAL),
+            Err(e) => {
+                tracing::warn!("Failed to accept NT4 client: {e}");
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+// tungstenite errors are large but only returned once per connection
+#[allow(clippy::result_large_err)]
+/// Completes the websocket handshake, picking an NT4 subprotocol and the client name from the path.
+fn handshake(stream: TcpStream) -> Result<(WebSocket<TcpStream>, String), tungstenite::Error> {
+    let mut name = String::new();
+    let websocket = tungstenite::accept_hdr(stream, |request: &Request, mut response: Response| {
+        request
+            .uri()
+            .path()
+            .strip_prefix("/nt/")
+            .unwrap_or_default()
+            .clone_into(&mut name);
+        let requested = request
+            .headers()
+            .get_all("Sec-WebSocket-Protocol")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(',').map(str::trim))
+            .collect::<Vec<_>>();
+        if let Some(protocol) = SUBPROTOCOLS
+            .iter()
+            .find(|protocol| requested.contains(protocol))
+        {
+            let _ = response
+                .headers_mut()
+                .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(protocol));
+        }
+        Ok(response)
+    })
+    .map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+        }
+    })?;
+    Ok((websocket, name))
+}
+
+#[allow(clippy::result_large_err)]
+fn serve_client(state: &ServerState, stream: TcpStream) -> Result<(), tungstenite::Error> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let (mut websocket, name) = handshake(stream)?;
+    websocket.get_ref().set_read_timeout(Some(CLIENT_POLL_INTERVAL))?;
+
+    let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE_DEPTH);
+    let client_id = {
+        let mut inner = state.inner.lock();
+        let id = inner.next_client_id;
+        inner.next_client_id += 1;
+        let _ = inner.clients.insert(
+            id,
+            Client {
+                sender,
+                stale: HashSet::new(),
+                lagging_sends: 0,
+                subscriptions: HashMap::new(),
+                announced: HashSet::new(),
+                publishers: HashMap::new(),
+            },
+        );
+        id
+    };
+    tracing::info!("NT4 client {name} connected");
+    let result = client_loop(state, client_id, &mut websocket, &receiver);
+    let _ = state.inner.lock().clients.remove(&client_id);
+    tracing::info!("NT4 client {name} disconnected");
+    result
+}
+
+#[allow(clippy::result_large_err)]
+fn client_loop(
+    state: &ServerState,
+    client_id: u64,
+    websocket: &mut WebSocket<TcpStream>,
+    receiver: &Receiver<Message>,
+) -> Result<(), tungstenite::Error> {
+    while state.running.load(Ordering::Acquire) {
+        loop {
+            match receiver.try_recv() {
+                Ok(message) => websocket.write(message)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = websocket.close(None);
+                    let _ = websocket.flush();
+                    return Ok(());
+                }
+            }
+        }
+        websocket.flush()?;
+
+        match websocket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<Vec<ClientMessage>>(&text) {
+                Ok(messages) => state.with_outbox(|inner, outbox| {
+                    for message in messages {
+                        inner.handle_text(client_id, message, outbox);
+                    }
+                }),
+                Err(e) => tracing::debug!("Invalid NT4 text message: {e}"),
+            },
+            Ok(Message::Binary(data)) => {
+                state.with_outbox(|inner, outbox| inner.handle_binary(client_id, &data, outbox));
+            }
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let _ = websocket.close(None);
+    let _ = websocket.flush();
+    Ok(())
+}
+
+static GLOBAL_SERVER: Mutex<Option<Nt4Server>> = Mutex::new(None);
+
+/// Starts the server every flush of the data log is published to,
+/// returns the address it is listening on or the existing address if it was already started.
+///
+/// # Errors
+/// Returns an error if the address could not be bound.
+pub fn start_server(address: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    let mut server = GLOBAL_SERVER.lock();
+    if let Some(server) = server.as_ref() {
+        return Ok(server.local_addr());
+    }
+    let started = Nt4Server::bind(address)?;
+    let address = started.local_addr();
+    *server = Some(started);
+    drop(server);
+    Ok(address)
+}
+
+/// Stops the server started with [`start_server`], disconnecting every client.
+pub fn stop_server() {
+    drop(GLOBAL_SERVER.lock().take());
+}
+
+/// The latest value of a topic on the server started with [`start_server`].
+#[must_use]
+pub fn latest_value(name: &str) -> Option<FrcValue> {
+    GLOBAL_SERVER
+        .lock()
+        .as_ref()
+        .and_then(|server| server.latest_value(name))
+}
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static NT4_SINK: super::TelemetrySink = |entries| {
+    if let Some(server) = GLOBAL_SERVER.lock().as_ref() {
+        server.publish(entries);
+    }
+};
+
+#[distributed_slice(TELEMETRY_FINALIZERS)]
+static NT4_FINALIZER: super::TelemetryFinalizer = stop_server;
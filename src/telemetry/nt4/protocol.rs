@@ -0,0 +1,192 @@
+//! Encoding of the NT4 text (JSON) and binary (`MessagePack`) messages.
+
+use frclib_core::value::{FrcType, FrcValue};
+use rmpv::Value;
+use serde::Deserialize;
+use serde_json::{Map, Value as Json};
+
+/// The websocket subprotocols a client can request, most preferred first.
+pub(super) const SUBPROTOCOLS: [&str; 2] = [
+    "v4.1.networktables.first.wpi.edu",
+    "networktables.first.wpi.edu",
+];
+
+/// The topic id binary messages use to sync time between client and server.
+pub(super) const TIME_SYNC_ID: i64 = -1;
+
+/// The NT4 type string of a value type, or none for [`FrcType::Void`] which can't be published.
+pub(super) fn type_string(frc_type: FrcType) -> Option<&'static str> {
+    match frc_type {
+        FrcType::Int => Some("int"),
+        FrcType::IntArray => Some("int[]"),
+        _ => crate::telemetry::wpilog::type_string(frc_type),
+    }
+}
+
+/// The type index sent alongside a value in binary messages.
+const fn type_index(value: &FrcValue) -> u8 {
+    match value {
+        FrcValue::Boolean(_) => 0,
+        FrcValue::Double(_) => 1,
+        FrcValue::Int(_) => 2,
+        FrcValue::Float(_) => 3,
+        FrcValue::String(_) => 4,
+        FrcValue::Void | FrcValue::Raw(_) | FrcValue::Struct(_) | FrcValue::StructArray(_) => 5,
+        FrcValue::BooleanArray(_) => 16,
+        FrcValue::DoubleArray(_) => 17,
+        FrcValue::IntArray(_) => 18,
+        FrcValue::FloatArray(_) => 19,
+        FrcValue::StringArray(_) => 20,
+    }
+}
+
+fn to_msgpack(value: &FrcValue) -> Value {
+    match value {
+        FrcValue::Void => Value::Nil,
+        FrcValue::Boolean(value) => Value::Boolean(*value),
+        FrcValue::Double(value) => Value::F64(*value),
+        FrcValue::Int(value) => Value::from(*value),
+        FrcValue::Float(value) => Value::F32(*value),
+        FrcValue::String(value) => Value::from(&**value),
+        FrcValue::Raw(bytes) => Value::Binary(bytes.to_vec()),
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => Value::Binary(bytes.data.to_vec()),
+        FrcValue::BooleanArray(values) => Value::Array(values.iter().map(|value| Value::Boolean(*value)).collect()),
+        FrcValue::DoubleArray(values) => Value::Array(values.iter().map(|value| Value::F64(*value)).collect()),
+        FrcValue::IntArray(values) => Value::Array(values.iter().map(|value| Value::from(*value)).collect()),
+        FrcValue::FloatArray(values) => Value::Array(values.iter().map(|value| Value::F32(*value)).collect()),
+        FrcValue::StringArray(values) => Value::Array(values.iter().map(|value| Value::from(&**value)).collect()),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn as_f64(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_i64().map(|value| value as f64))
+}
+
+/// Decodes a value sent by a client for a topic of type `type_str`,
+/// struct and unknown types are kept as raw bytes.
+#[allow(clippy::cast_possible_truncation)]
+pub(super) fn from_msgpack(type_str: &str, value: &Value) -> Option<FrcValue> {
+    fn array<T>(value: &Value, convert: impl Fn(&Value) -> Option<T>) -> Option<Box<[T]>> {
+        value.as_array()?.iter().map(convert).collect()
+    }
+    Some(match type_str {
+        "boolean" => FrcValue::Boolean(value.as_bool()?),
+        "double" => FrcValue::Double(as_f64(value)?),
+        "int" => FrcValue::Int(value.as_i64().or_else(|| value.as_f64().map(|value| value as i64))?),
+        "float" => FrcValue::Float(as_f64(value)? as f32),
+        "string" | "json" => FrcValue::String(value.as_str()?.into()),
+        "boolean[]" => FrcValue::BooleanArray(array(value, Value::as_bool)?),
+        "double[]" => FrcValue::DoubleArray(array(value, as_f64)?),
+        "int[]" => FrcValue::IntArray(array(value, Value::as_i64)?),
+        "float[]" => FrcValue::FloatArray(array(value, |value| as_f64(value).map(|value| value as f32))?),
+        "string[]" => FrcValue::StringArray(array(value, |value| value.as_str().map(Box::from))?),
+        _ => FrcValue::Raw(value.as_slice()?.into()),
+    })
+}
+
+/// Appends a binary message for one value, binary frames can hold any number of messages back to back.
+pub(super) fn encode_value(buffer: &mut Vec<u8>, id: i64, timestamp: u64, value: &FrcValue) {
+    encode_raw(
+        buffer,
+        id,
+        timestamp,
+        Value::from(type_index(value)),
+        to_msgpack(value),
+    );
+}
+
+pub(super) fn encode_raw(buffer: &mut Vec<u8>, id: i64, timestamp: u64, type_index: Value, value: Value) {
+    let message = Value::Array(vec![Value::from(id), Value::from(timestamp), type_index, value]);
+    // writing to a vec can't fail
+    let _ = rmpv::encode::write_value(buffer, &message);
+}
+
+/// A binary message from a client, `[id, timestamp, type, value]`.
+#[derive(Debug)]
+pub(super) struct BinaryMessage {
+    pub id: i64,
+    pub type_index: Value,
+    pub value: Value,
+}
+
+/// Decodes every message in a binary frame, stopping at the first malformed one.
+pub(super) fn decode_binary(mut data: &[u8]) -> Vec<BinaryMessage> {
+    let mut messages = Vec::new();
+    while !data.is_empty() {
+        let Ok(Value::Array(parts)) = rmpv::decode::read_value(&mut data) else {
+            break;
+        };
+        let Ok([id, _, type_index, value]) = <[Value; 4]>::try_from(parts) else {
+            break;
+        };
+        let Some(id) = id.as_i64() else {
+            break;
+        };
+        messages.push(BinaryMessage {
+            id,
+            type_index,
+            value,
+        });
+    }
+    messages
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct SubscribeOptions {
+    /// Only announce topics, never send their values.
+    #[serde(rename = "topicsonly")]
+    pub topics_only: bool,
+    /// Match every topic starting with one of the subscribed names instead of exact names.
+    pub prefix: bool,
+}
+
+/// A text message from a client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "lowercase")]
+pub(super) enum ClientMessage {
+    Publish {
+        name: String,
+        pubuid: i64,
+        #[serde(rename = "type")]
+        type_str: String,
+        #[serde(default)]
+        properties: Map<String, Json>,
+    },
+    Unpublish {
+        pubuid: i64,
+    },
+    SetProperties {
+        name: String,
+        update: Map<String, Json>,
+    },
+    Subscribe {
+        topics: Vec<String>,
+        subuid: i64,
+        #[serde(default)]
+        options: SubscribeOptions,
+    },
+    Unsubscribe {
+        subuid: i64,
+    },
+}
+
+pub(super) fn announce(name: &str, id: i64, type_str: &str, pubuid: Option<i64>, properties: &Map<String, Json>) -> Json {
+    let mut params = serde_json::json!({
+        "name": name,
+        "id": id,
+        "type": type_str,
+        "properties": properties,
+    });
+    if let (Some(pubuid), Some(params)) = (pubuid, params.as_object_mut()) {
+        let _ = params.insert("pubuid".to_owned(), Json::from(pubuid));
+    }
+    serde_json::json!({ "method": "announce", "params": params })
+}
+
+pub(super) fn properties(name: &str, update: &Map<String, Json>, ack: bool) -> Json {
+    serde_json::json!({ "method": "properties", "params": { "name": name, "ack": ack, "update": update } })
+}
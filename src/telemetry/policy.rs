@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+use super::{KeyId, TelemetryEntry};
+use crate::replay::{CYCLE_KEY, REPLAY_PREFIX};
+
+/// How often values of a key are passed on when the data log is flushed, see [`set_log_policy`].
+///
+/// Values a policy filters out never reach any consumer, so they are left out of log files
+/// and `NetworkTables` alike.
+/// The keys [replay](crate::replay) needs, the [`CYCLE_KEY`], the driver station state under `/DriverStation`
+/// and every [`input`](crate::replay::input), are never filtered so logs can always be replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogPolicy {
+    /// Only pass on values that differ from the last one passed on for the key.
+    pub on_change: bool,
+    /// With [`on_change`](LogPolicy::on_change), pass on an unchanged value again once this long
+    /// has passed since the last one so late subscribers see it, zero never repeats a value.
+    pub keepalive: Duration,
+    /// The shortest time between two values of the key, values logged sooner are dropped.
+    /// Zero doesn't limit the rate.
+    pub min_period: Duration,
+    /// Drop the key's values while the [bandwidth budget](set_telemetry_budget) is used up.
+    pub droppable: bool,
+}
+
+impl LogPolicy {
+    /// Every value is passed on, the policy of keys without one.
+    pub const ALWAYS: Self = Self {
+        on_change: false,
+        keepalive: Duration::ZERO,
+        min_period: Duration::ZERO,
+        droppable: false,
+    };
+
+    /// Only changes, with the value repeated every second while it doesn't change.
+    pub const ON_CHANGE: Self = Self {
+        on_change: true,
+        keepalive: Duration::from_secs(1),
+        ..Self::ALWAYS
+    };
+
+    /// At most `hz` values per second.
+    #[must_use]
+    pub fn decimated(hz: f64) -> Self {
+        Self {
+            min_period: Duration::try_from_secs_f64(hz.recip()).unwrap_or(Duration::ZERO),
+            ..Self::ALWAYS
+        }
+    }
+
+    const fn filters(&self) -> bool {
+        self.on_change || !self.min_period.is_zero()
+    }
+}
+
+impl Default for LogPolicy {
+    fn default() -> Self {
+        Self::ALWAYS
+    }
+}
+
+struct Budget {
+    bytes_per_second: u64,
+    /// The bytes that can still be passed on, refilled over time up to a second's worth.
+    available: f64,
+    last_refill: u64,
+}
+
+#[derive(Default)]
+struct PolicyState {
+    /// Policies by the key or namespace they apply to.
+    rules: Vec<(String, LogPolicy)>,
+    /// The replay inputs, which no policy applies to.
+    inputs: HashSet<&'static str>,
+    /// The policy of every key seen by [`KeyId`] index, none until it is first needed.
+    resolved: Vec<Option<LogPolicy>>,
+    /// The time and value last passed on for every key with a filtering policy.
+    last: Vec<Option<(u64, FrcValue)>>,
+    budget: Option<Budget>,
+}
+
+impl PolicyState {
+    fn resolve(&self, key: &str) -> LogPolicy {
+        // replays log the same keys under their prefix
+        let unprefixed = key.strip_prefix(REPLAY_PREFIX).unwrap_or(key);
+        if unprefixed == CYCLE_KEY || unprefixed.starts_with("/DriverStation/") || self.inputs.contains(unprefixed) {
+            return LogPolicy::ALWAYS;
+        }
+        self.rules
+            .iter()
+            .filter(|(rule, _)| {
+                key.strip_prefix(rule.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|(rule, _)| rule.len())
+            .map_or(LogPolicy::ALWAYS, |(_, policy)| *policy)
+    }
+
+    fn keep(&mut self, entry: &TelemetryEntry) -> bool {
+        let index = entry.id.index();
+        if index >= self.resolved.len() {
+            self.resolved.resize(KeyId::count().max(index + 1), None);
+            self.last.resize(self.resolved.len(), None);
+        }
+        let policy = if let Some(policy) = self.resolved[index] {
+            policy
+        } else {
+            let policy = self.resolve(entry.key);
+            self.resolved[index] = Some(policy);
+            policy
+        };
+        let mut changed = true;
+        if let (true, Some((time, value))) = (policy.filters(), &self.last[index]) {
+            let elapsed = Duration::from_micros(entry.timestamp.saturating_sub(*time));
+            changed = *value != entry.value;
+            let repeat = !policy.keepalive.is_zero() && elapsed >= policy.keepalive;
+            if elapsed < policy.min_period || (policy.on_change && !changed && !repeat) {
+                return false;
+            }
+        }
+        if let Some(budget) = &mut self.budget {
+            #[allow(clippy::cast_precision_loss)]
+            let size = entry_size(entry) as f64;
+            if policy.droppable && budget.available < size {
+                let _ = BUDGET_DROPPED.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            budget.available -= size;
+        }
+        if policy.filters() {
+            match &mut self.last[index] {
+                Some((time, _)) if !changed => *time = entry.timestamp,
+                last => *last = Some((entry.timestamp, entry.value.clone())),
+            }
+        }
+        true
+    }
+}
+
+static POLICIES: Mutex<Option<PolicyState>> = Mutex::new(None);
+/// Whether any policy or budget is set, so flushes skip filtering entirely when none are.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static BUDGET_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+fn with_state<R>(func: impl FnOnce(&mut PolicyState) -> R) -> R {
+    let mut guard = POLICIES.lock();
+    let state = guard.get_or_insert_with(PolicyState::default);
+    let result = func(state);
+    ACTIVE.store(!state.rules.is_empty() || state.budget.is_some(), Ordering::Release);
+    drop(guard);
+    result
+}
+
+/// Sets the policy of a key and every key under it, like `/Config` for `/Config/Speed`.
+///
+/// The most specific policy applies to a key, `/` sets the policy of every key without a more specific one.
+/// Policies are applied when the data log is flushed, so keys are logged the same way no matter their policy.
+///
+/// ```ignore
+/// set_log_policy("/Config", LogPolicy::ON_CHANGE);
+/// set_log_policy("/Vision/Corners", LogPolicy { droppable: true, ..LogPolicy::decimated(10.0) });
+/// ```
+pub fn set_log_policy(key: &str, policy: LogPolicy) {
+    let key = key.trim_end_matches('/');
+    with_state(|state| {
+        match state.rules.iter_mut().find(|(rule, _)| rule == key) {
+            Some((_, existing)) => *existing = policy,
+            None => state.rules.push((key.to_owned(), policy)),
+        }
+        state.resolved.clear();
+    });
+}
+
+/// Removes the policy set for exactly `key` with [`set_log_policy`].
+pub fn clear_log_policy(key: &str) {
+    let key = key.trim_end_matches('/');
+    with_state(|state| {
+        state.rules.retain(|(rule, _)| rule != key);
+        state.resolved.clear();
+    });
+}
+
+/// Exempts a replay input from every policy, called the first time each input is read on a thread.
+pub fn exempt_replay_input(key: &'static str) {
+    with_state(|state| {
+        if state.inputs.insert(key) {
+            state.resolved.clear();
+        }
+    });
+}
+
+/// The policy that applies to a key.
+#[must_use]
+pub fn log_policy(key: &str) -> LogPolicy {
+    with_state(|state| state.resolve(key))
+}
+
+/// Limits how many bytes of telemetry are passed on per second, none removes the limit.
+///
+/// Values of keys with a [`droppable`](LogPolicy::droppable) policy are dropped while the budget is used up,
+/// every other key is always passed on but still uses up the budget.
+/// Up to a second's worth of unused budget is saved for bursts.
+pub fn set_telemetry_budget(bytes_per_second: Option<u64>) {
+    with_state(|state| {
+        state.budget = bytes_per_second.map(|bytes_per_second| Budget {
+            bytes_per_second,
+            #[allow(clippy::cast_precision_loss)]
+            available: bytes_per_second as f64,
+            last_refill: uptime_micros(),
+        });
+    });
+}
+
+/// How many values have been dropped because the [bandwidth budget](set_telemetry_budget) was used up.
+#[must_use]
+pub fn budget_dropped_entries() -> u64 {
+    BUDGET_DROPPED.load(Ordering::Relaxed)
+}
+
+fn uptime_micros() -> u64 {
+    u64::try_from(frclib_core::time::uptime().as_micros()).unwrap_or(u64::MAX)
+}
+
+/// About how many bytes an entry takes up in a log file or on the wire.
+fn entry_size(entry: &TelemetryEntry) -> usize {
+    let payload = match &entry.value {
+        FrcValue::Void => 0,
+        FrcValue::Boolean(_) => 1,
+        FrcValue::Float(_) => 4,
+        FrcValue::Int(_) | FrcValue::Double(_) => 8,
+        FrcValue::String(value) => value.len(),
+        FrcValue::Raw(bytes) => bytes.len(),
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => bytes.data.len(),
+        FrcValue::BooleanArray(values) => values.len(),
+        FrcValue::FloatArray(values) => values.len() * 4,
+        FrcValue::IntArray(values) => values.len() * 8,
+        FrcValue::DoubleArray(values) => values.len() * 8,
+        FrcValue::StringArray(values) => values.iter().map(|value| value.len() + 4).sum(),
+    };
+    // the key id, size and timestamp of a record
+    payload + 12
+}
+
+/// Removes every entry the policy of its key filters out, called on every flush before any consumer sees them.
+pub fn apply_log_policies(entries: &mut Vec<TelemetryEntry>) {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return;
+    }
+    let mut guard = POLICIES.lock();
+    let Some(state) = guard.as_mut() else {
+        return;
+    };
+    if let Some(budget) = &mut state.budget {
+        let now = uptime_micros();
+        #[allow(clippy::cast_precision_loss)]
+        let rate = budget.bytes_per_second as f64;
+        #[allow(clippy::cast_precision_loss)]
+        let elapsed = now.saturating_sub(budget.last_refill) as f64 / 1_000_000.0;
+        budget.available = elapsed.mul_add(rate, budget.available).min(rate);
+        budget.last_refill = now;
+    }
+    entries.retain(|entry| state.keep(entry));
+    drop(guard);
+}
@@ -0,0 +1,215 @@
+//! Struct schemas and the order they have to be published in.
+//!
+//! Schemas are published as their [`FrcStructure`](frclib_core::structure::FrcStructure) implementation supplies them.
+//! The geometry types end every declaration in the `;` the `WPILib` struct format expects,
+//! but the `FrcStructure` derive glues declarations together without one,
+//! so the schema of a struct derived outside frclib can't be split back into its fields.
+//! Dashboards can't decode those structs and the schemas of the structs they use may not be published,
+//! a warning is logged the first time such a schema is seen.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use frclib_core::structure::{FrcStructDesc, FrcStructDescDB};
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+/// The types the struct format has built in, every other field type is another struct.
+const PRIMITIVE_TYPES: [&str; 14] = [
+    "bool", "char", "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "float",
+    "float32", "double", "float64",
+];
+
+/// The struct types whose schema couldn't be parsed and that have been warned about.
+static UNPARSEABLE: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// The key the schema of a struct type is published under.
+#[must_use]
+pub fn schema_key(type_str: &str) -> String {
+    format!("/.schema/struct:{type_str}")
+}
+
+/// The declarations of a struct schema, each of which ends in `;`.
+fn declarations(schema: &str) -> impl Iterator<Item = &str> {
+    schema
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+}
+
+/// The type and name of a declaration, skipping the values of an enum.
+fn split_declaration(declaration: &str) -> Option<(&str, &str)> {
+    let declaration = match declaration.strip_prefix("enum") {
+        Some(rest) => &rest[rest.find('}')? + 1..],
+        None => declaration,
+    };
+    let (type_str, name) = declaration.trim().split_once(char::is_whitespace)?;
+    Some((type_str, name.trim()))
+}
+
+/// The type of a declaration or none if it isn't a single `type name` declaration of a known type.
+fn declaration_type(declaration: &str) -> Option<&str> {
+    let (type_str, name) = split_declaration(declaration)?;
+    let mut tokens = name.split_whitespace();
+    let _name = tokens.next()?;
+    // only the width of a bit field can follow the name
+    if tokens.next().is_some_and(|token| !token.starts_with(':')) {
+        return None;
+    }
+    (PRIMITIVE_TYPES.contains(&type_str) || FrcStructDescDB::get(type_str).is_some()).then_some(type_str)
+}
+
+fn warn_unparseable(desc: &'static FrcStructDesc, declaration: &str) {
+    let mut warned = UNPARSEABLE.lock();
+    if warned.contains(&desc.type_str) {
+        return;
+    }
+    warned.push(desc.type_str);
+    drop(warned);
+    tracing::warn!(
+        "The schema of struct {} can't be parsed at `{declaration}`, dashboards may not be able to decode it",
+        desc.type_str
+    );
+}
+
+/// The schema of a struct type in the `WPILib` struct format.
+#[must_use]
+pub fn struct_schema(desc: &FrcStructDesc) -> String {
+    (desc.schema_supplier)()
+}
+
+/// A struct type and every struct type its fields use, directly or not,
+/// ordered so every type comes after the types it uses.
+///
+/// A reader can only decode a struct once it has the schemas of all its fields,
+/// so this is the order schemas should be published in.
+#[must_use]
+pub fn schema_dependencies(desc: &'static FrcStructDesc) -> Vec<&'static FrcStructDesc> {
+    fn visit(desc: &'static FrcStructDesc, out: &mut Vec<&'static FrcStructDesc>) {
+        if out.iter().any(|seen| seen.type_str == desc.type_str) {
+            return;
+        }
+        for declaration in declarations(&(desc.schema_supplier)()) {
+            let Some(type_str) = declaration_type(declaration) else {
+                warn_unparseable(desc, declaration);
+                continue;
+            };
+            let nested = Some(type_str)
+                .filter(|type_str| *type_str != desc.type_str)
+                .and_then(FrcStructDescDB::get);
+            if let Some(nested) = nested {
+                visit(nested, out);
+            }
+        }
+        out.push(desc);
+    }
+    let mut out = Vec::new();
+    visit(desc, &mut out);
+    out
+}
+
+/// The size in bytes of a primitive type of the struct format.
+fn primitive_size(type_str: &str) -> Option<usize> {
+    Some(match type_str {
+        "bool" | "char" | "int8" | "uint8" => 1,
+        "int16" | "uint16" => 2,
+        "int32" | "uint32" | "float" | "float32" => 4,
+        "int64" | "uint64" | "double" | "float64" => 8,
+        _ => return None,
+    })
+}
+
+/// A primitive field of a struct with every nested struct flattened into it, see [`struct_layout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructField {
+    /// The path to the field through nested structs and arrays, like `translation/x` or `values/2`.
+    pub name: String,
+    /// The primitive type of the field, like `float64`.
+    pub type_str: &'static str,
+    /// Where the field starts in the packed struct.
+    pub offset: usize,
+}
+
+impl StructField {
+    /// Reads the field out of a packed struct, none if the data is too short.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn read(&self, data: &[u8]) -> Option<FrcValue> {
+        fn bytes<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+            data.get(offset..offset + N)?.try_into().ok()
+        }
+        let offset = self.offset;
+        Some(match self.type_str {
+            "bool" => FrcValue::Boolean(*data.get(offset)? != 0),
+            "char" => FrcValue::String(char::from(*data.get(offset)?).to_string().into()),
+            "int8" => FrcValue::Int(i64::from(i8::from_le_bytes(bytes(data, offset)?))),
+            "uint8" => FrcValue::Int(i64::from(*data.get(offset)?)),
+            "int16" => FrcValue::Int(i64::from(i16::from_le_bytes(bytes(data, offset)?))),
+            "uint16" => FrcValue::Int(i64::from(u16::from_le_bytes(bytes(data, offset)?))),
+            "int32" => FrcValue::Int(i64::from(i32::from_le_bytes(bytes(data, offset)?))),
+            "uint32" => FrcValue::Int(i64::from(u32::from_le_bytes(bytes(data, offset)?))),
+            "int64" => FrcValue::Int(i64::from_le_bytes(bytes(data, offset)?)),
+            "uint64" => FrcValue::Int(u64::from_le_bytes(bytes(data, offset)?) as i64),
+            "float" | "float32" => FrcValue::Float(f32::from_le_bytes(bytes(data, offset)?)),
+            "double" | "float64" => FrcValue::Double(f64::from_le_bytes(bytes(data, offset)?)),
+            _ => return None,
+        })
+    }
+}
+
+fn append_layout(schema: &str, prefix: &str, offset: &mut usize, fields: &mut Vec<StructField>) -> Option<()> {
+    for declaration in declarations(schema) {
+        let (type_str, name) = split_declaration(declaration)?;
+        // bit fields are not supported
+        if name.contains(':') {
+            return None;
+        }
+        let (name, count) = match name.split_once('[') {
+            Some((name, count)) => (name.trim(), count.trim_end_matches(']').trim().parse().ok()?),
+            None => (name, 1),
+        };
+        let nested = FrcStructDescDB::get(type_str);
+        for index in 0..count {
+            let mut path = format!("{prefix}{name}");
+            if count > 1 {
+                path = format!("{path}/{index}");
+            }
+            if let Some(size) = primitive_size(type_str) {
+                let type_str = PRIMITIVE_TYPES.iter().find(|primitive| **primitive == type_str)?;
+                fields.push(StructField {
+                    name: path,
+                    type_str,
+                    offset: *offset,
+                });
+                *offset += size;
+            } else {
+                append_layout(&(nested?.schema_supplier)(), &format!("{path}/"), offset, fields)?;
+            }
+        }
+    }
+    Some(())
+}
+
+/// The layout of every struct type asked for, so schemas are only parsed once.
+#[allow(clippy::type_complexity)]
+static LAYOUTS: LazyLock<Mutex<HashMap<&'static str, Option<Arc<[StructField]>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Every primitive field of a struct type in the order they are packed,
+/// nested structs are flattened so their fields are named by their path.
+///
+/// Returns none if the schema uses a struct type that isn't registered,
+/// uses bit fields, or doesn't add up to the size of the type.
+#[must_use]
+pub fn struct_layout(desc: &'static FrcStructDesc) -> Option<Arc<[StructField]>> {
+    LAYOUTS
+        .lock()
+        .entry(desc.type_str)
+        .or_insert_with(|| {
+            let mut fields = Vec::new();
+            let mut size = 0;
+            append_layout(&(desc.schema_supplier)(), "", &mut size, &mut fields)?;
+            (size == desc.size).then(|| fields.into())
+        })
+        .clone()
+}
@@ -0,0 +1,154 @@
+#[cfg(frc_dev)]
+use std::collections::HashMap;
+use std::fmt::Debug;
+#[cfg(frc_dev)]
+use std::sync::LazyLock;
+
+use frclib_core::value::{FrcValue, IntoFrcValue};
+#[cfg(frc_dev)]
+use parking_lot::Mutex;
+
+#[cfg(frc_dev)]
+use super::log;
+use super::TelemetryKey;
+
+/// Values set with [`set_tunable`], with a generation so every tunable on a key sees each edit once.
+#[cfg(frc_dev)]
+static LOCAL_EDITS: LazyLock<Mutex<HashMap<&'static str, (u64, FrcValue)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Edits a tunable as if a dashboard had, for simulations and tests without a `NetworkTables` client.
+///
+/// Does nothing unless the `frc_dev` cfg is set, like every other way of editing a tunable.
+pub fn set_tunable(key: impl TelemetryKey, value: impl IntoFrcValue) {
+    #[cfg(frc_dev)]
+    {
+        let value = value.into_frc_value();
+        let mut edits = LOCAL_EDITS.lock();
+        let edit = edits.entry(key.into_key()).or_insert((0, FrcValue::Void));
+        edit.0 += 1;
+        edit.1 = value;
+        drop(edits);
+    }
+    #[cfg(not(frc_dev))]
+    let _ = (key, value);
+}
+
+/// A value that can be edited from a dashboard while the robot runs, like the gains of a controller.
+///
+/// The value is published under its key when created,
+/// after that edits to the key from a `NetworkTables` client or [`set_tunable`] are picked up
+/// whenever the tunable is read.
+/// Edits that can't be converted to `T` are ignored.
+///
+/// Tunables are only editable when the `frc_dev` cfg is set,
+/// in every other build they are locked to their default and never publish anything.
+///
+/// ```ignore
+/// let mut k_p = Tunable::new("/Tuning/Arm/kP", 0.5);
+/// let mut k_d = Tunable::new("/Tuning/Arm/kD", 0.0);
+/// // every cycle
+/// if k_p.has_changed() || k_d.has_changed() {
+///     pid.k_p = k_p.get();
+///     pid.k_d = k_d.get();
+/// }
+/// ```
+#[cfg_attr(not(frc_dev), allow(dead_code))]
+pub struct Tunable<T> {
+    key: &'static str,
+    default: T,
+    value: T,
+    changed: bool,
+    /// The last edit seen from `NetworkTables`, so the same value isn't applied twice.
+    remote: Option<FrcValue>,
+    /// The generation of the last edit seen from [`set_tunable`].
+    local_generation: u64,
+}
+
+impl<T> Tunable<T>
+where
+    T: Clone + PartialEq + IntoFrcValue + TryFrom<FrcValue>,
+{
+    #[must_use]
+    pub fn new(key: impl TelemetryKey, default: T) -> Self {
+        let tunable = Self {
+            key: key.into_key(),
+            value: default.clone(),
+            default,
+            changed: false,
+            remote: None,
+            local_generation: 0,
+        };
+        #[cfg(frc_dev)]
+        log(tunable.key, tunable.value.clone());
+        tunable
+    }
+
+    /// Picks up the latest edit, if there is a new one.
+    #[cfg(frc_dev)]
+    fn poll(&mut self) {
+        let local = LOCAL_EDITS
+            .lock()
+            .get(self.key)
+            .filter(|(generation, _)| *generation != self.local_generation)
+            .cloned();
+        let edit = if let Some((generation, value)) = local {
+            self.local_generation = generation;
+            value
+        } else {
+            match super::nt4::latest_value(self.key) {
+                Some(value) if self.remote.as_ref() != Some(&value) => {
+                    self.remote = Some(value.clone());
+                    value
+                }
+                _ => return,
+            }
+        };
+        match T::try_from(edit) {
+            Ok(value) if value != self.value => {
+                self.value = value;
+                self.changed = true;
+                log(self.key, self.value.clone());
+            }
+            Ok(_) => {}
+            Err(_) => tracing::warn!("Ignoring an edit to tunable {} of the wrong type", self.key),
+        }
+    }
+
+    #[cfg(not(frc_dev))]
+    #[allow(clippy::unused_self, clippy::needless_pass_by_ref_mut, clippy::missing_const_for_fn)]
+    fn poll(&mut self) {}
+
+    /// The current value, including any edit made since the last read.
+    pub fn get(&mut self) -> T {
+        self.poll();
+        self.value.clone()
+    }
+
+    /// Whether the value has been edited since the last time this was called,
+    /// for re-applying things derived from it only when needed.
+    pub fn has_changed(&mut self) -> bool {
+        self.poll();
+        std::mem::take(&mut self.changed)
+    }
+
+    #[must_use]
+    pub const fn key(&self) -> &'static str {
+        self.key
+    }
+
+    #[must_use]
+    pub const fn default_value(&self) -> &T {
+        &self.default
+    }
+}
+
+impl<T: Debug> Debug for Tunable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tunable")
+            .field("key", &self.key)
+            .field("value", &self.value)
+            .field("default", &self.default)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,151 @@
+//! Reading and writing telemetry as `WPILib` `.wpilog` data log files.
+//!
+//! Every flush of the data log is appended to a file in the [`log_directory`],
+//! a new file is started the first time anything is flushed in a program.
+//! The files can be opened directly with `AdvantageScope` or the `WPILib` data log tool,
+//! or read back with [`WpiLog`] for analysis.
+
+mod reader;
+mod writer;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use frclib_core::value::FrcType;
+use linkme::distributed_slice;
+use parking_lot::{Mutex, RwLock};
+
+pub use reader::{WpiLog, WpiLogEntryInfo, WpiLogError};
+pub use writer::{encode_payload, WpiLogWriter};
+
+use super::{TELEMETRY_FINALIZERS, TELEMETRY_SINKS};
+
+/// The magic bytes every `.wpilog` file starts with.
+pub const WPILOG_MAGIC: &[u8; 6] = b"WPILOG";
+/// The version of the format this writes, 1.0.
+pub const WPILOG_VERSION: u16 = 0x0100;
+
+const CONTROL_START: u8 = 0;
+const CONTROL_FINISH: u8 = 1;
+const CONTROL_SET_METADATA: u8 = 2;
+
+/// The type strings of every struct type seen so far, by its name, formatted once and kept for the program.
+static STRUCT_TYPE_STRINGS: LazyLock<RwLock<HashMap<&'static str, [&'static str; 2]>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// The `.wpilog` type string of a value type, or none for [`FrcType::Void`] which can't be logged.
+///
+/// `NetworkTables` uses the same strings except for integers.
+#[must_use]
+pub fn type_string(frc_type: FrcType) -> Option<&'static str> {
+    Some(match frc_type {
+        FrcType::Void => return None,
+        FrcType::Raw => "raw",
+        FrcType::Boolean => "boolean",
+        FrcType::Int => "int64",
+        FrcType::Double => "double",
+        FrcType::Float => "float",
+        FrcType::String => "string",
+        FrcType::BooleanArray => "boolean[]",
+        FrcType::IntArray => "int64[]",
+        FrcType::FloatArray => "float[]",
+        FrcType::DoubleArray => "double[]",
+        FrcType::StringArray => "string[]",
+        FrcType::Struct(desc) => struct_type_strings(desc.type_str)[0],
+        FrcType::StructArray(desc) => struct_type_strings(desc.type_str)[1],
+    })
+}
+
+fn struct_type_strings(name: &'static str) -> [&'static str; 2] {
+    if let Some(strings) = STRUCT_TYPE_STRINGS.read().get(name) {
+        return *strings;
+    }
+    *STRUCT_TYPE_STRINGS.write().entry(name).or_insert_with(|| {
+        [
+            Box::leak(format!("struct:{name}").into_boxed_str()),
+            Box::leak(format!("struct:{name}[]").into_boxed_str()),
+        ]
+    })
+}
+
+enum LogFile {
+    /// No file has been opened yet.
+    Pending,
+    Open(WpiLogWriter<BufWriter<File>>),
+    /// Writing failed, nothing else will be written this run.
+    Failed,
+}
+
+static LOG_DIRECTORY: Mutex<Option<PathBuf>> = Mutex::new(None);
+static LOG_FILE: Mutex<LogFile> = Mutex::new(LogFile::Pending);
+
+/// Sets the directory new log files are created in, only affects files that have not been opened yet.
+pub fn set_log_directory(directory: impl Into<PathBuf>) {
+    *LOG_DIRECTORY.lock() = Some(directory.into());
+}
+
+/// The directory log files are created in,
+/// defaults to `/home/lvuser/logs` on a robot and `logs` in the deploy directory in simulation.
+#[must_use]
+pub fn log_directory() -> PathBuf {
+    LOG_DIRECTORY.lock().clone().unwrap_or_else(|| {
+        if cfg!(frc_real) {
+            PathBuf::from("/home/lvuser/logs")
+        } else {
+            Path::new(crate::deploy_dir!()).join("logs")
+        }
+    })
+}
+
+fn open_log_file() -> io::Result<WpiLogWriter<BufWriter<File>>> {
+    let directory = log_directory();
+    fs::create_dir_all(&directory)?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = directory.join(format!("frc_{started}.wpilog"));
+    tracing::info!("Writing data log to {}", path.display());
+    WpiLogWriter::new(BufWriter::new(File::create(path)?), "")
+}
+
+fn with_log_file(func: impl FnOnce(&mut WpiLogWriter<BufWriter<File>>) -> io::Result<()>) {
+    let mut file = LOG_FILE.lock();
+    if matches!(*file, LogFile::Pending) {
+        *file = match open_log_file() {
+            Ok(writer) => LogFile::Open(writer),
+            Err(e) => {
+                tracing::error!("Failed to open data log file: {e}");
+                LogFile::Failed
+            }
+        };
+    }
+    if let LogFile::Open(writer) = &mut *file {
+        if let Err(e) = func(writer) {
+            tracing::error!("Failed to write data log file, logging to it is stopped: {e}");
+            *file = LogFile::Failed;
+        }
+    }
+}
+
+#[distributed_slice(TELEMETRY_SINKS)]
+static WPILOG_SINK: super::TelemetrySink = |entries| {
+    if entries.is_empty() {
+        return;
+    }
+    with_log_file(|writer| entries.iter().try_for_each(|entry| writer.append(entry)));
+};
+
+#[distributed_slice(TELEMETRY_FINALIZERS)]
+static WPILOG_FINALIZER: super::TelemetryFinalizer = || {
+    let mut file = LOG_FILE.lock();
+    if let LogFile::Open(writer) = &mut *file {
+        if let Err(e) = writer.flush() {
+            tracing::error!("Failed to flush data log file: {e}");
+        }
+    }
+};
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::time::Duration;
+
+use frclib_core::structure::{FrcStructDescDB, FrcStructure, FrcStructureBytes};
+use frclib_core::value::FrcValue;
+
+use super::{CONTROL_FINISH, CONTROL_SET_METADATA, CONTROL_START, WPILOG_MAGIC};
+use crate::telemetry::{KeyId, TelemetryEntry};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WpiLogError {
+    #[error("Failed to read log file")]
+    Io(#[from] std::io::Error),
+    #[error("Not a wpilog file")]
+    InvalidHeader,
+    #[error("Unsupported wpilog version {0:#06x}")]
+    UnsupportedVersion(u16),
+    #[error("Log is truncated or corrupt at byte {0}")]
+    Corrupt(usize),
+}
+
+/// How an entry was started in a log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WpiLogEntryInfo {
+    pub name: String,
+    /// The `.wpilog` type string, like `double` or `struct:Pose2d`.
+    pub type_str: String,
+    pub metadata: String,
+}
+
+/// A `.wpilog` file parsed into telemetry entries.
+///
+/// Entry names become keys, each key is only leaked once per process so reading many logs
+/// with the same keys does not grow memory.
+/// Values are decoded by their type string,
+/// struct types without a registered [`FrcStructure`] and unknown types are kept as [`FrcValue::Raw`].
+#[derive(Debug, Clone)]
+pub struct WpiLog {
+    extra_header: String,
+    infos: HashMap<&'static str, WpiLogEntryInfo>,
+    entries: Vec<TelemetryEntry>,
+}
+
+/// Reads little endian fields out of a log, tracking the offset for errors.
+struct Bytes<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], WpiLogError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(WpiLogError::Corrupt(self.offset))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, WpiLogError> {
+        let mut buffer = [0u8; 8];
+        buffer[..len].copy_from_slice(self.take(len)?);
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    fn u32(&mut self) -> Result<u32, WpiLogError> {
+        let offset = self.offset;
+        u32::try_from(self.uint(4)?).map_err(|_| WpiLogError::Corrupt(offset))
+    }
+
+    fn string(&mut self) -> Result<String, WpiLogError> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    const fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+}
+
+impl WpiLog {
+    /// # Errors
+    /// Returns an error if the file could not be read or is not a valid log.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WpiLogError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses a whole log, a record cut off at the end of the data is ignored
+    /// so logs from a program that did not shut down cleanly can still be read.
+    ///
+    /// # Errors
+    /// Returns an error if the header is invalid or a record is corrupt.
+    pub fn parse(data: &[u8]) -> Result<Self, WpiLogError> {
+        let mut bytes = Bytes { data, offset: 0 };
+        if bytes.take(WPILOG_MAGIC.len()).ok() != Some(WPILOG_MAGIC.as_slice()) {
+            return Err(WpiLogError::InvalidHeader);
+        }
+        let version = u16::try_from(bytes.uint(2)?).map_err(|_| WpiLogError::InvalidHeader)?;
+        if version >> 8 != 1 {
+            return Err(WpiLogError::UnsupportedVersion(version));
+        }
+        let extra_header = bytes.string()?;
+
+        let mut log = Self {
+            extra_header,
+            infos: HashMap::new(),
+            entries: Vec::new(),
+        };
+        // the key and type of every started entry id
+        let mut active: HashMap<u32, (KeyId, &'static str, String)> = HashMap::new();
+        while !bytes.is_empty() {
+            let record_start = bytes.offset;
+            // a record header can't be invalid, it can only run past the end of the data
+            let Ok((id, timestamp, payload)) = Self::read_record(&mut bytes) else {
+                tracing::warn!("Log ends with a partial record at byte {record_start}");
+                break;
+            };
+            if id == 0 {
+                log.read_control(payload, &mut active)
+                    .map_err(|_| WpiLogError::Corrupt(record_start))?;
+            } else if let Some((id, key, type_str)) = active.get(&id) {
+                log.entries.push(TelemetryEntry {
+                    id: *id,
+                    key,
+                    timestamp,
+                    value: decode_payload(type_str, payload),
+                });
+            }
+        }
+        Ok(log)
+    }
+
+    fn read_record<'a>(bytes: &mut Bytes<'a>) -> Result<(u32, u64, &'a [u8]), WpiLogError> {
+        let offset = bytes.offset;
+        let header = bytes.take(1)?[0];
+        let id_len = usize::from(header & 0b11) + 1;
+        let size_len = usize::from(header >> 2 & 0b11) + 1;
+        let timestamp_len = usize::from(header >> 4 & 0b111) + 1;
+        let id = u32::try_from(bytes.uint(id_len)?).map_err(|_| WpiLogError::Corrupt(offset))?;
+        let size = usize::try_from(bytes.uint(size_len)?).map_err(|_| WpiLogError::Corrupt(offset))?;
+        let timestamp = bytes.uint(timestamp_len)?;
+        Ok((id, timestamp, bytes.take(size)?))
+    }
+
+    fn read_control(
+        &mut self,
+        payload: &[u8],
+        active: &mut HashMap<u32, (KeyId, &'static str, String)>,
+    ) -> Result<(), WpiLogError> {
+        let mut bytes = Bytes { data: payload, offset: 0 };
+        let control = bytes.take(1)?[0];
+        let id = bytes.u32()?;
+        match control {
+            CONTROL_START => {
+                let info = WpiLogEntryInfo {
+                    name: bytes.string()?,
+                    type_str: bytes.string()?,
+                    metadata: bytes.string()?,
+                };
+                let key = KeyId::intern(&info.name);
+                let _ = active.insert(id, (key, key.name(), info.type_str.clone()));
+                let _ = self.infos.insert(key.name(), info);
+            }
+            CONTROL_FINISH => {
+                let _ = active.remove(&id);
+            }
+            CONTROL_SET_METADATA => {
+                let metadata = bytes.string()?;
+                if let Some(info) = active.get(&id).and_then(|(_, key, _)| self.infos.get_mut(key)) {
+                    info.metadata = metadata;
+                }
+            }
+            _ => tracing::warn!("Unknown wpilog control record {control}"),
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn extra_header(&self) -> &str {
+        &self.extra_header
+    }
+
+    /// Every key started in the log, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.infos.keys().copied()
+    }
+
+    /// How a key was last started in the log.
+    #[must_use]
+    pub fn entry_info(&self, key: &str) -> Option<&WpiLogEntryInfo> {
+        self.infos.get(key)
+    }
+
+    /// Every data record in the order it was written.
+    #[must_use]
+    pub fn entries(&self) -> &[TelemetryEntry] {
+        &self.entries
+    }
+
+    /// Every data record written under a key, in order.
+    pub fn values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a TelemetryEntry> + 'a {
+        self.entries.iter().filter(move |entry| entry.key == key)
+    }
+
+    /// Every data record with a timestamp in `range`, in order.
+    pub fn time_range(&self, range: impl RangeBounds<Duration>) -> impl Iterator<Item = &TelemetryEntry> + '_ {
+        let micros = |bound: Bound<&Duration>| {
+            bound.map(|time| u64::try_from(time.as_micros()).unwrap_or(u64::MAX))
+        };
+        let range = (micros(range.start_bound()), micros(range.end_bound()));
+        self.entries
+            .iter()
+            .filter(move |entry| range.contains(&entry.timestamp))
+    }
+
+    /// A copy of the log with only the records in `range`.
+    #[must_use]
+    pub fn slice(&self, range: impl RangeBounds<Duration>) -> Self {
+        Self {
+            extra_header: self.extra_header.clone(),
+            infos: self.infos.clone(),
+            entries: self.time_range(range).cloned().collect(),
+        }
+    }
+
+    /// Decodes every record under a key logged as a single `T`,
+    /// records of any other type are skipped.
+    pub fn struct_values<'a, T: FrcStructure>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Iterator<Item = (Duration, T)> + 'a {
+        self.values(key).filter_map(|entry| {
+            let data = struct_data::<T>(&entry.value)?;
+            (data.len() == T::SIZE).then(|| {
+                (
+                    Duration::from_micros(entry.timestamp),
+                    T::unpack(&mut Cursor::new(data)),
+                )
+            })
+        })
+    }
+
+    /// Decodes every record under a key logged as an array of `T`,
+    /// records of any other type are skipped.
+    pub fn struct_array_values<'a, T: FrcStructure>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Iterator<Item = (Duration, Vec<T>)> + 'a {
+        self.values(key).filter_map(|entry| {
+            let data = struct_data::<T>(&entry.value)?;
+            if T::SIZE == 0 || !data.len().is_multiple_of(T::SIZE) {
+                return None;
+            }
+            let values = data
+                .chunks_exact(T::SIZE)
+                .map(|chunk| T::unpack(&mut Cursor::new(chunk)))
+                .collect();
+            Some((Duration::from_micros(entry.timestamp), values))
+        })
+    }
+}
+
+/// The packed bytes of a struct value of type `T`,
+/// raw values are accepted since a struct type is only decoded if it was registered.
+fn struct_data<T: FrcStructure>(value: &FrcValue) -> Option<&[u8]> {
+    match value {
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) if bytes.desc.type_str == T::TYPE => {
+            Some(&bytes.data)
+        }
+        FrcValue::Raw(data) => Some(data),
+        _ => None,
+    }
+}
+
+fn decode_array<T, const N: usize>(payload: &[u8], decode: fn([u8; N]) -> T) -> Box<[T]> {
+    payload
+        .chunks_exact(N)
+        .map(|chunk| decode(chunk.try_into().unwrap_or([0; N])))
+        .collect()
+}
+
+fn decode_string_array(payload: &[u8]) -> Option<Box<[Box<str>]>> {
+    let mut bytes = Bytes { data: payload, offset: 0 };
+    let count = bytes.u32().ok()?;
+    (0..count)
+        .map(|_| bytes.string().ok().map(String::into_boxed_str))
+        .collect()
+}
+
+/// Decodes a record payload by its type string, anything that does not decode is kept as raw bytes.
+fn decode_payload(type_str: &str, payload: &[u8]) -> FrcValue {
+    let raw = || FrcValue::Raw(payload.into());
+    match type_str {
+        "boolean" => payload.first().map_or_else(raw, |value| FrcValue::Boolean(*value != 0)),
+        "int64" => payload
+            .try_into()
+            .map_or_else(|_| raw(), |bytes| FrcValue::Int(i64::from_le_bytes(bytes))),
+        "float" => payload
+            .try_into()
+            .map_or_else(|_| raw(), |bytes| FrcValue::Float(f32::from_le_bytes(bytes))),
+        "double" => payload
+            .try_into()
+            .map_or_else(|_| raw(), |bytes| FrcValue::Double(f64::from_le_bytes(bytes))),
+        "string" | "json" => FrcValue::String(String::from_utf8_lossy(payload).into()),
+        "boolean[]" => FrcValue::BooleanArray(payload.iter().map(|value| *value != 0).collect()),
+        "int64[]" => FrcValue::IntArray(decode_array(payload, i64::from_le_bytes)),
+        "float[]" => FrcValue::FloatArray(decode_array(payload, f32::from_le_bytes)),
+        "double[]" => FrcValue::DoubleArray(decode_array(payload, f64::from_le_bytes)),
+        "string[]" => decode_string_array(payload).map_or_else(raw, FrcValue::StringArray),
+        _ => type_str
+            .strip_prefix("struct:")
+            .and_then(|struct_type| decode_struct(struct_type, payload))
+            .unwrap_or_else(raw),
+    }
+}
+
+fn decode_struct(struct_type: &str, payload: &[u8]) -> Option<FrcValue> {
+    let (name, is_array) = struct_type
+        .strip_suffix("[]")
+        .map_or((struct_type, false), |name| (name, true));
+    let desc = FrcStructDescDB::get(name)?;
+    if desc.size == 0 || !payload.len().is_multiple_of(desc.size) {
+        return None;
+    }
+    let count = payload.len() / desc.size;
+    let bytes = Box::new(FrcStructureBytes::from_parts(desc, count, payload.into()));
+    if is_array {
+        Some(FrcValue::StructArray(bytes))
+    } else if count == 1 {
+        Some(FrcValue::Struct(bytes))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use frclib_core::structure::FrcStructDesc;
+use frclib_core::value::{FrcType, FrcValue};
+
+use serde_json::Value as Json;
+
+use super::{type_string, CONTROL_FINISH, CONTROL_SET_METADATA, CONTROL_START, WPILOG_MAGIC, WPILOG_VERSION};
+use crate::telemetry::{
+    key_metadata, metadata_changed_since, metadata_generation, schema_dependencies, schema_key, struct_schema,
+    KeyId, KeyMetadata, TelemetryEntry,
+};
+
+/// Serializes a value to its `.wpilog` record payload, all numbers are little endian.
+pub fn encode_payload(value: &FrcValue, buffer: &mut Vec<u8>) {
+    match value {
+        FrcValue::Void => {}
+        FrcValue::Raw(bytes) => buffer.extend_from_slice(bytes),
+        FrcValue::Boolean(value) => buffer.push(u8::from(*value)),
+        FrcValue::Int(value) => buffer.extend_from_slice(&value.to_le_bytes()),
+        FrcValue::Double(value) => buffer.extend_from_slice(&value.to_le_bytes()),
+        FrcValue::Float(value) => buffer.extend_from_slice(&value.to_le_bytes()),
+        FrcValue::String(value) => buffer.extend_from_slice(value.as_bytes()),
+        FrcValue::BooleanArray(values) => buffer.extend(values.iter().map(|value| u8::from(*value))),
+        FrcValue::IntArray(values) => {
+            for value in values {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        FrcValue::FloatArray(values) => {
+            for value in values {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        FrcValue::DoubleArray(values) => {
+            for value in values {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        FrcValue::StringArray(values) => {
+            push_len(buffer, values.len());
+            for value in values {
+                push_str(buffer, value);
+            }
+        }
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => buffer.extend_from_slice(&bytes.data),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn push_len(buffer: &mut Vec<u8>, len: usize) {
+    buffer.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn push_str(buffer: &mut Vec<u8>, string: &str) {
+    push_len(buffer, string.len());
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+/// The fewest little endian bytes that can hold `value`, at least one.
+#[allow(clippy::cast_possible_truncation)]
+const fn byte_len(value: u64) -> usize {
+    let bits = u64::BITS - value.leading_zeros();
+    if bits == 0 {
+        1
+    } else {
+        bits.div_ceil(8) as usize
+    }
+}
+
+/// The metadata string of an entry, empty if no field of the metadata is set.
+fn metadata_string(metadata: &KeyMetadata) -> String {
+    let json = metadata.to_json();
+    if json.is_empty() {
+        String::new()
+    } else {
+        Json::Object(json).to_string()
+    }
+}
+
+/// Writes a stream of telemetry entries in the `.wpilog` format.
+///
+/// Each key gets its own entry id, started the first time the key is written.
+/// If the type of a key changes its entry is finished and a new one is started,
+/// since an entry can only hold one type.
+/// Struct schemas are written under `/.schema/struct:<type>` the first time a struct type is seen,
+/// along with the schemas of any struct types its fields use.
+/// The [`KeyMetadata`] of a key is written as JSON in the metadata of its entry,
+/// and updated with a set metadata record if it changes after the entry was started.
+pub struct WpiLogWriter<W: Write> {
+    writer: W,
+    /// The entry id and type of every key by [`KeyId`] index.
+    entries: Vec<Option<(u32, FrcType)>>,
+    schemas: HashSet<&'static str>,
+    /// The metadata generation written so far.
+    metadata_generation: u64,
+    next_id: u32,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> WpiLogWriter<W> {
+    /// Writes the file header, `extra_header` is an arbitrary string stored with it.
+    ///
+    /// # Errors
+    /// Returns an error if the header could not be written.
+    pub fn new(mut writer: W, extra_header: &str) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(12 + extra_header.len());
+        header.extend_from_slice(WPILOG_MAGIC);
+        header.extend_from_slice(&WPILOG_VERSION.to_le_bytes());
+        push_str(&mut header, extra_header);
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            entries: Vec::new(),
+            schemas: HashSet::new(),
+            metadata_generation: 0,
+            next_id: 1,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Appends a data record for an entry, starting the entry first if needed.
+    /// [`FrcValue::Void`] values are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn append(&mut self, entry: &TelemetryEntry) -> io::Result<()> {
+        let frc_type = entry.value.get_type();
+        let Some(type_str) = type_string(frc_type) else {
+            return Ok(());
+        };
+        if let FrcType::Struct(desc) | FrcType::StructArray(desc) = frc_type {
+            self.write_schema(desc, entry.timestamp)?;
+        }
+        self.write_changed_metadata(entry.timestamp)?;
+        let key = entry.id.index();
+        if key >= self.entries.len() {
+            self.entries.resize(KeyId::count(), None);
+        }
+        let id = match self.entries[key] {
+            Some((id, existing)) if existing == frc_type => id,
+            existing => {
+                if let Some((id, _)) = existing {
+                    self.write_finish(id, entry.timestamp)?;
+                }
+                let metadata = key_metadata(entry.key).map(|metadata| metadata_string(&metadata));
+                let id = self.start_entry(entry.key, type_str, &metadata.unwrap_or_default(), entry.timestamp)?;
+                self.entries[key] = Some((id, frc_type));
+                id
+            }
+        };
+
+        let mut payload = std::mem::take(&mut self.buffer);
+        payload.clear();
+        encode_payload(&entry.value, &mut payload);
+        let result = self.write_record(id, entry.timestamp, &payload);
+        self.buffer = payload;
+        result
+    }
+
+    /// Sets the metadata string of a key that has already been written.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn set_metadata(&mut self, key: &str, metadata: &str, timestamp: u64) -> io::Result<()> {
+        let Some((id, _)) = KeyId::lookup(key).and_then(|key| self.entries.get(key.index()).copied().flatten()) else {
+            return Ok(());
+        };
+        let mut payload = vec![CONTROL_SET_METADATA];
+        payload.extend_from_slice(&id.to_le_bytes());
+        push_str(&mut payload, metadata);
+        self.write_record(0, timestamp, &payload)
+    }
+
+    /// # Errors
+    /// Returns an error if the underlying writer failed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if the final flush failed.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Updates the metadata of every started entry whose [`KeyMetadata`] changed since it was last written.
+    fn write_changed_metadata(&mut self, timestamp: u64) -> io::Result<()> {
+        let generation = metadata_generation();
+        if generation == self.metadata_generation {
+            return Ok(());
+        }
+        for (key, metadata) in metadata_changed_since(self.metadata_generation) {
+            self.set_metadata(key, &metadata_string(&metadata), timestamp)?;
+        }
+        self.metadata_generation = generation;
+        Ok(())
+    }
+
+    fn write_schema(&mut self, desc: &'static FrcStructDesc, timestamp: u64) -> io::Result<()> {
+        if self.schemas.contains(desc.type_str) {
+            return Ok(());
+        }
+        for desc in schema_dependencies(desc) {
+            if !self.schemas.insert(desc.type_str) {
+                continue;
+            }
+            let id = self.start_entry(&schema_key(desc.type_str), "structschema", "", timestamp)?;
+            self.write_record(id, timestamp, struct_schema(desc).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn start_entry(&mut self, name: &str, type_str: &str, metadata: &str, timestamp: u64) -> io::Result<u32> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut payload = Vec::with_capacity(17 + name.len() + type_str.len() + metadata.len());
+        payload.push(CONTROL_START);
+        payload.extend_from_slice(&id.to_le_bytes());
+        push_str(&mut payload, name);
+        push_str(&mut payload, type_str);
+        push_str(&mut payload, metadata);
+        self.write_record(0, timestamp, &payload)?;
+        Ok(id)
+    }
+
+    fn write_finish(&mut self, id: u32, timestamp: u64) -> io::Result<()> {
+        let mut payload = vec![CONTROL_FINISH];
+        payload.extend_from_slice(&id.to_le_bytes());
+        self.write_record(0, timestamp, &payload)
+    }
+
+    /// Writes a record header with variable length fields followed by the payload.
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_record(&mut self, id: u32, timestamp: u64, payload: &[u8]) -> io::Result<()> {
+        let id_len = byte_len(u64::from(id));
+        let size_len = byte_len(payload.len() as u64);
+        let timestamp_len = byte_len(timestamp);
+        let mut header = [0u8; 17];
+        header[0] = ((id_len - 1) | (size_len - 1) << 2 | (timestamp_len - 1) << 4) as u8;
+        let mut end = 1;
+        for (value, len) in [
+            (u64::from(id), id_len),
+            (payload.len() as u64, size_len),
+            (timestamp, timestamp_len),
+        ] {
+            header[end..end + len].copy_from_slice(&value.to_le_bytes()[..len]);
+            end += len;
+        }
+        self.writer.write_all(&header[..end])?;
+        self.writer.write_all(payload)
+    }
+}
+
+impl<W: Write> std::fmt::Debug for WpiLogWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WpiLogWriter")
+            .field("entries", &self.entries.iter().flatten().count())
+            .field("next_id", &self.next_id)
+            .finish_non_exhaustive()
+    }
+}
+
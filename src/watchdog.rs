@@ -0,0 +1,113 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use frclib_core::time::Instant;
+
+use crate::telemetry::{intern_key, log};
+use crate::EventTypes;
+
+/// Times one iteration of a periodic loop and reports when it takes longer than its timeout.
+///
+/// The iteration is split into named epochs with [`add_epoch`](Watchdog::add_epoch),
+/// so an overrun warning shows where the time went.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    timeout: Duration,
+    start: Instant,
+    last_epoch: Instant,
+    epochs: Vec<(&'static str, Duration)>,
+    overruns: u32,
+}
+
+impl Watchdog {
+    /// Creates a watchdog, a timeout of zero will never overrun.
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            timeout,
+            start: now,
+            last_epoch: now,
+            epochs: Vec::new(),
+            overruns: 0,
+        }
+    }
+
+    pub const fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Starts timing a new iteration, clearing the epochs of the last one.
+    pub fn reset(&mut self) {
+        self.start = Instant::now();
+        self.last_epoch = self.start;
+        self.epochs.clear();
+    }
+
+    /// Records the time since the last epoch (or the reset) under a name,
+    /// adding to the time already recorded if the name was used this iteration.
+    pub fn add_epoch(&mut self, name: &'static str) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_epoch);
+        self.last_epoch = now;
+        if let Some((_, time)) = self.epochs.iter_mut().find(|(epoch, _)| *epoch == name) {
+            *time += elapsed;
+        } else {
+            self.epochs.push((name, elapsed));
+        }
+    }
+
+    /// The time since the last reset.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        !self.timeout.is_zero() && self.elapsed() > self.timeout
+    }
+
+    /// The number of iterations that have overrun since the watchdog was created.
+    #[must_use]
+    pub const fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    #[must_use]
+    pub fn epochs(&self) -> &[(&'static str, Duration)] {
+        &self.epochs
+    }
+
+    /// Ends the iteration, logging the loop time to telemetry
+    /// and warning with the epoch breakdown if the iteration overran.
+    ///
+    /// Returns true if the iteration overran.
+    pub fn check(&mut self) -> bool {
+        let elapsed = self.elapsed();
+        log("/Watchdog/LoopTime", elapsed.as_secs_f64() * 1000.0);
+        if !self.is_expired() {
+            return false;
+        }
+        self.overruns = self.overruns.saturating_add(1);
+        log("/Watchdog/Overruns", self.overruns);
+
+        let mut breakdown = String::new();
+        for (name, time) in &self.epochs {
+            let _ = write!(breakdown, "\n\t{name}: {:.3}ms", time.as_secs_f64() * 1000.0);
+            log(intern_key(&format!("/Watchdog/Epochs/{name}")), time.as_secs_f64() * 1000.0);
+        }
+        tracing::warn!(
+            event = ?EventTypes::Overrun,
+            "Loop overrun, took {:.3}ms of {:.3}ms{breakdown}",
+            elapsed.as_secs_f64() * 1000.0,
+            self.timeout.as_secs_f64() * 1000.0,
+        );
+        true
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(frc_sim)]
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use frclib::telemetry::{self, Alert, AlertLevel, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+/// Everything ever flushed, tests only look at their own groups since they flush concurrently.
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+/// Flushes the data log and returns the last value flushed under `key`.
+fn published(key: &str) -> Option<FrcValue> {
+    telemetry::flush_datalog();
+    FLUSHED
+        .lock()
+        .iter()
+        .rev()
+        .find(|entry| entry.key == key)
+        .map(|entry| entry.value.clone())
+}
+
+fn texts(values: &[&str]) -> Option<FrcValue> {
+    let texts: Vec<Box<str>> = values.iter().map(|text| Box::from(*text)).collect();
+    Some(FrcValue::StringArray(texts.as_slice().into()))
+}
+
+#[test]
+fn active_alerts_are_published_by_level_newest_first() {
+    let first = Alert::in_group("/Levels/Alerts/", "first warning", AlertLevel::Warning);
+    let second = Alert::in_group("/Levels/Alerts", "second warning", AlertLevel::Warning);
+    let error = Alert::in_group("/Levels/Alerts", "error", AlertLevel::Error);
+    let info = Alert::in_group("/Levels/Alerts", "info", AlertLevel::Info);
+    let _inactive = Alert::in_group("/Levels/Alerts", "inactive", AlertLevel::Error);
+
+    first.set(true);
+    sleep(Duration::from_millis(2));
+    second.set(true);
+    error.set(true);
+    info.set(true);
+    assert!(first.is_active());
+
+    assert_eq!(
+        published("/Levels/Alerts/.type"),
+        Some(FrcValue::String("Alerts".into()))
+    );
+    assert_eq!(
+        published("/Levels/Alerts/warnings"),
+        texts(&["second warning", "first warning"])
+    );
+    assert_eq!(published("/Levels/Alerts/errors"), texts(&["error"]));
+    assert_eq!(published("/Levels/Alerts/infos"), texts(&["info"]));
+
+    second.set(false);
+    assert_eq!(published("/Levels/Alerts/warnings"), texts(&["first warning"]));
+}
+
+#[test]
+fn dropped_alerts_are_removed() {
+    let kept = Alert::in_group("/Dropped/Alerts", "kept", AlertLevel::Error);
+    let dropped = Alert::in_group("/Dropped/Alerts", "dropped", AlertLevel::Error);
+    kept.set(true);
+    sleep(Duration::from_millis(2));
+    dropped.set(true);
+    assert_eq!(published("/Dropped/Alerts/errors"), texts(&["dropped", "kept"]));
+
+    drop(dropped);
+    assert_eq!(published("/Dropped/Alerts/errors"), texts(&["kept"]));
+}
+
+#[test]
+fn new_text_is_published_in_place() {
+    let older = Alert::in_group("/Text/Alerts", "older", AlertLevel::Info);
+    let newer = Alert::in_group("/Text/Alerts", "newer", AlertLevel::Info);
+    older.set(true);
+    sleep(Duration::from_millis(2));
+    newer.set(true);
+    assert_eq!(published("/Text/Alerts/infos"), texts(&["newer", "older"]));
+
+    older.set_text("renamed");
+    assert_eq!(older.text(), "renamed");
+    assert_eq!(published("/Text/Alerts/infos"), texts(&["newer", "renamed"]));
+}
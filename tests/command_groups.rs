@@ -0,0 +1,317 @@
+#![cfg(frc_sim)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+use frclib::commands::{
+    deadline, either, parallel, race, select, sequence, Command, CommandExt, CommandScheduler, Requirements,
+    Subsystem, SubsystemId,
+};
+use frclib::driver_station::sim;
+use frclib::robots::RobotMode;
+
+/// Every event of every probe in a test, in order.
+type Events = Rc<RefCell<Vec<String>>>;
+
+/// A command that records its lifecycle and finishes when its flag is set.
+struct Probe {
+    name: &'static str,
+    events: Events,
+    requirements: Requirements,
+    finished: Rc<Cell<bool>>,
+}
+
+impl Probe {
+    fn new(name: &'static str, events: &Events) -> Self {
+        Self {
+            name,
+            events: events.clone(),
+            requirements: Requirements::new(),
+            finished: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// A probe that finishes the first time it is executed.
+    fn finishing(name: &'static str, events: &Events) -> Self {
+        let probe = Self::new(name, events);
+        probe.finished.set(true);
+        probe
+    }
+
+    fn requiring(mut self, subsystem: SubsystemId) -> Self {
+        let _ = self.requirements.insert(subsystem);
+        self
+    }
+}
+
+impl Command for Probe {
+    fn initialize(&mut self) {
+        self.events.borrow_mut().push(format!("{} init", self.name));
+    }
+    fn execute(&mut self) {
+        self.events.borrow_mut().push(format!("{} execute", self.name));
+    }
+    fn end(&mut self, interrupted: bool) {
+        self.events.borrow_mut().push(format!("{} end {interrupted}", self.name));
+    }
+    fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+    fn requirements(&self) -> Requirements {
+        self.requirements.clone()
+    }
+}
+
+struct Arm;
+impl Subsystem for Arm {}
+
+fn take(events: &Events) -> Vec<String> {
+    std::mem::take(&mut *events.borrow_mut())
+}
+
+/// Executes a command once like the scheduler does, ending it if it finished.
+fn step(command: &mut impl Command) -> bool {
+    command.execute();
+    let finished = command.is_finished();
+    if finished {
+        command.end(false);
+    }
+    finished
+}
+
+#[test]
+fn sequence_runs_commands_in_order() {
+    let events = Events::default();
+    let mut group = sequence(vec![
+        Probe::finishing("a", &events).boxed(),
+        Probe::finishing("b", &events).boxed(),
+    ]);
+
+    group.initialize();
+    assert!(!step(&mut group));
+    assert!(step(&mut group));
+    assert_eq!(
+        take(&events),
+        ["a init", "a execute", "a end false", "b init", "b execute", "b end false"]
+    );
+}
+
+#[test]
+fn interrupting_a_sequence_only_ends_the_current_command() {
+    let events = Events::default();
+    let mut group = Probe::finishing("a", &events).and_then(Probe::new("b", &events));
+
+    group.initialize();
+    assert!(!step(&mut group));
+    group.end(true);
+    assert_eq!(
+        take(&events),
+        ["a init", "a execute", "a end false", "b init", "b end true"]
+    );
+}
+
+#[test]
+fn parallel_waits_for_every_command() {
+    let events = Events::default();
+    let slow = Probe::new("slow", &events);
+    let done = slow.finished.clone();
+    let mut group = parallel(vec![Probe::finishing("fast", &events).boxed(), slow.boxed()]);
+
+    group.initialize();
+    assert!(!step(&mut group));
+    done.set(true);
+    assert!(step(&mut group));
+    assert_eq!(
+        take(&events),
+        [
+            "fast init",
+            "slow init",
+            "fast execute",
+            "fast end false",
+            "slow execute",
+            "slow execute",
+            "slow end false"
+        ]
+    );
+}
+
+#[test]
+fn interrupting_a_parallel_group_ends_the_unfinished_commands() {
+    let events = Events::default();
+    let mut group = Probe::finishing("fast", &events).along_with(Probe::new("slow", &events));
+
+    group.initialize();
+    assert!(!step(&mut group));
+    group.end(true);
+    assert_eq!(
+        take(&events),
+        ["fast init", "slow init", "fast execute", "fast end false", "slow execute", "slow end true"]
+    );
+}
+
+#[test]
+fn race_ends_with_the_first_command_and_interrupts_the_rest() {
+    let events = Events::default();
+    let mut group = race(vec![Probe::new("slow", &events).boxed(), Probe::finishing("fast", &events).boxed()]);
+
+    group.initialize();
+    assert!(step(&mut group));
+    assert_eq!(
+        take(&events),
+        [
+            "slow init",
+            "fast init",
+            "slow execute",
+            "fast execute",
+            "slow end true",
+            "fast end false"
+        ]
+    );
+}
+
+#[test]
+fn deadline_ends_with_the_deadline_and_interrupts_the_rest() {
+    let events = Events::default();
+    let leader = Probe::new("deadline", &events);
+    let done = leader.finished.clone();
+    let mut group = deadline(
+        leader.boxed(),
+        vec![Probe::finishing("fast", &events).boxed(), Probe::new("slow", &events).boxed()],
+    );
+
+    group.initialize();
+    assert!(!step(&mut group));
+    done.set(true);
+    assert!(step(&mut group));
+    assert_eq!(
+        take(&events),
+        [
+            "deadline init",
+            "fast init",
+            "slow init",
+            "deadline execute",
+            "fast execute",
+            "fast end false",
+            "slow execute",
+            "deadline execute",
+            "deadline end false",
+            "slow execute",
+            "slow end true"
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot share requirements")]
+fn parallel_commands_cannot_share_requirements() {
+    let events = Events::default();
+    let arm = CommandScheduler::register_subsystem(Arm).id();
+    let _ = Probe::new("a", &events)
+        .requiring(arm)
+        .race_with(Probe::new("b", &events).requiring(arm));
+}
+
+#[test]
+fn repeatedly_restarts_the_command() {
+    let events = Events::default();
+    let mut command = Probe::finishing("a", &events).repeatedly();
+
+    command.initialize();
+    assert!(!step(&mut command));
+    assert!(!step(&mut command));
+    command.end(true);
+    assert_eq!(
+        take(&events),
+        ["a init", "a execute", "a end false", "a init", "a execute", "a end false"]
+    );
+}
+
+#[test]
+fn until_interrupts_once_the_condition_is_true() {
+    let events = Events::default();
+    let stop = Rc::new(Cell::new(false));
+    let condition = stop.clone();
+    let mut command = Probe::new("a", &events).until(move || condition.get());
+
+    command.initialize();
+    assert!(!step(&mut command));
+    stop.set(true);
+    assert!(step(&mut command));
+    assert_eq!(take(&events), ["a init", "a execute", "a execute", "a end true"]);
+}
+
+#[test]
+fn with_timeout_interrupts_after_the_timeout() {
+    let events = Events::default();
+    let mut command = Probe::new("a", &events).with_timeout(Duration::from_millis(20));
+
+    command.initialize();
+    assert!(!step(&mut command));
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(step(&mut command));
+    assert_eq!(take(&events), ["a init", "a execute", "a execute", "a end true"]);
+}
+
+#[test]
+fn proxy_schedules_the_command_without_its_requirements() {
+    sim::set_robot_mode(RobotMode::Teleop);
+    let events = Events::default();
+    let arm = CommandScheduler::register_subsystem(Arm).id();
+    let proxied = Probe::new("proxied", &events).requiring(arm);
+    let done = proxied.finished.clone();
+    let mut proxy = proxied.proxy();
+    assert!(proxy.requirements().is_empty());
+
+    proxy.initialize();
+    assert!(CommandScheduler::requiring(arm).is_some());
+    assert!(!step(&mut proxy));
+    done.set(true);
+    CommandScheduler::run(true);
+    assert!(step(&mut proxy));
+    assert_eq!(
+        take(&events),
+        ["proxied init", "proxied execute", "proxied end false"]
+    );
+}
+
+#[test]
+fn either_picks_a_command_when_initialized() {
+    let events = Events::default();
+    let choice = Rc::new(Cell::new(true));
+    let condition = choice.clone();
+    let mut command = either(
+        Probe::finishing("true", &events).boxed(),
+        Probe::finishing("false", &events).boxed(),
+        move || condition.get(),
+    );
+
+    choice.set(false);
+    command.initialize();
+    assert!(step(&mut command));
+    assert_eq!(take(&events), ["false init", "false execute", "false end false"]);
+}
+
+#[test]
+fn select_runs_the_command_for_the_key() {
+    let events = Events::default();
+    let key = Rc::new(Cell::new(2));
+    let selector = key.clone();
+    let mut command = select(
+        HashMap::from([
+            (1, Probe::finishing("one", &events).boxed()),
+            (2, Probe::finishing("two", &events).boxed()),
+        ]),
+        move || selector.get(),
+    );
+
+    command.initialize();
+    assert!(step(&mut command));
+    assert_eq!(take(&events), ["two init", "two execute", "two end false"]);
+
+    key.set(3);
+    command.initialize();
+    assert!(command.is_finished());
+    assert!(take(&events).is_empty());
+}
@@ -0,0 +1,130 @@
+#![cfg(frc_sim)]
+
+use std::fs;
+use std::time::Duration;
+
+use frclib::math::geometry::Translation2d;
+use frclib::telemetry::csv::{start_csv_log, stop_csv_log, wpilog_to_csv, CsvWriter};
+use frclib::telemetry::wpilog::{set_log_directory, WpiLog, WpiLogWriter};
+use frclib::telemetry::{self, flush_datalog, TelemetryEntry};
+use frclib::units::length::Meter;
+use frclib_core::value::FrcValue;
+
+fn parse_log(entries: &[TelemetryEntry]) -> WpiLog {
+    let mut writer = WpiLogWriter::new(Vec::new(), "").expect("writing to a vec can't fail");
+    for entry in entries {
+        writer.append(entry).expect("writing to a vec can't fail");
+    }
+    WpiLog::parse(&writer.into_inner().expect("writing to a vec can't fail")).expect("the log is valid")
+}
+
+#[test]
+fn columns_are_added_as_keys_show_up() {
+    let translation = Translation2d {
+        x: Meter::new(1.0),
+        y: Meter::new(-2.5),
+    };
+    let mut writer = CsvWriter::new(Vec::new());
+    writer
+        .write_row(
+            1_000,
+            &[
+                TelemetryEntry::new("/Csv/A", 1_000, FrcValue::Double(1.5)),
+                TelemetryEntry::new("/Csv/Text", 1_000, FrcValue::String("hi, \"you\"".into())),
+            ],
+        )
+        .expect("writing to a vec can't fail");
+    writer
+        .write_row(
+            2_500,
+            &[
+                TelemetryEntry::new("/Csv/A", 2_000, FrcValue::Double(2.5)),
+                TelemetryEntry::new("/Csv/Translation", 2_000, FrcValue::from_struct(&translation)),
+                TelemetryEntry::new("/Csv/Array", 2_500, FrcValue::DoubleArray([1.0, 2.0].into())),
+            ],
+        )
+        .expect("writing to a vec can't fail");
+    writer
+        .write_row(3_000, &[TelemetryEntry::new("/Csv/A", 3_000, FrcValue::Double(3.5))])
+        .expect("writing to a vec can't fail");
+
+    assert_eq!(
+        writer.header(),
+        "timestamp,/Csv/A,/Csv/Text,/Csv/Translation/x,/Csv/Translation/y,/Csv/Array\n"
+    );
+    let rows = String::from_utf8(writer.into_inner().expect("writing to a vec can't fail"))
+        .expect("rows are utf-8");
+    assert_eq!(
+        rows,
+        "0.001000,1.5,\"hi, \"\"you\"\"\"\n\
+         0.002500,2.5,,1,-2.5,\"[1, 2]\"\n\
+         0.003000,3.5\n"
+    );
+}
+
+#[test]
+fn wpilog_rows_are_grouped_by_period() {
+    let log = parse_log(&[
+        TelemetryEntry::new("/x", 0, FrcValue::Double(1.0)),
+        TelemetryEntry::new("/y", 5_000, FrcValue::Double(2.0)),
+        TelemetryEntry::new("/x", 21_000, FrcValue::Double(3.0)),
+        TelemetryEntry::new("/x", 22_000, FrcValue::Double(4.0)),
+        TelemetryEntry::new("/y", 50_000, FrcValue::Double(5.0)),
+    ]);
+
+    let csv = wpilog_to_csv(&log, Vec::new(), Duration::from_millis(20)).expect("writing to a vec can't fail");
+    assert_eq!(
+        String::from_utf8(csv).expect("rows are utf-8"),
+        "timestamp,/x,/y\n0.005000,1,2\n0.022000,4\n0.050000,,5\n"
+    );
+    let csv = wpilog_to_csv(&log, Vec::new(), Duration::ZERO).expect("writing to a vec can't fail");
+    assert_eq!(
+        String::from_utf8(csv).expect("rows are utf-8"),
+        "timestamp,/x,/y\n0.000000,1\n0.005000,,2\n0.021000,3\n0.022000,4\n0.050000,,5\n"
+    );
+}
+
+#[test]
+fn wpilog_timestamps_going_backwards_stay_in_their_row() {
+    let log = parse_log(&[
+        TelemetryEntry::new("/x", 100_000, FrcValue::Double(1.0)),
+        TelemetryEntry::new("/x", 50_000, FrcValue::Double(2.0)),
+        TelemetryEntry::new("/y", 60_000, FrcValue::Double(3.0)),
+    ]);
+
+    let csv = wpilog_to_csv(&log, Vec::new(), Duration::from_millis(20)).expect("writing to a vec can't fail");
+    assert_eq!(
+        String::from_utf8(csv).expect("rows are utf-8"),
+        "timestamp,/x,/y\n0.060000,2,3\n"
+    );
+}
+
+#[test]
+fn live_log_keeps_its_header_up_to_date() {
+    let directory = std::env::temp_dir().join(format!("frclib_csv_test_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    set_log_directory(&directory);
+    let path = start_csv_log().expect("the log directory can be written");
+    let header = path.with_extension("csv.header");
+
+    telemetry::log("/Live/A", 1.5);
+    flush_datalog();
+    assert_eq!(fs::read_to_string(&header).expect("the header is written"), "timestamp,/Live/A\n");
+
+    telemetry::log("/Live/B", true);
+    flush_datalog();
+    assert_eq!(
+        fs::read_to_string(&header).expect("the header is written"),
+        "timestamp,/Live/A,/Live/B\n"
+    );
+
+    stop_csv_log();
+    assert!(!header.exists());
+    let table = fs::read_to_string(&path).expect("the table is written");
+    let mut lines = table.lines();
+    assert_eq!(lines.next(), Some("timestamp,/Live/A,/Live/B"));
+    assert!(lines.next().is_some_and(|row| row.ends_with(",1.5")));
+    assert!(lines.next().is_some_and(|row| row.ends_with(",,true")));
+    assert_eq!(lines.next(), None);
+    let _ = fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,107 @@
+#![cfg(frc_sim)]
+
+use frclib::driver_station::{sim, Alliance, DriverStation, MatchInfo};
+use frclib::robots::RobotMode;
+use frclib_core::hal::rt::station_interface::{EnabledState, Mode, StationData};
+use parking_lot::Mutex;
+
+/// Held by every test that touches the simulated driver station, it is global.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn station(enabled_state: EnabledState, mode: Mode) -> StationData {
+    StationData {
+        enabled_state,
+        mode,
+        ..StationData::default()
+    }
+}
+
+#[test]
+fn robot_mode_follows_the_control_word() {
+    for mode in [Mode::Teleop, Mode::Auto, Mode::Test] {
+        assert_eq!(
+            RobotMode::from_station_data(&station(EnabledState::Disabled, mode)),
+            RobotMode::Disabled
+        );
+        assert_eq!(
+            RobotMode::from_station_data(&station(EnabledState::EStopped, mode)),
+            RobotMode::Disabled
+        );
+    }
+    assert_eq!(
+        RobotMode::from_station_data(&station(EnabledState::Enabled, Mode::Auto)),
+        RobotMode::Autonomous
+    );
+    assert_eq!(
+        RobotMode::from_station_data(&station(EnabledState::Enabled, Mode::Teleop)),
+        RobotMode::Teleop
+    );
+    assert_eq!(
+        RobotMode::from_station_data(&station(EnabledState::Enabled, Mode::Test)),
+        RobotMode::Test
+    );
+}
+
+#[test]
+fn sim_setters_are_visible_immediately() {
+    let _serial = SERIAL.lock();
+    sim::reset();
+
+    sim::set_robot_mode(RobotMode::Autonomous);
+    assert_eq!(DriverStation::robot_mode(), RobotMode::Autonomous);
+    assert!(DriverStation::is_enabled() && DriverStation::is_autonomous());
+    sim::set_robot_mode(RobotMode::Test);
+    assert!(DriverStation::is_test());
+    sim::set_robot_mode(RobotMode::Disabled);
+    assert!(DriverStation::is_disabled());
+
+    sim::set_ds_attached(true);
+    sim::set_fms_attached(true);
+    sim::set_alliance(Some(Alliance::Blue));
+    sim::set_station_number(Some(2));
+    sim::set_match_number(42);
+    assert!(DriverStation::is_ds_attached() && DriverStation::is_fms_attached());
+    assert_eq!(
+        DriverStation::match_info(),
+        MatchInfo {
+            alliance: Some(Alliance::Blue),
+            station_number: Some(2),
+            match_number: 42,
+        }
+    );
+    sim::reset();
+}
+
+#[test]
+fn estop_holds_until_cleared() {
+    let _serial = SERIAL.lock();
+    sim::reset();
+
+    sim::set_robot_mode(RobotMode::Teleop);
+    sim::set_estopped(true);
+    assert!(DriverStation::is_estopped());
+    sim::set_enabled(true);
+    assert!(DriverStation::is_estopped());
+    assert_eq!(DriverStation::robot_mode(), RobotMode::Disabled);
+
+    sim::set_estopped(false);
+    sim::set_enabled(true);
+    assert_eq!(DriverStation::robot_mode(), RobotMode::Teleop);
+    sim::reset();
+}
+
+#[test]
+fn reset_clears_every_override() {
+    let _serial = SERIAL.lock();
+    sim::set_robot_mode(RobotMode::Teleop);
+    sim::set_estopped(true);
+    sim::set_ds_attached(true);
+    sim::set_alliance(Some(Alliance::Red));
+    sim::set_match_number(7);
+
+    sim::reset();
+    assert_eq!(DriverStation::robot_mode(), RobotMode::Disabled);
+    assert!(!DriverStation::is_estopped());
+    assert!(!DriverStation::is_ds_attached());
+    assert_eq!(DriverStation::match_info(), MatchInfo::default());
+}
@@ -0,0 +1,116 @@
+#![cfg(frc_sim)]
+
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use frclib::telemetry::foxglove::FoxgloveServer;
+use frclib::telemetry::TelemetryEntry;
+use frclib_core::value::FrcValue;
+use serde_json::Value as Json;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+fn connect(server: &FoxgloveServer) -> Client {
+    let mut request = format!("ws://{}", server.local_addr())
+        .into_client_request()
+        .expect("the url is valid");
+    let _ = request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "foxglove.websocket.v1".parse().expect("the header is valid"),
+    );
+    tungstenite::connect(request).expect("the server accepts clients").0
+}
+
+fn read_text(client: &mut Client) -> Json {
+    match client.read().expect("the server stays connected") {
+        Message::Text(text) => serde_json::from_str(&text).expect("text messages are json"),
+        message => panic!("expected a text message, got {message:?}"),
+    }
+}
+
+/// Reads a message data frame, returning its subscription id, timestamp and JSON payload.
+fn read_message(client: &mut Client) -> (u32, u64, Json) {
+    let Message::Binary(data) = client.read().expect("the server stays connected") else {
+        panic!("expected a binary message");
+    };
+    assert_eq!(data[0], 0x01);
+    let subscription = u32::from_le_bytes(data[1..5].try_into().expect("the frame has a subscription id"));
+    let timestamp = u64::from_le_bytes(data[5..13].try_into().expect("the frame has a timestamp"));
+    let payload = serde_json::from_slice(&data[13..]).expect("payloads are json");
+    (subscription, timestamp, payload)
+}
+
+fn wait_for_clients(server: &FoxgloveServer, count: usize) {
+    let start = Instant::now();
+    while server.client_count() != count {
+        assert!(start.elapsed() < Duration::from_secs(5), "the client count never reached {count}");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn subscribers_get_the_latest_and_new_messages() {
+    let server = FoxgloveServer::bind("127.0.0.1:0").expect("any local port is free");
+    server.publish(&[TelemetryEntry::new("/Robot/Speed", 5, FrcValue::Double(2.0))]);
+
+    let mut client = connect(&server);
+    assert_eq!(read_text(&mut client)["op"], "serverInfo");
+    let advertisement = read_text(&mut client);
+    assert_eq!(advertisement["op"], "advertise");
+    let channel = &advertisement["channels"][0];
+    assert_eq!(channel["topic"], "/Robot/Speed");
+
+    client
+        .send(Message::Text(
+            serde_json::json!({
+                "op": "subscribe",
+                "subscriptions": [{ "id": 3, "channelId": channel["id"] }],
+            })
+            .to_string(),
+        ))
+        .expect("the server stays connected");
+    let (subscription, timestamp, payload) = read_message(&mut client);
+    assert_eq!((subscription, timestamp), (3, 5_000));
+    assert_eq!(payload["value"], 2.0);
+
+    server.publish(&[
+        TelemetryEntry::new("/Robot/Speed", 9, FrcValue::Double(3.0)),
+        TelemetryEntry::new("/Robot/Other", 9, FrcValue::Int(1)),
+    ]);
+    let (subscription, timestamp, payload) = read_message(&mut client);
+    assert_eq!((subscription, timestamp), (3, 9_000));
+    assert_eq!(payload["value"], 3.0);
+    let advertisement = read_text(&mut client);
+    assert_eq!(advertisement["channels"][0]["topic"], "/Robot/Other");
+}
+
+#[test]
+fn clients_that_fall_behind_are_disconnected() {
+    let server = FoxgloveServer::bind("127.0.0.1:0").expect("any local port is free");
+    server.publish(&[TelemetryEntry::new("/Robot/Blob", 1, FrcValue::String("".into()))]);
+    let mut client = connect(&server);
+    let _ = read_text(&mut client);
+    let channel = read_text(&mut client)["channels"][0]["id"].clone();
+    client
+        .send(Message::Text(
+            serde_json::json!({
+                "op": "subscribe",
+                "subscriptions": [{ "id": 1, "channelId": channel }],
+            })
+            .to_string(),
+        ))
+        .expect("the server stays connected");
+    wait_for_clients(&server, 1);
+
+    // the client stops reading, so its socket and then its queue fill up
+    let payload = FrcValue::String("x".repeat(64 * 1024).into());
+    let start = Instant::now();
+    while server.client_count() > 0 {
+        assert!(start.elapsed() < Duration::from_secs(30), "the client was never disconnected");
+        server.publish(&[TelemetryEntry::new("/Robot/Blob", 1, payload.clone())]);
+    }
+    drop(client);
+}
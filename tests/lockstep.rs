@@ -0,0 +1,131 @@
+#![cfg(frc_sim)]
+
+use std::time::Duration;
+
+use frclib::commands::{CommandExt, InstantCommand, WaitCommand};
+use frclib::robots::{lockstep::Lockstep, PeriodicCallbacks, RobotMode, UserRobot};
+use frclib::telemetry::{self, LogPolicy};
+use frclib_core::value::FrcValue;
+
+#[derive(Default)]
+struct TestRobot {
+    periodic: u32,
+    teleop: u32,
+    teleop_inits: u32,
+    fast: u32,
+    /// How many of the next teleop periodics panic.
+    panics: u32,
+    log_constant: bool,
+}
+
+impl UserRobot for TestRobot {
+    fn construct() -> Self {
+        Self::default()
+    }
+
+    fn robot_init(&mut self) {}
+
+    fn robot_periodic(&mut self, _: Duration) {
+        self.periodic += 1;
+        telemetry::log("/Lockstep/Periodic", self.periodic);
+        if self.log_constant {
+            telemetry::log("/Lockstep/Constant", 1.0);
+        }
+    }
+
+    fn robot_teleop_init(&mut self) {
+        self.teleop_inits += 1;
+        WaitCommand::new(Duration::from_millis(100))
+            .and_then(InstantCommand::new(|| telemetry::log("/Lockstep/Waited", true), &[]))
+            .schedule();
+    }
+
+    fn robot_teleop_periodic(&mut self, period: Duration) {
+        if self.panics > 0 {
+            self.panics -= 1;
+            panic!("teleop failed");
+        }
+        self.teleop += 1;
+        assert!(period <= Duration::from_millis(20));
+        let _ = std::thread::spawn(|| telemetry::log("/Lockstep/Thread", true)).join();
+    }
+
+    fn register_periodic(&mut self, callbacks: &mut PeriodicCallbacks<Self>) {
+        callbacks.add(Duration::from_millis(5), Duration::ZERO, |robot, _| {
+            robot.fast += 1;
+            telemetry::log("/Lockstep/Fast", robot.fast);
+        });
+    }
+}
+
+#[test]
+fn steps_advance_simulated_time() {
+    let mut sim = Lockstep::<TestRobot>::new();
+    sim.step_n(10);
+    assert_eq!(sim.now(), Duration::from_millis(200));
+    assert_eq!(sim.robot().periodic, 10);
+    assert_eq!(sim.robot().fast, 39);
+    assert_eq!(sim.mode(), RobotMode::Disabled);
+    assert_eq!(sim.robot().teleop, 0);
+}
+
+#[test]
+fn mode_changes_and_commands_run_between_steps() {
+    let mut sim = Lockstep::<TestRobot>::new();
+    sim.step_n(2);
+    sim.set_mode(RobotMode::Teleop);
+    sim.step_n(5);
+    assert_eq!(sim.robot().teleop_inits, 1);
+    assert_eq!(sim.robot().teleop, 5);
+    assert_eq!(sim.latest_value("/Lockstep/Waited"), None);
+    sim.step_n(2);
+    assert_eq!(sim.latest_value("/Lockstep/Waited"), Some(FrcValue::Boolean(true)));
+    assert_eq!(sim.latest_value("/Lockstep/Periodic"), Some(FrcValue::Int(9)));
+}
+
+#[test]
+fn telemetry_goes_through_the_flush() {
+    telemetry::set_log_policy("/Lockstep/Constant", LogPolicy::ON_CHANGE);
+    let mut sim = Lockstep::<TestRobot>::new();
+    sim.robot_mut().log_constant = true;
+    sim.set_mode(RobotMode::Teleop);
+    sim.step_n(3);
+    assert_eq!(sim.latest_value("/Lockstep/Thread"), Some(FrcValue::Boolean(true)));
+    assert_eq!(sim.values("/Lockstep/Constant").len(), 1);
+
+    let periodic = sim.values("/Lockstep/Periodic");
+    let times: Vec<_> = periodic.iter().map(|(time, _)| *time).collect();
+    assert_eq!(
+        times,
+        [Duration::ZERO, Duration::from_millis(20), Duration::from_millis(40)]
+    );
+}
+
+#[test]
+fn callbacks_between_loops_are_flushed_with_the_next_loop() {
+    let mut sim = Lockstep::<TestRobot>::new();
+    sim.step();
+    assert_eq!(sim.robot().fast, 3);
+    assert!(sim.values("/Lockstep/Fast").is_empty());
+
+    sim.step();
+    let times: Vec<_> = sim.values("/Lockstep/Fast").iter().map(|(time, _)| *time).collect();
+    assert_eq!(times, [5, 10, 15, 20].map(Duration::from_millis));
+}
+
+#[test]
+fn panics_disable_the_robot_for_a_moment() {
+    let mut sim = Lockstep::<TestRobot>::new();
+    sim.robot_mut().panics = 1;
+    sim.set_mode(RobotMode::Teleop);
+    sim.step();
+    assert_eq!(sim.latest_value("/Recovery/Recovered"), Some(FrcValue::Boolean(true)));
+    assert_eq!(sim.mode(), RobotMode::Disabled);
+
+    sim.step_n(49);
+    assert_eq!(sim.robot().teleop, 0);
+    sim.step();
+    assert_eq!(sim.mode(), RobotMode::Teleop);
+    assert_eq!(sim.robot().teleop, 1);
+    assert_eq!(sim.robot().periodic, 51);
+}
@@ -0,0 +1,103 @@
+#![cfg(frc_sim)]
+
+use std::time::Duration;
+
+use frclib::telemetry::{self, LogPolicy, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+use frclib::units::time::Millisecond;
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+/// Held by every test, flushing drains the telemetry of every thread.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+/// Logs every value at its time in milliseconds and flushes after each,
+/// returning the times and values of `key` that were passed on.
+fn log_and_flush(key: &'static str, values: &[(u64, f64)]) -> Vec<(u64, FrcValue)> {
+    telemetry::wpilog::set_log_directory(std::env::temp_dir().join("frclib_policy_test"));
+    for &(time, value) in values {
+        #[allow(clippy::cast_precision_loss)]
+        telemetry::log_with_timestamp(key, value, Millisecond::new(time as f64));
+        telemetry::flush_datalog();
+    }
+    FLUSHED
+        .lock()
+        .iter()
+        .filter(|entry| entry.key == key)
+        .map(|entry| (entry.timestamp / 1_000, entry.value.clone()))
+        .collect()
+}
+
+#[test]
+fn on_change_only_passes_changes() {
+    let _serial = SERIAL.lock();
+    telemetry::set_log_policy("/Policy/Changes", LogPolicy { keepalive: Duration::ZERO, ..LogPolicy::ON_CHANGE });
+    let values = [(0, 1.0), (20, 1.0), (40, 2.0), (60, 2.0), (80, 1.0)];
+
+    assert_eq!(
+        log_and_flush("/Policy/Changes/Value", &values),
+        [(0, FrcValue::Double(1.0)), (40, FrcValue::Double(2.0)), (80, FrcValue::Double(1.0))]
+    );
+}
+
+#[test]
+fn keepalive_repeats_unchanged_values() {
+    let _serial = SERIAL.lock();
+    telemetry::set_log_policy(
+        "/Policy/Keepalive",
+        LogPolicy { keepalive: Duration::from_millis(100), ..LogPolicy::ON_CHANGE },
+    );
+    let values: Vec<_> = (0..=15).map(|step| (step * 20, if step < 6 { 1.0 } else { 2.0 })).collect();
+
+    let times: Vec<_> = log_and_flush("/Policy/Keepalive/Value", &values)
+        .into_iter()
+        .map(|(time, _)| time)
+        .collect();
+    assert_eq!(times, [0, 100, 120, 220]);
+}
+
+#[test]
+fn decimation_limits_the_rate() {
+    let _serial = SERIAL.lock();
+    telemetry::set_log_policy("/Policy/Decimated", LogPolicy::decimated(25.0));
+    #[allow(clippy::cast_precision_loss)]
+    let values: Vec<_> = (0..=10).map(|step| (step * 10, step as f64)).collect();
+
+    assert_eq!(
+        log_and_flush("/Policy/Decimated", &values),
+        [(0, FrcValue::Double(0.0)), (40, FrcValue::Double(4.0)), (80, FrcValue::Double(8.0))]
+    );
+    assert_eq!(telemetry::log_policy("/Policy/Decimated/Nested").min_period, Duration::from_millis(40));
+    assert_eq!(telemetry::log_policy("/Policy/DecimatedSibling"), LogPolicy::ALWAYS);
+}
+
+#[test]
+fn droppable_keys_are_dropped_once_the_budget_is_used_up() {
+    let _serial = SERIAL.lock();
+    telemetry::set_log_policy("/Budget/Droppable", LogPolicy { droppable: true, ..LogPolicy::ALWAYS });
+    let passed = |key| FLUSHED.lock().iter().filter(|entry| entry.key == key).count();
+    let dropped = telemetry::budget_dropped_entries();
+    // a double takes up 20 bytes, so a second's worth of budget fits 50 of them
+    telemetry::set_telemetry_budget(Some(1_000));
+
+    for value in 0..60 {
+        telemetry::log("/Budget/Droppable", f64::from(value));
+    }
+    telemetry::log("/Budget/Kept", 1.0);
+    telemetry::flush_datalog();
+    assert_eq!(passed("/Budget/Droppable"), 50);
+    assert_eq!(passed("/Budget/Kept"), 1);
+    assert_eq!(telemetry::budget_dropped_entries() - dropped, 10);
+
+    // the budget refills at a byte per millisecond
+    std::thread::sleep(Duration::from_millis(100));
+    telemetry::log("/Budget/Droppable", 60.0);
+    telemetry::flush_datalog();
+    assert_eq!(passed("/Budget/Droppable"), 51);
+
+    telemetry::set_telemetry_budget(None);
+    telemetry::clear_log_policy("/Budget/Droppable");
+}
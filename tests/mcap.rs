@@ -0,0 +1,258 @@
+#![cfg(frc_sim)]
+
+use frclib::math::geometry::Pose3d;
+use frclib::telemetry::mcap::{wpilog_to_mcap, McapWriter, MCAP_MAGIC};
+use frclib::telemetry::wpilog::{WpiLog, WpiLogWriter};
+use frclib::telemetry::TelemetryEntry;
+use frclib_core::value::FrcValue;
+
+/// The records of an MCAP file between its magic bytes, with the offset each one starts at.
+#[derive(Debug)]
+enum Record {
+    Schema {
+        id: u16,
+        name: String,
+        encoding: String,
+    },
+    Channel {
+        id: u16,
+        schema: u16,
+        topic: String,
+        encoding: String,
+    },
+    Message {
+        channel: u16,
+        sequence: u32,
+        time: u64,
+        data: serde_json::Value,
+    },
+    Statistics {
+        messages: u64,
+        schemas: u16,
+        channels: u32,
+        start: u64,
+        end: u64,
+        counts: Vec<(u16, u64)>,
+    },
+    Footer {
+        summary_start: u64,
+    },
+    Other(u8),
+}
+
+/// A cursor over a record body, panicking on truncated records.
+struct Body<'a>(&'a [u8]);
+
+impl Body<'_> {
+    fn take(&mut self, len: usize) -> &[u8] {
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        taken
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take(2).try_into().expect("the slice is 2 bytes"))
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take(4).try_into().expect("the slice is 4 bytes"))
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take(8).try_into().expect("the slice is 8 bytes"))
+    }
+
+    fn string(&mut self) -> String {
+        let len = self.u32() as usize;
+        String::from_utf8(self.take(len).to_vec()).expect("strings are utf-8")
+    }
+}
+
+fn parse(data: &[u8]) -> Vec<(u64, Record)> {
+    assert!(data.starts_with(MCAP_MAGIC), "the file starts with the magic");
+    assert!(data.ends_with(MCAP_MAGIC), "the file ends with the magic");
+    let mut records = Vec::new();
+    let mut offset = MCAP_MAGIC.len();
+    while offset < data.len() - MCAP_MAGIC.len() {
+        let opcode = data[offset];
+        let len = u64::from_le_bytes(data[offset + 1..offset + 9].try_into().expect("the slice is 8 bytes"));
+        let start = offset + 9;
+        let end = start + usize::try_from(len).expect("records fit in memory");
+        let mut body = Body(&data[start..end]);
+        let record = match opcode {
+            0x03 => {
+                let id = body.u16();
+                let name = body.string();
+                let encoding = body.string();
+                let _schema = body.string();
+                Record::Schema { id, name, encoding }
+            }
+            0x04 => Record::Channel {
+                id: body.u16(),
+                schema: body.u16(),
+                topic: body.string(),
+                encoding: body.string(),
+            },
+            0x05 => {
+                let channel = body.u16();
+                let sequence = body.u32();
+                let time = body.u64();
+                let _publish_time = body.u64();
+                let data = serde_json::from_slice(body.0).expect("messages are json");
+                Record::Message {
+                    channel,
+                    sequence,
+                    time,
+                    data,
+                }
+            }
+            0x0B => {
+                let messages = body.u64();
+                let schemas = body.u16();
+                let channels = body.u32();
+                let _attachments_metadata_chunks = body.take(12);
+                let start = body.u64();
+                let end = body.u64();
+                let count_bytes = body.u32() as usize;
+                let counts = (0..count_bytes / 10).map(|_| (body.u16(), body.u64())).collect();
+                Record::Statistics {
+                    messages,
+                    schemas,
+                    channels,
+                    start,
+                    end,
+                    counts,
+                }
+            }
+            0x02 => Record::Footer {
+                summary_start: body.u64(),
+            },
+            opcode => Record::Other(opcode),
+        };
+        records.push((offset as u64, record));
+        offset = end;
+    }
+    assert_eq!(
+        offset,
+        data.len() - MCAP_MAGIC.len(),
+        "the last record ends at the magic"
+    );
+    records
+}
+
+fn entries() -> [TelemetryEntry; 4] {
+    [
+        TelemetryEntry::new("/Mcap/Speed", 1_000, FrcValue::Double(1.5)),
+        TelemetryEntry::new("/Mcap/Pose", 1_500, FrcValue::from_struct(&Pose3d::default())),
+        TelemetryEntry::new("/Mcap/Speed", 2_000, FrcValue::Double(-2.0)),
+        TelemetryEntry::new("/Mcap/Speed", 3_000, FrcValue::Int(4)),
+    ]
+}
+
+fn check(data: &[u8]) {
+    let records = parse(data);
+
+    let schemas: Vec<_> = records
+        .iter()
+        .filter_map(|(_, record)| match record {
+            Record::Schema { id, name, encoding } => Some((*id, name.as_str(), encoding.as_str())),
+            _ => None,
+        })
+        .collect();
+    // every schema is written once with the data and once in the summary
+    let data_schemas = [
+        (1, "frclib.Double", "jsonschema"),
+        (2, "foxglove.PoseInFrame", "jsonschema"),
+        (3, "frclib.Int", "jsonschema"),
+    ];
+    assert_eq!(schemas, [data_schemas, data_schemas].concat());
+
+    let channels: Vec<_> = records
+        .iter()
+        .filter_map(|(_, record)| match record {
+            Record::Channel {
+                id,
+                schema,
+                topic,
+                encoding,
+            } => Some((*id, *schema, topic.as_str(), encoding.as_str())),
+            _ => None,
+        })
+        .collect();
+    let data_channels = [
+        (0, 1, "/Mcap/Speed", "json"),
+        (1, 2, "/Mcap/Pose", "json"),
+        (2, 3, "/Mcap/Speed", "json"),
+    ];
+    assert_eq!(channels, [data_channels, data_channels].concat());
+
+    let messages: Vec<_> = records
+        .iter()
+        .filter_map(|(_, record)| match record {
+            Record::Message {
+                channel,
+                sequence,
+                time,
+                data,
+            } => Some((*channel, *sequence, *time, data)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[0], (0, 0, 1_000_000, &serde_json::json!({ "value": 1.5 })));
+    assert_eq!((messages[1].0, messages[1].1, messages[1].2), (1, 0, 1_500_000));
+    assert_eq!(messages[1].3["pose"]["orientation"]["w"], 1.0);
+    assert_eq!(messages[2], (0, 1, 2_000_000, &serde_json::json!({ "value": -2.0 })));
+    assert_eq!(messages[3], (2, 0, 3_000_000, &serde_json::json!({ "value": 4 })));
+
+    let Some((
+        statistics_offset,
+        Record::Statistics {
+            messages,
+            schemas,
+            channels,
+            start,
+            end,
+            counts,
+        },
+    )) = records
+        .iter()
+        .find(|(_, record)| matches!(record, Record::Statistics { .. }))
+    else {
+        panic!("the summary has statistics");
+    };
+    assert_eq!((*messages, *schemas, *channels), (4, 3, 3));
+    assert_eq!((*start, *end), (1_000_000, 3_000_000));
+    assert_eq!(*counts, [(0, 2), (1, 1), (2, 1)]);
+
+    let Some((_, Record::Footer { summary_start })) = records.last() else {
+        panic!("the file ends with a footer");
+    };
+    let data_end = records
+        .iter()
+        .position(|(_, record)| matches!(record, Record::Other(0x0F)))
+        .expect("the data ends with a data end record");
+    assert_eq!(*summary_start, records[data_end + 1].0);
+    assert!(*summary_start < *statistics_offset);
+    assert!(matches!(records[data_end + 1].1, Record::Schema { id: 1, .. }));
+}
+
+#[test]
+fn writer_produces_a_valid_file() {
+    let mut writer = McapWriter::new(Vec::new()).expect("writing to a vec can't fail");
+    for entry in &entries() {
+        writer.append(entry).expect("writing to a vec can't fail");
+    }
+    check(&writer.finish().expect("writing to a vec can't fail"));
+}
+
+#[test]
+fn wpilog_files_convert_to_mcap() {
+    let mut wpilog = WpiLogWriter::new(Vec::new(), "").expect("writing to a vec can't fail");
+    for entry in &entries() {
+        wpilog.append(entry).expect("writing to a vec can't fail");
+    }
+    let log = WpiLog::parse(&wpilog.into_inner().expect("writing to a vec can't fail")).expect("the log is valid");
+
+    check(&wpilog_to_mcap(&log, Vec::new()).expect("writing to a vec can't fail"));
+}
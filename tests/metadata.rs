@@ -0,0 +1,63 @@
+#![cfg(frc_sim)]
+
+use frclib::telemetry::wpilog::{WpiLog, WpiLogWriter};
+use frclib::telemetry::{self, describe_key, key_metadata, set_key_unit, KeyId, TelemetryEntry, TelemetryNamespace};
+use frclib::units::{angle::Radian, length::Meter};
+use frclib_core::value::FrcValue;
+use serde_json::Value as Json;
+
+#[test]
+fn logging_a_unit_records_it() {
+    telemetry::log("/Units/Arm", Radian::new(1.0));
+    telemetry::log("/Units/Plain", 1.0);
+    TelemetryNamespace::new("/Units/Drive").log("Distance", Meter::new(2.0));
+
+    let unit = |key| key_metadata(key).and_then(|metadata| metadata.unit);
+    assert_eq!(unit("/Units/Arm").as_deref(), Some("radians"));
+    assert_eq!(unit("/Units/Drive/Distance").as_deref(), Some("meters"));
+    assert_eq!(unit("/Units/Plain"), None);
+
+    telemetry::log("/Units/Arm", Meter::new(1.0));
+    assert_eq!(unit("/Units/Arm").as_deref(), Some("meters"));
+}
+
+#[test]
+fn looking_up_metadata_does_not_add_keys() {
+    assert_eq!(key_metadata("/Units/NeverLogged"), None);
+    assert_eq!(KeyId::lookup("/Units/NeverLogged"), None);
+}
+
+/// The metadata a log file ends up with for a key, parsed as JSON.
+fn logged_metadata(log: &WpiLog, key: &str) -> Json {
+    let info = log.entry_info(key).expect("the key was logged");
+    serde_json::from_str(&info.metadata).expect("the metadata is json")
+}
+
+#[test]
+fn metadata_is_written_to_log_files() {
+    set_key_unit("/Metadata/Speed", "meters per second");
+    let source = format!("{}:{}", file!(), line!() + 1);
+    describe_key("/Metadata/Speed", "How fast the robot drives");
+    let first = TelemetryEntry::new("/Metadata/Speed", 1_000, FrcValue::Double(1.5));
+
+    let mut writer = WpiLogWriter::new(Vec::new(), "").expect("writing to a vec can't fail");
+    writer.append(&first).expect("writing to a vec can't fail");
+    let started = WpiLog::parse(&writer.into_inner().expect("writing to a vec can't fail")).expect("the log is valid");
+    let metadata = logged_metadata(&started, "/Metadata/Speed");
+    assert_eq!(metadata["unit"], "meters per second");
+    assert_eq!(metadata["description"], "How fast the robot drives");
+    assert_eq!(metadata["source"], source.as_str());
+
+    let mut writer = WpiLogWriter::new(Vec::new(), "").expect("writing to a vec can't fail");
+    writer.append(&first).expect("writing to a vec can't fail");
+    set_key_unit("/Metadata/Speed", "feet per second");
+    writer
+        .append(&TelemetryEntry::new("/Metadata/Speed", 2_000, FrcValue::Double(2.5)))
+        .expect("writing to a vec can't fail");
+    let updated = WpiLog::parse(&writer.into_inner().expect("writing to a vec can't fail")).expect("the log is valid");
+    // the entry was started with the old unit, only a set metadata record can have changed it
+    let metadata = logged_metadata(&updated, "/Metadata/Speed");
+    assert_eq!(metadata["unit"], "feet per second");
+    assert_eq!(metadata["source"], source.as_str());
+    assert_eq!(updated.values("/Metadata/Speed").count(), 2);
+}
@@ -0,0 +1,102 @@
+#![cfg(frc_sim)]
+
+use frclib::math::geometry::Pose2d;
+use frclib::telemetry::{self, KeyId, TelemetryEntry, TelemetryNamespace, TelemetrySink, TELEMETRY_SINKS};
+use frclib::telemetry_tree;
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+/// Everything ever flushed, tests only look at their own keys since they flush concurrently.
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+/// Flushes the data log and returns every value flushed under a key starting with `prefix`, in order.
+fn flushed(prefix: &str) -> Vec<(&'static str, FrcValue)> {
+    telemetry::flush_datalog();
+    FLUSHED
+        .lock()
+        .iter()
+        .filter(|entry| entry.key.starts_with(prefix))
+        .map(|entry| (entry.key, entry.value.clone()))
+        .collect()
+}
+
+struct Module {
+    velocity: f64,
+    angle: Option<f64>,
+}
+
+telemetry_tree!(Module { velocity, angle });
+
+struct Drive {
+    pose: Pose2d,
+    modules: [Module; 2],
+    target: Option<Pose2d>,
+}
+
+telemetry_tree!(Drive { pose, modules, target });
+
+#[test]
+fn children_and_indices_build_paths() {
+    let drive = TelemetryNamespace::new("/Paths/Drive/");
+    assert_eq!(drive.path(), "/Paths/Drive");
+    assert_eq!(drive.child("Module").path(), "/Paths/Drive/Module");
+    assert_eq!(drive.index(3).path(), "/Paths/Drive/3");
+    assert_eq!(drive.index(3).child("Velocity").path(), "/Paths/Drive/3/Velocity");
+    assert_eq!(drive.key("Speed"), "/Paths/Drive/Speed");
+}
+
+#[test]
+fn cached_children_keep_their_key() {
+    let drive = TelemetryNamespace::new("/Cached/Drive");
+    let first = drive.child("Module");
+    let second = drive.child("Module");
+    assert!(std::ptr::eq(first.path(), second.path()));
+    assert_eq!(KeyId::of(first.path()), KeyId::of(second.path()));
+    assert_eq!(KeyId::of(drive.index(1).path()), KeyId::intern("/Cached/Drive/1"));
+    assert_eq!(drive.clone().child("Module").path(), first.path());
+}
+
+#[test]
+fn trees_expand_into_keys() {
+    let namespace = TelemetryNamespace::new("/Tree");
+    let drive = Drive {
+        pose: Pose2d::default(),
+        modules: [
+            Module {
+                velocity: 1.0,
+                angle: Some(0.5),
+            },
+            Module {
+                velocity: 2.0,
+                angle: None,
+            },
+        ],
+        target: None,
+    };
+    namespace.log_tree("Drive", &drive);
+
+    assert_eq!(
+        flushed("/Tree/"),
+        [
+            ("/Tree/Drive/pose", FrcValue::from_struct(&Pose2d::default())),
+            ("/Tree/Drive/modules/0/velocity", FrcValue::Double(1.0)),
+            ("/Tree/Drive/modules/0/angle", FrcValue::Double(0.5)),
+            ("/Tree/Drive/modules/1/velocity", FrcValue::Double(2.0)),
+        ]
+    );
+}
+
+#[test]
+fn string_keys_log_under_their_interned_name() {
+    let key = format!("/Strings/{}", 7);
+    telemetry::log(&key, 1.0);
+    telemetry::log(key.clone(), 2.0);
+
+    let values = flushed("/Strings/");
+    assert_eq!(values.len(), 2);
+    assert!(values.iter().all(|(logged, _)| std::ptr::eq(*logged, KeyId::intern(&key).name())));
+    assert_eq!(values[1].1, FrcValue::Double(2.0));
+}
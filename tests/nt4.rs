@@ -0,0 +1,175 @@
+#![cfg(frc_sim)]
+
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use frclib::telemetry::nt4::Nt4Server;
+use frclib::telemetry::{describe_key, set_key_unit, TelemetryEntry};
+use frclib_core::value::FrcValue;
+use rmpv::Value;
+use serde_json::Value as Json;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+fn connect(server: &Nt4Server) -> Client {
+    let mut request = format!("ws://{}/nt/test", server.local_addr())
+        .into_client_request()
+        .expect("the url is valid");
+    let _ = request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "v4.1.networktables.first.wpi.edu".parse().expect("the header is valid"),
+    );
+    let (client, response) = tungstenite::connect(request).expect("the server accepts clients");
+    assert_eq!(
+        response.headers().get("Sec-WebSocket-Protocol").map(|value| value.as_bytes()),
+        Some(b"v4.1.networktables.first.wpi.edu".as_slice())
+    );
+    client
+}
+
+/// Reads frames until a binary one, returning the text messages before it and the values in it.
+fn read_until_values(client: &mut Client) -> (Vec<Json>, Vec<Vec<Value>>) {
+    let mut text = Vec::new();
+    loop {
+        match client.read().expect("the server stays connected") {
+            Message::Text(frame) => {
+                let Json::Array(messages) = serde_json::from_str(&frame).expect("text frames are json") else {
+                    panic!("text frames are arrays");
+                };
+                text.extend(messages);
+            }
+            Message::Binary(frame) => {
+                let mut data = frame.as_slice();
+                let mut values = Vec::new();
+                while !data.is_empty() {
+                    let Value::Array(value) = rmpv::decode::read_value(&mut data).expect("binary frames are msgpack")
+                    else {
+                        panic!("values are arrays");
+                    };
+                    values.push(value);
+                }
+                return (text, values);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn announced_id(messages: &[Json], name: &str) -> Option<i64> {
+    messages
+        .iter()
+        .find(|message| message["method"] == "announce" && message["params"]["name"] == name)
+        .and_then(|message| message["params"]["id"].as_i64())
+}
+
+fn encode(values: &[[Value; 4]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for value in values {
+        rmpv::encode::write_value(&mut data, &Value::Array(value.to_vec())).expect("writing to a vec can't fail");
+    }
+    data
+}
+
+#[test]
+fn subscribe_and_publish_round_trip() {
+    let server = Nt4Server::bind("127.0.0.1:0").expect("any local port is free");
+    server.publish(&[TelemetryEntry::new("/Robot/Speed", 5, FrcValue::Double(2.0))]);
+
+    let mut client = connect(&server);
+    client
+        .send(Message::Text(
+            r#"[
+                {"method":"subscribe","params":{"topics":["/Robot"],"subuid":1,"options":{"prefix":true}}},
+                {"method":"publish","params":{"name":"/Dashboard/Target","pubuid":7,"type":"int","properties":{}}}
+            ]"#
+            .into(),
+        ))
+        .expect("the server stays connected");
+
+    let (announcements, values) = read_until_values(&mut client);
+    let speed = announced_id(&announcements, "/Robot/Speed").expect("subscribed topics are announced");
+    assert!(announced_id(&announcements, "/Dashboard/Target").is_some());
+    assert_eq!(values, [vec![speed.into(), 5.into(), 1.into(), 2.0.into()]]);
+
+    server.publish(&[
+        TelemetryEntry::new("/Robot/Speed", 9, FrcValue::Double(3.0)),
+        TelemetryEntry::new("/Other", 9, FrcValue::Int(1)),
+    ]);
+    let (announcements, values) = read_until_values(&mut client);
+    assert!(announcements.is_empty());
+    assert_eq!(values, [vec![speed.into(), 9.into(), 1.into(), 3.0.into()]]);
+
+    let published = encode(&[
+        [7.into(), 0.into(), 2.into(), 42.into()],
+        [(-1).into(), 0.into(), 2.into(), 1234.into()],
+    ]);
+    client.send(Message::Binary(published)).expect("the server stays connected");
+    let (_, values) = read_until_values(&mut client);
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0][0], Value::from(-1));
+    assert_eq!(values[0][3], Value::from(1234));
+    assert_eq!(server.latest_value("/Dashboard/Target"), Some(FrcValue::Int(42)));
+    assert_eq!(server.client_count(), 1);
+}
+
+#[test]
+fn metadata_is_published_as_properties() {
+    let server = Nt4Server::bind("127.0.0.1:0").expect("any local port is free");
+    set_key_unit("/Described/Height", "meters");
+    describe_key("/Described/Height", "How high the elevator is");
+    server.publish(&[TelemetryEntry::new("/Described/Height", 5, FrcValue::Double(0.5))]);
+
+    let mut client = connect(&server);
+    client
+        .send(Message::Text(
+            r#"[{"method":"subscribe","params":{"topics":["/Described"],"subuid":1,"options":{"prefix":true}}}]"#
+                .into(),
+        ))
+        .expect("the server stays connected");
+    let (announcements, _) = read_until_values(&mut client);
+    let announce = announcements
+        .iter()
+        .find(|message| message["method"] == "announce")
+        .expect("the topic is announced");
+    assert_eq!(announce["params"]["properties"]["unit"], "meters");
+    assert_eq!(announce["params"]["properties"]["description"], "How high the elevator is");
+
+    set_key_unit("/Described/Height", "inches");
+    server.publish(&[TelemetryEntry::new("/Described/Height", 9, FrcValue::Double(20.0))]);
+    let (messages, _) = read_until_values(&mut client);
+    let update = messages
+        .iter()
+        .find(|message| message["method"] == "properties")
+        .expect("changed metadata updates the properties");
+    assert_eq!(update["params"]["name"], "/Described/Height");
+    assert_eq!(update["params"]["update"]["unit"], "inches");
+    assert_eq!(update["params"]["update"]["description"], "How high the elevator is");
+}
+
+#[test]
+fn clients_that_fall_behind_are_disconnected() {
+    let server = Nt4Server::bind("127.0.0.1:0").expect("any local port is free");
+    let mut client = connect(&server);
+    client
+        .send(Message::Text(
+            r#"[{"method":"subscribe","params":{"topics":["/Robot"],"subuid":1,"options":{"prefix":true}}}]"#.into(),
+        ))
+        .expect("the server stays connected");
+    let start = Instant::now();
+    while server.client_count() == 0 {
+        assert!(start.elapsed() < Duration::from_secs(5), "the client never connected");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    // the client never reads, so its socket and then its queue fill up
+    let payload = FrcValue::Raw(vec![0; 64 * 1024].into());
+    let start = Instant::now();
+    while server.client_count() > 0 {
+        assert!(start.elapsed() < Duration::from_secs(30), "the client was never disconnected");
+        server.publish(&[TelemetryEntry::new("/Robot/Blob", 1, payload.clone())]);
+    }
+    drop(client);
+}
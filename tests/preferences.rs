@@ -0,0 +1,63 @@
+#![cfg(frc_sim)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use frclib::preferences::{Preferences, PreferencesError};
+
+/// An empty directory for one test's preferences file.
+fn preferences_file(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("frclib_preferences_{test}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory.join("preferences.json")
+}
+
+#[test]
+fn values_persist_between_opens() {
+    let path = preferences_file("persist");
+    let mut preferences = Preferences::open(&path).expect("a missing file gives empty preferences");
+    assert_eq!(preferences.get_or("offset", 1.5), 1.5);
+    preferences.set("offset", 2.25).expect("the file can be written");
+    preferences.set("name", "arm").expect("the file can be written");
+
+    let reopened = Preferences::open(&path).expect("the file was saved");
+    assert_eq!(reopened.get::<f64>("offset"), Some(2.25));
+    assert_eq!(reopened.get::<String>("name").as_deref(), Some("arm"));
+    assert_eq!(reopened.get::<bool>("name"), None);
+}
+
+#[test]
+fn corrupt_file_falls_back_to_the_backup() {
+    let path = preferences_file("corrupt");
+    let mut preferences = Preferences::open(&path).expect("a missing file gives empty preferences");
+    preferences.set("offset", 2.25).expect("the file can be written");
+    preferences.set("offset", 3.0).expect("the file can be written");
+    fs::write(&path, "{\"version\":1,\"val").expect("the file can be written");
+
+    let recovered = Preferences::open(&path).expect("the backup is read");
+    assert_eq!(recovered.get::<f64>("offset"), Some(2.25));
+}
+
+#[test]
+fn missing_file_falls_back_to_the_backup() {
+    let path = preferences_file("missing");
+    let mut preferences = Preferences::open(&path).expect("a missing file gives empty preferences");
+    preferences.set("enabled", true).expect("the file can be written");
+    preferences.set("enabled", false).expect("the file can be written");
+    fs::remove_file(&path).expect("the file was saved");
+
+    let recovered = Preferences::open(&path).expect("the backup is read");
+    assert_eq!(recovered.get::<bool>("enabled"), Some(true));
+}
+
+#[test]
+fn newer_versions_are_not_loaded() {
+    let path = preferences_file("version");
+    fs::create_dir_all(path.parent().expect("the file is in a directory")).expect("the directory can be created");
+    fs::write(&path, r#"{"version":9,"values":{}}"#).expect("the file can be written");
+
+    assert!(matches!(
+        Preferences::open(&path),
+        Err(PreferencesError::UnsupportedVersion(9))
+    ));
+}
@@ -0,0 +1,120 @@
+#![cfg(frc_sim)]
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use frclib::driver_station::DriverStation;
+use frclib::replay::{input, EntryReplaySource, CYCLE_KEY, REPLAY_PREFIX};
+use frclib::robots::{lockstep::Lockstep, RobotMode, UserRobot};
+use frclib::runtime;
+use frclib::telemetry::wpilog::{WpiLog, WpiLogWriter};
+use frclib::telemetry::{self, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+/// The mode the robot saw on every cycle.
+static MODES: Mutex<Vec<RobotMode>> = Mutex::new(Vec::new());
+/// Cleared while replaying, the sensor can only be read on the original run.
+static LIVE: AtomicBool = AtomicBool::new(true);
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+#[derive(Default)]
+struct ReplayRobot {
+    position: f64,
+}
+
+impl UserRobot for ReplayRobot {
+    fn construct() -> Self {
+        Self::default()
+    }
+
+    fn robot_init(&mut self) {}
+
+    fn robot_periodic(&mut self, _: Duration) {
+        MODES.lock().push(DriverStation::robot_mode());
+        let reading = input("/Sensor/Position", || {
+            if LIVE.load(Ordering::Relaxed) {
+                self.position += 1.5;
+                self.position
+            } else {
+                -1.0
+            }
+        });
+        telemetry::log("/Output/Doubled", reading * 2.0);
+    }
+
+    fn robot_autonomous_periodic(&mut self, _: Duration) {
+        telemetry::log("/Output/Auto", true);
+    }
+
+    fn robot_teleop_periodic(&mut self, _: Duration) {
+        telemetry::log("/Output/Teleop", true);
+    }
+}
+
+/// Every value flushed under a key with its timestamp.
+fn values(entries: &[TelemetryEntry], key: &str) -> Vec<(u64, FrcValue)> {
+    entries
+        .iter()
+        .filter(|entry| entry.key == key)
+        .map(|entry| (entry.timestamp, entry.value.clone()))
+        .collect()
+}
+
+#[test]
+fn replaying_a_log_file_reproduces_its_outputs() {
+    let path = std::env::temp_dir().join(format!("frclib_replay_{}.wpilog", std::process::id()));
+    {
+        let mut sim = Lockstep::<ReplayRobot>::new();
+        sim.step_n(2);
+        sim.set_mode(RobotMode::Autonomous);
+        sim.step_n(3);
+        sim.set_mode(RobotMode::Teleop);
+        sim.step_n(3);
+        sim.set_mode(RobotMode::Disabled);
+        sim.step_n(2);
+    }
+    let recorded = std::mem::take(&mut *FLUSHED.lock());
+    let recorded_modes = std::mem::take(&mut *MODES.lock());
+    assert_eq!(values(&recorded, CYCLE_KEY).len(), 10);
+    assert_eq!(recorded_modes.len(), 10);
+    assert!(recorded_modes[2..5].iter().all(|mode| *mode == RobotMode::Autonomous));
+    {
+        let file = File::create(&path).expect("the temp directory is writable");
+        let mut writer = WpiLogWriter::new(BufWriter::new(file), "").expect("the log file can be written");
+        for entry in &recorded {
+            writer.append(entry).expect("the log file can be written");
+        }
+        writer.flush().expect("the log file can be written");
+    }
+    let log = WpiLog::open(&path).expect("the log file is valid");
+    let _ = std::fs::remove_file(&path);
+
+    LIVE.store(false, Ordering::Relaxed);
+    runtime::replay::<ReplayRobot>(EntryReplaySource::new(log.entries().iter().cloned()));
+    let replayed = std::mem::take(&mut *FLUSHED.lock());
+
+    assert_eq!(*MODES.lock(), recorded_modes);
+    for key in [
+        CYCLE_KEY,
+        "/Sensor/Position",
+        "/Output/Doubled",
+        "/Output/Auto",
+        "/Output/Teleop",
+        "/DriverStation/Enabled",
+        "/DriverStation/Autonomous",
+    ] {
+        let original = values(&recorded, key);
+        assert!(!original.is_empty(), "{key} was recorded");
+        assert_eq!(
+            values(&replayed, &format!("{REPLAY_PREFIX}{key}")),
+            original,
+            "{key} was replayed"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+#![cfg(frc_sim)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use frclib::driver_station::DriverStation;
+use frclib::replay::{input, EntryReplaySource, ReplaySource, CYCLE_KEY, REPLAY_PREFIX};
+use frclib::robots::{lockstep::Lockstep, RobotMode, UserRobot};
+use frclib::runtime;
+use frclib::telemetry::{self, LogPolicy, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+use frclib_core::hal::rt::station_interface::EnabledState;
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+/// The mode the robot saw on every cycle.
+static MODES: Mutex<Vec<RobotMode>> = Mutex::new(Vec::new());
+/// Cleared while replaying, the sensor can only be read on the original run.
+static LIVE: AtomicBool = AtomicBool::new(true);
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+#[derive(Default)]
+struct SwitchRobot {
+    cycles: u32,
+}
+
+impl UserRobot for SwitchRobot {
+    fn construct() -> Self {
+        Self::default()
+    }
+
+    fn robot_init(&mut self) {}
+
+    fn robot_periodic(&mut self, _: Duration) {
+        MODES.lock().push(DriverStation::robot_mode());
+        // only changes every 4 cycles, so an on change policy would drop most readings
+        let reading = input("/Sensor/Switch", || {
+            if LIVE.load(Ordering::Relaxed) {
+                f64::from(self.cycles / 4)
+            } else {
+                -1.0
+            }
+        });
+        self.cycles += 1;
+        telemetry::log("/Output/Switch", reading);
+    }
+
+    fn robot_teleop_periodic(&mut self, _: Duration) {}
+}
+
+/// Every value flushed under a key with its timestamp.
+fn values(entries: &[TelemetryEntry], key: &str) -> Vec<(u64, FrcValue)> {
+    entries
+        .iter()
+        .filter(|entry| entry.key == key)
+        .map(|entry| (entry.timestamp, entry.value.clone()))
+        .collect()
+}
+
+#[test]
+fn logs_recorded_with_policies_replay() {
+    telemetry::set_log_policy(
+        "/",
+        LogPolicy {
+            keepalive: Duration::ZERO,
+            ..LogPolicy::ON_CHANGE
+        },
+    );
+    {
+        let mut sim = Lockstep::<SwitchRobot>::new();
+        sim.step_n(2);
+        sim.set_mode(RobotMode::Autonomous);
+        sim.step_n(3);
+        sim.set_mode(RobotMode::Teleop);
+        sim.step_n(3);
+        sim.set_mode(RobotMode::Disabled);
+        sim.step_n(2);
+    }
+    let recorded = std::mem::take(&mut *FLUSHED.lock());
+    let recorded_modes = std::mem::take(&mut *MODES.lock());
+    // the keys replay needs are never filtered
+    assert_eq!(values(&recorded, CYCLE_KEY).len(), 10);
+    assert_eq!(values(&recorded, "/Sensor/Switch").len(), 10);
+    // the driver station is also refreshed once before the first cycle
+    assert_eq!(values(&recorded, "/DriverStation/Enabled").len(), 11);
+    assert_eq!(values(&recorded, "/DriverStation/Autonomous").len(), 11);
+    let outputs = values(&recorded, "/Output/Switch");
+    assert_eq!(
+        outputs.iter().map(|(_, value)| value.clone()).collect::<Vec<_>>(),
+        [FrcValue::Double(0.0), FrcValue::Double(1.0), FrcValue::Double(2.0)]
+    );
+
+    LIVE.store(false, Ordering::Relaxed);
+    runtime::replay::<SwitchRobot>(EntryReplaySource::new(recorded.iter().cloned()));
+    let replayed = std::mem::take(&mut *FLUSHED.lock());
+
+    assert_eq!(*MODES.lock(), recorded_modes);
+    assert_eq!(values(&replayed, &format!("{REPLAY_PREFIX}/Output/Switch")), outputs);
+    assert_eq!(
+        values(&replayed, &format!("{REPLAY_PREFIX}/Sensor/Switch")),
+        values(&recorded, "/Sensor/Switch")
+    );
+}
+
+#[test]
+fn values_carry_over_to_later_frames() {
+    let entries = [
+        TelemetryEntry::new(CYCLE_KEY, 0, FrcValue::Int(0)),
+        TelemetryEntry::new("/DriverStation/Enabled", 0, FrcValue::Boolean(true)),
+        TelemetryEntry::new("/Sensor/Carried", 0, FrcValue::Double(1.0)),
+        TelemetryEntry::new(CYCLE_KEY, 20_000, FrcValue::Int(1)),
+        TelemetryEntry::new(CYCLE_KEY, 40_000, FrcValue::Int(2)),
+        TelemetryEntry::new("/Sensor/Carried", 40_000, FrcValue::Double(2.0)),
+    ];
+    let mut source = EntryReplaySource::new(entries);
+
+    let carried = |source: &mut EntryReplaySource<_>| {
+        let frame = source.next_frame().expect("every cycle is a frame");
+        assert!(matches!(frame.station.enabled_state, EnabledState::Enabled));
+        frame.values.get("/Sensor/Carried").cloned()
+    };
+    assert_eq!(carried(&mut source), Some(FrcValue::Double(1.0)));
+    assert_eq!(carried(&mut source), Some(FrcValue::Double(1.0)));
+    assert_eq!(carried(&mut source), Some(FrcValue::Double(2.0)));
+    assert!(source.next_frame().is_none());
+}
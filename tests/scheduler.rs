@@ -0,0 +1,207 @@
+#![cfg(frc_sim)]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use frclib::commands::{
+    Command, CommandExt, CommandScheduler, InterruptionBehavior, Requirements, RunCommand,
+    Subsystem, SubsystemId,
+};
+use frclib::driver_station::sim;
+use frclib::robots::RobotMode;
+
+/// Every event of every probe in a test, in order.
+type Events = Rc<RefCell<Vec<String>>>;
+
+/// A command that records its lifecycle and finishes when told to.
+struct Probe {
+    name: &'static str,
+    events: Events,
+    requirements: Requirements,
+    interruption_behavior: InterruptionBehavior,
+    finished: Rc<Cell<bool>>,
+}
+
+impl Probe {
+    fn new(name: &'static str, events: &Events, requirements: &[SubsystemId]) -> Self {
+        Self {
+            name,
+            events: events.clone(),
+            requirements: requirements.iter().copied().collect(),
+            interruption_behavior: InterruptionBehavior::CancelSelf,
+            finished: Rc::new(Cell::new(false)),
+        }
+    }
+}
+
+impl Command for Probe {
+    fn initialize(&mut self) {
+        self.events.borrow_mut().push(format!("{} init", self.name));
+    }
+    fn execute(&mut self) {
+        self.events.borrow_mut().push(format!("{} execute", self.name));
+    }
+    fn end(&mut self, interrupted: bool) {
+        self.events.borrow_mut().push(format!("{} end {interrupted}", self.name));
+    }
+    fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+    fn requirements(&self) -> Requirements {
+        self.requirements.clone()
+    }
+    fn interruption_behavior(&self) -> InterruptionBehavior {
+        self.interruption_behavior
+    }
+}
+
+struct Arm;
+impl Subsystem for Arm {}
+
+/// Commands only get scheduled while enabled, every test in this file runs in teleop.
+fn enable() {
+    sim::set_robot_mode(RobotMode::Teleop);
+}
+
+fn take(events: &Events) -> Vec<String> {
+    std::mem::take(&mut *events.borrow_mut())
+}
+
+#[test]
+fn shared_requirement_interrupts_the_running_command() {
+    enable();
+    let events = Events::default();
+    let arm = CommandScheduler::register_subsystem(Arm).id();
+
+    let first = Probe::new("first", &events, &[arm]).schedule();
+    let second = Probe::new("second", &events, &[arm]).schedule();
+    assert!(!first.is_scheduled());
+    assert!(second.is_scheduled());
+    assert_eq!(CommandScheduler::requiring(arm), Some(second));
+    assert_eq!(take(&events), ["first init", "first end true", "second init"]);
+}
+
+#[test]
+fn cancel_incoming_rejects_the_new_command() {
+    enable();
+    let events = Events::default();
+    let arm = CommandScheduler::register_subsystem(Arm).id();
+
+    let mut first = Probe::new("first", &events, &[arm]);
+    first.interruption_behavior = InterruptionBehavior::CancelIncoming;
+    let first = first.schedule();
+    let second = Probe::new("second", &events, &[arm]).schedule();
+    assert!(first.is_scheduled());
+    assert!(!second.is_scheduled());
+    assert_eq!(take(&events), ["first init"]);
+}
+
+#[test]
+fn cancel_ends_interrupted_and_finishing_does_not() {
+    enable();
+    let events = Events::default();
+
+    let cancelled = Probe::new("cancelled", &events, &[]).schedule();
+    CommandScheduler::run(true);
+    cancelled.cancel();
+    assert!(!cancelled.is_scheduled());
+
+    let finishing = Probe::new("finishing", &events, &[]);
+    let finished = finishing.finished.clone();
+    let finishing = finishing.schedule();
+    finished.set(true);
+    CommandScheduler::run(true);
+    assert!(!finishing.is_scheduled());
+    assert_eq!(
+        take(&events),
+        [
+            "cancelled init",
+            "cancelled execute",
+            "cancelled end true",
+            "finishing init",
+            "finishing execute",
+            "finishing end false"
+        ]
+    );
+}
+
+#[test]
+fn schedules_and_cancels_in_the_run_loop_are_deferred() {
+    enable();
+    let events = Events::default();
+    let incoming = Probe::new("incoming", &events, &[]).into_handle();
+    let outgoing = Probe::new("outgoing", &events, &[]).schedule();
+
+    let seen = events.clone();
+    let (to_schedule, to_cancel) = (incoming.clone(), outgoing.clone());
+    let _driver = RunCommand::new(
+        move || {
+            to_schedule.schedule();
+            to_cancel.cancel();
+            seen.borrow_mut().push(format!(
+                "incoming {} outgoing {}",
+                to_schedule.is_scheduled(),
+                to_cancel.is_scheduled()
+            ));
+        },
+        &[],
+    )
+    .schedule();
+    let _ = take(&events);
+
+    CommandScheduler::run(true);
+    assert!(incoming.is_scheduled());
+    assert!(!outgoing.is_scheduled());
+    assert_eq!(
+        take(&events),
+        [
+            "outgoing execute",
+            "incoming false outgoing true",
+            "outgoing end true",
+            "incoming init"
+        ]
+    );
+}
+
+#[test]
+fn disabling_ends_commands_that_do_not_run_disabled() {
+    enable();
+    let events = Events::default();
+    let command = Probe::new("command", &events, &[]).schedule();
+
+    CommandScheduler::run(false);
+    assert!(!command.is_scheduled());
+    assert_eq!(take(&events), ["command init", "command end true"]);
+}
+
+#[test]
+fn default_command_is_rescheduled_when_its_subsystem_is_free() {
+    enable();
+    let events = Events::default();
+    let arm = CommandScheduler::register_subsystem(Arm);
+    arm.set_default_command(Probe::new("default", &events, &[arm.id()]));
+
+    CommandScheduler::run(true);
+    let default = CommandScheduler::requiring(arm.id()).expect("the default command is scheduled");
+
+    let user = Probe::new("user", &events, &[arm.id()]);
+    let finished = user.finished.clone();
+    let user = user.schedule();
+    assert!(!default.is_scheduled());
+    finished.set(true);
+    CommandScheduler::run(true);
+    assert!(!user.is_scheduled());
+    assert_eq!(CommandScheduler::requiring(arm.id()), Some(default.clone()));
+    assert!(default.is_scheduled());
+    assert_eq!(
+        take(&events),
+        [
+            "default init",
+            "default end true",
+            "user init",
+            "user execute",
+            "user end false",
+            "default init"
+        ]
+    );
+}
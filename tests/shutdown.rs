@@ -0,0 +1,63 @@
+#![cfg(frc_sim)]
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use frclib::replay::{EntryReplaySource, CYCLE_KEY};
+use frclib::robots::UserRobot;
+use frclib::runtime::{self, request_shutdown, shutdown_requested};
+use frclib::telemetry::{self, TelemetryEntry, TelemetryFinalizer, TelemetrySink, TELEMETRY_FINALIZERS, TELEMETRY_SINKS};
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+static CYCLES: AtomicU32 = AtomicU32::new(0);
+/// Set by the finalizer if the final flush already had the robot's last values.
+static FINALIZED_AFTER_FLUSH: AtomicBool = AtomicBool::new(false);
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| FLUSHED.lock().extend_from_slice(entries);
+
+#[linkme::distributed_slice(TELEMETRY_FINALIZERS)]
+static FINALIZER: TelemetryFinalizer = || {
+    let flushed = FLUSHED.lock().iter().any(|entry| entry.key.ends_with("/Shutdown/Ended"));
+    FINALIZED_AFTER_FLUSH.store(flushed, Ordering::Release);
+};
+
+struct ShutdownRobot;
+
+impl UserRobot for ShutdownRobot {
+    fn construct() -> Self {
+        Self
+    }
+
+    fn robot_init(&mut self) {}
+
+    fn robot_periodic(&mut self, _: Duration) {
+        if CYCLES.fetch_add(1, Ordering::AcqRel) + 1 == 3 {
+            request_shutdown();
+        }
+    }
+
+    fn robot_teleop_periodic(&mut self, _: Duration) {}
+
+    fn robot_end(&mut self) {
+        telemetry::log("/Shutdown/Ended", true);
+    }
+}
+
+#[test]
+fn shutdown_ends_the_loop_then_flushes_and_finalizes() {
+    let cycles = (0..10).map(|cycle| {
+        TelemetryEntry::new(
+            CYCLE_KEY,
+            cycle * 20_000,
+            FrcValue::Int(i64::try_from(cycle).expect("the cycle is small")),
+        )
+    });
+    runtime::replay::<ShutdownRobot>(EntryReplaySource::new(cycles));
+
+    assert!(shutdown_requested());
+    assert_eq!(CYCLES.load(Ordering::Acquire), 3);
+    assert!(FINALIZED_AFTER_FLUSH.load(Ordering::Acquire));
+}
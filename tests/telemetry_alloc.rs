@@ -0,0 +1,82 @@
+#![cfg(frc_sim)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use frclib::math::geometry::Pose2d;
+use frclib::telemetry::{self, TelemetryEntry, TelemetrySink, TELEMETRY_SINKS};
+use frclib_core::value::FrcValue;
+use parking_lot::Mutex;
+
+/// Counts allocations made while `COUNTING` is set.
+struct CountingAllocator;
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.load(Ordering::Relaxed) {
+            let _ = ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static FLUSHED: Mutex<Vec<TelemetryEntry>> = Mutex::new(Vec::new());
+
+#[linkme::distributed_slice(TELEMETRY_SINKS)]
+static CAPTURE: TelemetrySink = |entries| {
+    if !COUNTING.load(Ordering::Relaxed) {
+        *FLUSHED.lock() = entries.to_vec();
+    }
+};
+
+fn log_cycle(pose: &Pose2d) {
+    telemetry::log("/Alloc/Double", 1.5);
+    telemetry::log_str("/Alloc/String", "ready");
+    telemetry::log_slice("/Alloc/Doubles", &[1.0, 2.0, 3.0]);
+    telemetry::log_slice("/Alloc/Booleans", &[true, false]);
+    telemetry::log_struct("/Alloc/Pose", pose);
+    telemetry::log_struct_array("/Alloc/Poses", &[*pose, *pose]);
+}
+
+#[test]
+fn steady_state_logging_does_not_allocate() {
+    telemetry::wpilog::set_log_directory(std::env::temp_dir().join("frclib_alloc_test"));
+    let pose = Pose2d::default();
+    for _ in 0..3 {
+        log_cycle(&pose);
+        telemetry::flush_datalog();
+    }
+
+    COUNTING.store(true, Ordering::Relaxed);
+    log_cycle(&pose);
+    telemetry::flush_datalog();
+    COUNTING.store(false, Ordering::Relaxed);
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 0);
+
+    log_cycle(&pose);
+    telemetry::flush_datalog();
+    let flushed = FLUSHED.lock();
+    assert_eq!(flushed[1].key, "/Alloc/String");
+    assert_eq!(flushed[1].value, FrcValue::String("ready".into()));
+    assert_eq!(flushed[2].value, FrcValue::DoubleArray([1.0, 2.0, 3.0].into()));
+    assert_eq!(flushed[3].value, FrcValue::BooleanArray([true, false].into()));
+    let struct_data = |value: &FrcValue| match value {
+        FrcValue::Struct(bytes) | FrcValue::StructArray(bytes) => (bytes.desc.type_str, bytes.count, bytes.data.clone()),
+        _ => panic!("{value:?} is not a struct"),
+    };
+    assert_eq!(struct_data(&flushed[4].value), struct_data(&FrcValue::from_struct(&pose)));
+    assert_eq!(
+        struct_data(&flushed[5].value),
+        struct_data(&FrcValue::from_struct_array(&[pose, pose]))
+    );
+}
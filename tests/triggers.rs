@@ -0,0 +1,162 @@
+#![cfg(frc_sim)]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use frclib::commands::{CommandExt, CommandHandle, RunCommand, Trigger};
+use frclib::driver_station::sim;
+use frclib::event::EventLoop;
+use frclib::math::filter::debouncer::{DebounceType, Debouncer};
+use frclib::robots::RobotMode;
+use frclib::units::time::Millisecond;
+
+/// A condition the test flips, and a trigger on it polled by its own loop.
+fn trigger() -> (Rc<Cell<bool>>, EventLoop, Trigger) {
+    sim::set_robot_mode(RobotMode::Teleop);
+    let condition = Rc::new(Cell::new(false));
+    let event_loop = EventLoop::default();
+    let value = condition.clone();
+    let trigger = Trigger::with_loop(event_loop.clone(), move || value.get());
+    (condition, event_loop, trigger)
+}
+
+fn command() -> CommandHandle {
+    RunCommand::new(|| {}, &[]).into_handle()
+}
+
+#[test]
+fn on_true_schedules_on_the_rising_edge() {
+    let (condition, event_loop, trigger) = trigger();
+    let command = command();
+    let _ = trigger.on_true(command.clone());
+
+    event_loop.poll();
+    assert!(!command.is_scheduled());
+    condition.set(true);
+    event_loop.poll();
+    assert!(command.is_scheduled());
+
+    command.cancel();
+    event_loop.poll();
+    assert!(!command.is_scheduled());
+    condition.set(false);
+    event_loop.poll();
+    assert!(!command.is_scheduled());
+    condition.set(true);
+    event_loop.poll();
+    assert!(command.is_scheduled());
+    command.cancel();
+}
+
+#[test]
+fn on_false_schedules_on_the_falling_edge() {
+    let (condition, event_loop, trigger) = trigger();
+    let command = command();
+    let _ = trigger.on_false(command.clone());
+
+    condition.set(true);
+    event_loop.poll();
+    assert!(!command.is_scheduled());
+    condition.set(false);
+    event_loop.poll();
+    assert!(command.is_scheduled());
+    command.cancel();
+}
+
+#[test]
+fn while_true_cancels_on_the_falling_edge() {
+    let (condition, event_loop, trigger) = trigger();
+    let command = command();
+    let _ = trigger.while_true(command.clone());
+
+    condition.set(true);
+    event_loop.poll();
+    assert!(command.is_scheduled());
+    event_loop.poll();
+    assert!(command.is_scheduled());
+    condition.set(false);
+    event_loop.poll();
+    assert!(!command.is_scheduled());
+}
+
+#[test]
+fn toggle_on_true_flips_on_every_rising_edge() {
+    let (condition, event_loop, trigger) = trigger();
+    let command = command();
+    let _ = trigger.toggle_on_true(command.clone());
+
+    for scheduled in [true, false, true] {
+        condition.set(true);
+        event_loop.poll();
+        condition.set(false);
+        event_loop.poll();
+        assert_eq!(command.is_scheduled(), scheduled);
+    }
+    command.cancel();
+}
+
+#[test]
+fn conditions_compose() {
+    let (a, _, trigger_a) = trigger();
+    let (b, _, trigger_b) = trigger();
+    let and = trigger_a.and(trigger_b.clone());
+    let or = trigger_a.or(trigger_b);
+    let not = trigger_a.negate();
+
+    for (value_a, value_b) in [(false, false), (true, false), (false, true), (true, true)] {
+        a.set(value_a);
+        b.set(value_b);
+        assert_eq!(and.get(), value_a && value_b);
+        assert_eq!(or.get(), value_a || value_b);
+        assert_eq!(not.get(), !value_a);
+    }
+}
+
+#[test]
+fn debounced_rising_edges_wait_for_the_debounce_time() {
+    let (condition, _, trigger) = trigger();
+    let debounced = trigger.debounce(Millisecond::new(50.0), DebounceType::Rising);
+
+    condition.set(true);
+    assert!(!debounced.get());
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(debounced.get());
+    condition.set(false);
+    assert!(!debounced.get());
+
+    // a pulse shorter than the debounce time never gets through
+    condition.set(true);
+    assert!(!debounced.get());
+    condition.set(false);
+    assert!(!debounced.get());
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(!debounced.get());
+}
+
+#[test]
+fn debounced_falling_edges_wait_for_the_debounce_time() {
+    let (condition, _, trigger) = trigger();
+    condition.set(true);
+    let debounced = trigger.debounce(Millisecond::new(50.0), DebounceType::Falling);
+
+    assert!(debounced.get());
+    condition.set(false);
+    assert!(debounced.get());
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(!debounced.get());
+    condition.set(true);
+    assert!(debounced.get());
+}
+
+#[test]
+fn debouncing_both_edges_holds_each_new_value() {
+    let mut debouncer = Debouncer::new(Millisecond::new(50.0), DebounceType::Both, false);
+
+    assert!(!debouncer.calculate(true));
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(debouncer.calculate(true));
+    assert!(debouncer.calculate(false));
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(!debouncer.calculate(false));
+}
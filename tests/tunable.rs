@@ -0,0 +1,78 @@
+#![cfg(frc_sim)]
+
+use frclib::telemetry::{set_tunable, Tunable};
+
+#[cfg(frc_dev)]
+mod dev {
+    use std::time::{Duration, Instant};
+
+    use frclib::telemetry::nt4;
+    use rmpv::Value;
+    use tungstenite::client::IntoClientRequest;
+    use tungstenite::Message;
+
+    use super::{set_tunable, Tunable};
+
+    #[test]
+    fn edits_are_picked_up_once() {
+        let mut tunable = Tunable::new("/Tunable/Dev/Gain", 0.5);
+        assert!(!tunable.has_changed());
+        assert_eq!(tunable.get(), 0.5);
+
+        set_tunable("/Tunable/Dev/Gain", 1.5);
+        assert!(tunable.has_changed());
+        assert!(!tunable.has_changed());
+        assert_eq!(tunable.get(), 1.5);
+        assert_eq!(*tunable.default_value(), 0.5);
+    }
+
+    #[test]
+    fn edits_of_the_wrong_type_are_ignored() {
+        let mut tunable = Tunable::new("/Tunable/Dev/Typed", 2.0);
+        set_tunable("/Tunable/Dev/Typed", "fast");
+        assert!(!tunable.has_changed());
+        assert_eq!(tunable.get(), 2.0);
+    }
+
+    #[test]
+    fn dashboards_edit_through_networktables() {
+        let address = nt4::start_server("127.0.0.1:0").expect("any local port is free");
+        let mut tunable = Tunable::new("/Tunable/Dev/Remote", 1.0);
+
+        let mut request = format!("ws://{address}/nt/tunable")
+            .into_client_request()
+            .expect("the url is valid");
+        let _ = request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            "v4.1.networktables.first.wpi.edu".parse().expect("the header is valid"),
+        );
+        let (mut client, _) = tungstenite::connect(request).expect("the server accepts clients");
+        client
+            .send(Message::Text(
+                r#"[{"method":"publish","params":{"name":"/Tunable/Dev/Remote","pubuid":3,"type":"double","properties":{}}}]"#
+                    .into(),
+            ))
+            .expect("the server stays connected");
+        let mut edit = Vec::new();
+        rmpv::encode::write_value(&mut edit, &Value::Array(vec![3.into(), 0.into(), 1.into(), 4.0.into()]))
+            .expect("writing to a vec can't fail");
+        client.send(Message::Binary(edit)).expect("the server stays connected");
+
+        let start = Instant::now();
+        while !tunable.has_changed() {
+            assert!(start.elapsed() < Duration::from_secs(5), "the edit never arrived");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(tunable.get(), 4.0);
+        nt4::stop_server();
+    }
+}
+
+#[cfg(not(frc_dev))]
+#[test]
+fn tunables_are_locked_to_their_default() {
+    let mut tunable = Tunable::new("/Tunable/Locked", 0.5);
+    set_tunable("/Tunable/Locked", 1.5);
+    assert!(!tunable.has_changed());
+    assert_eq!(tunable.get(), 0.5);
+}
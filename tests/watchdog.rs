@@ -0,0 +1,60 @@
+#![cfg(frc_sim)]
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use frclib::watchdog::Watchdog;
+
+#[test]
+fn epochs_add_up_by_name() {
+    let mut watchdog = Watchdog::new(Duration::from_secs(1));
+    sleep(Duration::from_millis(5));
+    watchdog.add_epoch("first");
+    sleep(Duration::from_millis(5));
+    watchdog.add_epoch("second");
+    sleep(Duration::from_millis(5));
+    watchdog.add_epoch("first");
+
+    let epochs = watchdog.epochs();
+    assert_eq!(epochs.len(), 2);
+    assert_eq!((epochs[0].0, epochs[1].0), ("first", "second"));
+    assert!(epochs[0].1 >= Duration::from_millis(10));
+    assert!(epochs[1].1 >= Duration::from_millis(5));
+    let total: Duration = epochs.iter().map(|(_, time)| *time).sum();
+    assert!(total <= watchdog.elapsed());
+}
+
+#[test]
+fn only_iterations_past_the_timeout_overrun() {
+    let mut watchdog = Watchdog::new(Duration::from_millis(30));
+    assert!(!watchdog.check());
+    assert_eq!(watchdog.overruns(), 0);
+
+    sleep(Duration::from_millis(40));
+    assert!(watchdog.is_expired());
+    assert!(watchdog.check());
+    assert!(watchdog.check());
+    assert_eq!(watchdog.overruns(), 2);
+}
+
+#[test]
+fn zero_timeout_never_overruns() {
+    let mut watchdog = Watchdog::new(Duration::ZERO);
+    sleep(Duration::from_millis(5));
+    assert!(!watchdog.check());
+    assert_eq!(watchdog.overruns(), 0);
+}
+
+#[test]
+fn reset_starts_a_new_iteration() {
+    let mut watchdog = Watchdog::new(Duration::from_millis(30));
+    watchdog.add_epoch("work");
+    sleep(Duration::from_millis(40));
+    assert!(watchdog.check());
+
+    watchdog.reset();
+    assert!(watchdog.epochs().is_empty());
+    assert!(watchdog.elapsed() < Duration::from_millis(30));
+    assert!(!watchdog.check());
+    assert_eq!(watchdog.overruns(), 1);
+}
@@ -0,0 +1,99 @@
+#![cfg(frc_sim)]
+
+use std::time::Duration;
+
+use frclib::math::geometry::{Pose2d, Pose3d, Rotation2d};
+use frclib::telemetry::wpilog::{WpiLog, WpiLogWriter};
+use frclib::telemetry::TelemetryEntry;
+use frclib::units::{angle::Radian, length::Meter};
+use frclib_core::value::FrcValue;
+
+fn write_log(entries: &[TelemetryEntry]) -> Vec<u8> {
+    let mut writer = WpiLogWriter::new(Vec::new(), "round trip").expect("writing to a vec can't fail");
+    for entry in entries {
+        writer.append(entry).expect("writing to a vec can't fail");
+    }
+    writer.into_inner().expect("writing to a vec can't fail")
+}
+
+#[test]
+fn values_round_trip() {
+    let entries = [
+        TelemetryEntry::new("/RoundTrip/Double", 1_000, FrcValue::Double(1.5)),
+        TelemetryEntry::new("/RoundTrip/Boolean", 2_000, FrcValue::Boolean(true)),
+        TelemetryEntry::new("/RoundTrip/String", 3_000, FrcValue::String("ready".into())),
+        TelemetryEntry::new("/RoundTrip/Ints", 4_000, FrcValue::IntArray([1, -2, 3].into())),
+        TelemetryEntry::new(
+            "/RoundTrip/Strings",
+            5_000,
+            FrcValue::StringArray(["a".into(), "bc".into()].into()),
+        ),
+        TelemetryEntry::new("/RoundTrip/Double", 6_000, FrcValue::Double(-2.0)),
+    ];
+    let log = WpiLog::parse(&write_log(&entries)).expect("the log is valid");
+
+    assert_eq!(log.extra_header(), "round trip");
+    assert_eq!(log.entries(), entries);
+    assert_eq!(log.entry_info("/RoundTrip/Ints").map(|info| info.type_str.as_str()), Some("int64[]"));
+    let doubles: Vec<_> = log.values("/RoundTrip/Double").map(|entry| entry.value.clone()).collect();
+    assert_eq!(doubles, [FrcValue::Double(1.5), FrcValue::Double(-2.0)]);
+    let range = Duration::from_millis(2)..Duration::from_millis(4);
+    let keys: Vec<_> = log.time_range(range).map(|entry| entry.key).collect();
+    assert_eq!(keys, ["/RoundTrip/Boolean", "/RoundTrip/String"]);
+}
+
+#[test]
+fn type_changes_start_a_new_entry() {
+    let entries = [
+        TelemetryEntry::new("/TypeChange/Value", 1_000, FrcValue::Double(1.5)),
+        TelemetryEntry::new("/TypeChange/Value", 2_000, FrcValue::Int(3)),
+    ];
+    let log = WpiLog::parse(&write_log(&entries)).expect("the log is valid");
+
+    assert_eq!(log.entries(), entries);
+    assert_eq!(log.entry_info("/TypeChange/Value").map(|info| info.type_str.as_str()), Some("int64"));
+}
+
+#[test]
+fn structs_round_trip_with_their_schemas() {
+    let pose = Pose2d::new_xy_rot(Meter::new(1.0), Meter::new(-2.5), Rotation2d::new_angle(Radian::new(0.5)));
+    let poses = [Pose3d::default(), Pose3d::default()];
+    let entries = [
+        TelemetryEntry::new("/Structs/Pose", 10_000, FrcValue::from_struct(&pose)),
+        TelemetryEntry::new("/Structs/Poses", 20_000, FrcValue::from_struct_array(&poses)),
+    ];
+    let log = WpiLog::parse(&write_log(&entries)).expect("the log is valid");
+
+    assert_eq!(
+        log.struct_values::<Pose2d>("/Structs/Pose").collect::<Vec<_>>(),
+        [(Duration::from_millis(10), pose)]
+    );
+    assert_eq!(
+        log.struct_array_values::<Pose3d>("/Structs/Poses").collect::<Vec<_>>(),
+        [(Duration::from_millis(20), poses.to_vec())]
+    );
+    assert_eq!(
+        log.entry_info("/Structs/Poses").map(|info| info.type_str.as_str()),
+        Some("struct:Pose3d[]")
+    );
+    for schema in ["Pose2d", "Rotation2d", "Translation2d", "Pose3d", "Rotation3d", "Translation3d", "Quaternion"] {
+        let key = format!("/.schema/struct:{schema}");
+        let info = log.entry_info(&key).unwrap_or_else(|| panic!("{key} is missing"));
+        assert_eq!(info.type_str, "structschema");
+    }
+    let schemas: Vec<_> = log.values("/.schema/struct:Pose2d").map(|entry| entry.value.clone()).collect();
+    assert_eq!(
+        schemas,
+        [FrcValue::Raw(b"Translation2d translation;Rotation2d rotation;".as_slice().into())]
+    );
+}
+
+#[test]
+fn partial_trailing_record_is_ignored() {
+    let entries = [TelemetryEntry::new("/Partial/Value", 1_000, FrcValue::Int(7))];
+    let mut data = write_log(&entries);
+    data.extend_from_slice(&[0x10, 5]);
+    let log = WpiLog::parse(&data).expect("a partial record at the end is not an error");
+
+    assert_eq!(log.entries(), entries);
+}